@@ -1,4 +1,5 @@
 use common::get_pathfind;
+use sc2pathlib::path_find::batch::PathQuery;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 mod common;
 
@@ -6,7 +7,58 @@ fn bench_astar_automaton(c: &mut Criterion) {
     let path_find = get_pathfind("tests/AutomatonLE.txt");
     c.bench_function("find_path_automaton", |b| {
          b.iter(|| {
-              path_find.find_path_basic((32, 51), (150, 118), Some(0));
+              path_find.find_path_basic((32, 51), (150, 118), Some(0)).unwrap();
+          })
+     });
+}
+
+fn bench_octile_automaton(c: &mut Criterion) {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    c.bench_function("find_path_octile_automaton", |b| {
+         b.iter(|| {
+              path_find.find_path_basic((32, 51), (150, 118), Some(1)).unwrap();
+          })
+     });
+}
+
+fn bench_jps_automaton(c: &mut Criterion) {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    c.bench_function("find_path_jps_automaton", |b| {
+         b.iter(|| {
+              path_find.find_path_basic((32, 51), (150, 118), Some(3)).unwrap();
+          })
+     });
+}
+
+fn bench_batch_automaton(c: &mut Criterion) {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let queries: Vec<PathQuery> = (0..16).map(|_| PathQuery::new((32, 51), (150, 118))).collect();
+    c.bench_function("find_paths_batch_automaton_16", |b| {
+         b.iter(|| {
+              path_find.find_paths_batch_rust(&queries).unwrap();
+          })
+     });
+}
+
+fn bench_influence_automaton(c: &mut Criterion) {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    let positions: Vec<(usize, usize)> = (0..20).map(|i| (40 + i * 5, 60 + i * 3)).collect();
+    c.bench_function("influence_automaton", |b| {
+         b.iter(|| {
+              path_find.normalize_influence(black_box(20));
+              path_find.add_influence(positions.clone(), 100.0, 7.0);
+              path_find.reset_void();
+          })
+     });
+}
+
+fn bench_walk_influence_automaton(c: &mut Criterion) {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    let positions: Vec<(usize, usize)> = (0..10).map(|i| (40 + i * 10, 60 + i * 6)).collect();
+    c.bench_function("walk_influence_automaton", |b| {
+         b.iter(|| {
+              path_find.add_walk_influence(positions.clone(), 100.0, 10.0).unwrap();
+              path_find.reset_void();
           })
      });
 }
@@ -16,7 +68,7 @@ fn bench_astar_4x4(c: &mut Criterion) {
     // Run bench
     c.bench_function("find_path_4x4", |b| {
          b.iter(|| {
-              path_find.find_path_basic((0, 0), (0, 2), Some(0));
+              path_find.find_path_basic((0, 0), (0, 2), Some(0)).unwrap();
           })
      });
 }
@@ -26,10 +78,18 @@ fn bench_astar_10x10(c: &mut Criterion) {
     // Run bench
     c.bench_function("find_path_10x10", |b| {
          b.iter(|| {
-              path_find.find_path_basic((0, 0), (8, 9), Some(0));
+              path_find.find_path_basic((0, 0), (8, 9), Some(0)).unwrap();
           })
      });
 }
 
-criterion_group!(benches, bench_astar_automaton, bench_astar_4x4, bench_astar_10x10);
+criterion_group!(benches,
+                 bench_astar_automaton,
+                 bench_octile_automaton,
+                 bench_jps_automaton,
+                 bench_batch_automaton,
+                 bench_influence_automaton,
+                 bench_walk_influence_automaton,
+                 bench_astar_4x4,
+                 bench_astar_10x10);
 criterion_main!(benches);
@@ -1,10 +1,12 @@
 # noinspection PyUnresolvedReferences
 from .sc2pathlib import VisionUnit
-from .sc2pathlib import Map
+from .sc2pathlib import DistanceField, Map, PathStats, Ramp
 import numpy as np
 from typing import List, Optional, Tuple, Union
 from .choke import Choke
-from .mappings import MapsType, MapType, VisionStatus
+from .path_planner import PathPlanner
+from .path_finder import to_influence_cap
+from .mappings import Footprint, MapsType, MapType, PathStatus, VisionStatus
 from sc2.unit import Unit
 from sc2.position import Point2
 
@@ -21,7 +23,9 @@ class Sc2Map:
 
         self._overlord_spots: Optional[List[Tuple[float, float]]] = None
         self._chokes: Optional[List[Choke]] = None
-        self.heuristic_accuracy = 1  # Octile distance / set to 2 for optimal accuracy but less performance
+        # Octile distance / set to 2 for optimal accuracy but less performance
+        # set to 3 for jump point search, which is fast when influence is not used
+        self.heuristic_accuracy = 1
 
         self.height_map = height_map
         self._map = Map(
@@ -55,6 +59,13 @@ class Sc2Map:
         self._chokes = self._map.chokes
         return self._chokes
 
+    @property
+    def ramps(self) -> List[Ramp]:
+        """
+        Ramps between two height levels, zones of the ramps are set by `calculate_zones`.
+        """
+        return self._map.ramps
+
 
     def reset(self):
         self._map.reset()
@@ -104,11 +115,17 @@ class Sc2Map:
     def enable_reaper_map(self, enabled: bool):
         self._map.influence_reaper_map = enabled
 
-    def create_block(self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]):
+    def create_block(
+        self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]
+    ) -> Union[int, List[int]]:
+        """
+        Makes the area unpathable.
+        :return: Id of the block, or a list of ids when multiple centers are given, for remove_block_by_id
+        """
         if isinstance(center, list):
-            self._map.create_blocks(center, size)
+            return self._map.create_blocks(center, size)
         else:
-            self._map.create_block(center, size)
+            return self._map.create_block(center, size)
 
     def remove_block(self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]):
         if isinstance(center, list):
@@ -116,6 +133,72 @@ class Sc2Map:
         else:
             self._map.remove_block(center, size)
 
+    def create_footprint(self, center: Tuple[float, float], footprint: Footprint) -> int:
+        """
+        Blocks the pathing footprint of a building or resource, such as a town hall with its cut corners.
+        A lowered supply depot doesn't block pathing.
+        Odd sized footprints are centered on the middle of a cell (x.5) and even sized ones on a cell corner (x.0),
+        same as unit positions of buildings in the game.
+        :return: Id of the block for remove_block_by_id
+        """
+        return self._map.create_footprint(center, footprint)
+
+    def plan_wall(
+        self,
+        passage: Union[Choke, Ramp, Tuple[float, float]],
+        inside: Tuple[float, float],
+        footprints: List[Footprint],
+        gap: int = 0,
+    ) -> Optional[List[Tuple[Footprint, Tuple[float, float]]]]:
+        """
+        Plans a wall of buildings over a choke or a ramp, buildings are placed on the placement grid
+        and the wall is checked with ground pathing. Not all footprints need to be used.
+        :param passage: Choke, ramp or the center of the passage, ramps are walled at the top
+        :param inside: Point on the side that the wall protects, such as the main base
+        :param footprints: Buildings available for the wall
+        :param gap: 0 closes the passage, otherwise the wall leaves an opening this many cells wide
+        :return: Footprints and centers of the buildings, None if no wall was found
+        """
+        if hasattr(passage, "top_center"):
+            passage = passage.top_center
+        elif not isinstance(passage, tuple):
+            (x1, y1), (x2, y2) = passage.main_line
+            passage = ((x1 + x2) / 2, (y1 + y2) / 2)
+
+        wall = self._map.plan_wall(passage, inside, [int(f) for f in footprints], gap)
+        if wall is None:
+            return None
+        return [(Footprint(footprint), center) for footprint, center in wall]
+
+    def find_placement(
+        self,
+        near: Tuple[float, float],
+        footprint: Footprint,
+        max_distance: float = 10,
+        pylons: Optional[List[Tuple[float, float]]] = None,
+        creep: Optional[np.ndarray] = None,
+    ) -> Optional[Tuple[float, float]]:
+        """
+        Finds the closest position where the building fits on the placement grid.
+        Placement under blocks and footprints is taken into account, ramps and mineral lines
+        of minerals and geysers created with create_footprint are avoided.
+        :param pylons: When given, the building needs to be powered by one of the pylons
+        :param creep: When given, the building needs to be fully on creep
+        :return: Center of the building, None if there is no legal position within max_distance
+        """
+        if creep is not None:
+            creep = np.swapaxes(creep, 0, 1)
+        return self._map.find_placement(near, footprint, max_distance, pylons, creep)
+
+    def remove_block_by_id(self, block_id: int) -> bool:
+        """
+        Removes a block created with create_block.
+        Cells under it get back the terrain and influence they had before,
+        cells that other blocks also cover stay unpathable until those are removed too.
+        :return: False if there is no block with the id
+        """
+        return self._map.remove_block_by_id(block_id)
+
     def add_walk_influence(self, points: List["Point2"], influence: float, range: float = 3):
         """
         Influence applied fades up until the specified range
@@ -170,8 +253,13 @@ class Sc2Map:
         large: bool = False,
         influence: bool = False,
         window: Optional[Tuple[Tuple[float, float], Tuple[float, float]]] = None,
-        distance_from_target: Optional[float] = None
-    ) -> Tuple[List[Tuple[int, int]], float]:
+        distance_from_target: Optional[float] = None,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None,
+        return_corrections: bool = False
+    ) -> Union[Tuple[List[Tuple[int, int]], float],
+               Tuple[List[Tuple[int, int]], float, Tuple[Tuple[int, int], Tuple[int, int]]]]:
         """
         Finds a path ignoring influence.
 
@@ -182,16 +270,279 @@ class Sc2Map:
         :param window: Restrict path finding to the given window
         :param distance_from_target: Short circuit when finding a point closer
             than the given distance from the target
-        :return: Tuple of points and total distance.
+        :param radius: Only path through cells with enough room for a unit with the given radius
+        :param max_influence: Cells with influence above this are never entered
+        :param soft_cap: With max_influence, cells above it can be entered but cost soft_cap times more
+        :param return_corrections: Also return the start and end that were used after moving them
+            to the closest cells the unit can use
+        :return: Tuple of points and total distance, and corrected start and end if requested.
+        """
+
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        find = self._map.find_path_corrected if return_corrections else self._map.find_path
+        return find(
+            map_type, start, end, large, influence, self.heuristic_accuracy, window, distance_from_target,
+            possible_radius=radius, possible_influence_cap=influence_cap
+        )
+
+    def closest_pathable(
+        self, map_type: MapType, point: Tuple[float, float], max_distance: Optional[float] = None
+    ) -> Optional[Tuple[int, int]]:
         """
+        Finds the closest pathable cell, this is how find_path corrects unpathable start and end positions.
+        :param point: Position to correct, returned as is when it's pathable
+        :param max_distance: How far to search, defaults to the auto correct distance
+        :return: Closest pathable cell or None if there is none within max_distance
+        """
+        return self._map.closest_pathable(map_type, point, max_distance)
 
-        return self._map.find_path(map_type, start, end, large, influence, self.heuristic_accuracy, window, distance_from_target)
+    def set_auto_correct_distance(self, distance: float) -> None:
+        """
+        Sets how far unpathable start and end positions are moved to the closest pathable cell, 4 by default.
+        """
+        self._map.set_auto_correct_distance(distance)
 
     def find_path_influence(
         self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float], large: bool = False
     ) -> Tuple[List[Tuple[int, int]], float]:
         return self.find_path(map_type, start, end, large, influence=True)
 
+    def find_path_budget(
+        self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float],
+        large: bool = False,
+        influence: bool = False,
+        max_expansions: Optional[int] = None,
+        max_time: Optional[float] = None
+    ) -> Tuple[List[Tuple[int, int]], float, PathStatus]:
+        """
+        Same as find_path, but gives up when the budget runs out.
+        When the end was not reached, the path leads to the position closest to the end that was found.
+
+        :param max_expansions: Stop after expanding this many positions
+        :param max_time: Stop after this many milliseconds
+        :return: Tuple of points, total distance and PathStatus.
+        """
+        path, distance, status = self._map.find_path_budget(
+            map_type, start, end, large, influence, (max_expansions, max_time)
+        )
+        return path, distance, PathStatus(status)
+
+    def find_alternative_paths(
+        self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float],
+        k: int = 3,
+        overlap_penalty: float = 1.0,
+        influence: bool = False
+    ) -> List[Tuple[List[Tuple[int, int]], float, float]]:
+        """
+        Finds up to k different routes from start to end, the first one is the shortest path.
+        Every following path avoids the cells used by the earlier paths and their neighbours,
+        moving into such a cell costs overlap_penalty times more for each earlier path that used it.
+
+        :param k: Maximum number of paths, fewer are returned if the routes can't be told apart
+        :param overlap_penalty: Extra cost multiplier for sharing cells with earlier paths
+        :param influence: Account for enemy influence
+        :return: List of tuples of points, path length and share of the points that are also in the best path.
+        """
+        return self._map.find_alternative_paths(map_type, start, end, k, overlap_penalty, influence)
+
+    def find_nearest_goal(
+        self, map_type: MapType, start: Tuple[float, float], goals: List[Tuple[float, float]],
+        weights: Optional[List[float]] = None,
+        large: bool = False,
+        influence: bool = False
+    ) -> Optional[Tuple[int, List[Tuple[int, int]], float]]:
+        """
+        Finds the goal that can be reached first by walking with a single search.
+
+        :param goals: Candidate goals, for example enemy units or expansions
+        :param weights: Extra distance for each goal, the goal with the lowest distance plus weight is selected
+        :param large: Unit is large and requires path to have width of 2 to pass
+        :param influence: Account for enemy influence
+        :return: Tuple of the index of the goal, points and distance, None if no goal can be reached.
+        """
+        return self._map.find_nearest_goal(map_type, start, goals, weights, large, influence)
+
+    def find_paths_batch(
+        self, map_type: MapType, queries: List[tuple]
+    ) -> List[Tuple[List[Tuple[int, int]], float]]:
+        """
+        Finds multiple paths in parallel.
+
+        :param queries: List of tuples in format
+            (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap),
+            everything after end is optional. influence_cap is max_influence or (max_influence, soft_cap).
+        :return: List of path and distance tuples in the same order as the queries.
+        """
+        return self._map.find_paths_batch(map_type, queries)
+
+    def find_cooperative_paths(
+        self, map_type: MapType, agents: List[tuple],
+        max_expansions: Optional[int] = None,
+        max_time: Optional[float] = None
+    ) -> List[List[Tuple[Tuple[int, int], float]]]:
+        """
+        Plans paths for multiple units so that they don't walk through the same narrow cells at the same time.
+        Units are planned in the given order, later units path around the earlier ones or wait for them to pass.
+        Units stay at their goal after reaching it.
+
+        :param agents: List of tuples in format (start, goal, size, speed), size and speed are optional.
+            size is the width of the unit in cells, units larger than 1 use large unit path finding.
+            speed is in cells per second and defaults to 1.
+        :param max_expansions: Stop planning a unit after expanding this many positions, defaults to 200 000
+        :param max_time: Stop planning after this many milliseconds
+        :return: List of timed paths in the same order as the agents, empty when there is no path or the budget ran out.
+            Every cell has the time in seconds when the unit reaches it, repeated cells mean waiting.
+        """
+        budget = None if max_expansions is None and max_time is None else (max_expansions, max_time)
+        return self._map.find_cooperative_paths(map_type, agents, budget)
+
+    def find_path_smooth(
+        self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float],
+        large: bool = False,
+        influence: bool = False,
+        window: Optional[Tuple[Tuple[float, float], Tuple[float, float]]] = None,
+        distance_from_target: Optional[float] = None,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None
+    ) -> Tuple[List[Tuple[float, float]], float]:
+        """
+        Same as find_path, but returns only the waypoints where the path needs to turn.
+        Straight segments between waypoints never cross unpathable cells,
+        cells without room for the radius or, without soft_cap, cells above max_influence.
+
+        :return: Tuple of waypoints and euclidean length of the smoothed path.
+        """
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        return self._map.find_path_smooth(
+            map_type, start, end, large, influence, self.heuristic_accuracy, window, distance_from_target,
+            possible_radius=radius, possible_influence_cap=influence_cap
+        )
+
+    def find_path_hierarchical(
+        self, start: Tuple[float, float], end: Tuple[float, float], large: bool = False, influence: bool = False
+    ) -> Tuple[List[Tuple[int, int]], float]:
+        """
+        Finds a ground path by first routing through the chokes of the map and then
+        solving the shorter paths between the chokes.
+
+        :param start: Start position in float tuple
+        :param end: End position in float tuple
+        :param large: Unit is large and requires path to have width of 2 to pass
+        :param influence: Account for enemy influence
+        :return: Tuple of points and total distance.
+        """
+        return self._map.find_path_hierarchical(start, end, large, influence, self.heuristic_accuracy)
+
+    def find_path_abstract(self, start: Tuple[float, float], end: Tuple[float, float]) -> Tuple[List[Choke], float]:
+        """
+        Finds the chokes a ground unit needs to pass through to reach the end position.
+
+        :return: Tuple of chokes in order and estimated distance, distance is -1 if no route was found.
+        """
+        indices, distance = self._map.find_path_abstract(start, end)
+        chokes = self.chokes
+        return [chokes[i] for i in indices], distance
+
+    def raycast(
+        self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float], include_cells: bool = False
+    ) -> Tuple[bool, Optional[Tuple[int, int]], List[Tuple[int, int]]]:
+        """
+        Checks whether a unit can walk straight from start to end, using every cell the segment touches.
+
+        :param include_cells: Also return all traversed cells up to the blocking cell
+        :return: Tuple of whether the segment is clear, the first unpathable cell or None and the traversed cells.
+        """
+        return self._map.raycast(map_type, start, end, include_cells)
+
+    def vision_raycast(
+        self, start: Tuple[float, float], end: Tuple[float, float], include_cells: bool = False
+    ) -> Tuple[bool, Optional[Tuple[int, int]], List[Tuple[int, int]]]:
+        """
+        Checks whether ground vision from start reaches end.
+        Terrain higher than what can be seen from the start height blocks the vision.
+
+        :param include_cells: Also return all traversed cells up to the blocking cell
+        :return: Tuple of whether the vision is clear, the first blocking cell or None and the traversed cells.
+        """
+        return self._map.vision_raycast(start, end, include_cells)
+
+    def evaluate_path(self, map_type: MapType, path: List[Tuple[int, int]], threshold: float) -> PathStats:
+        """
+        Calculates danger along the path.
+        Returns total_influence and max_influence above the normal influence, max_influence_index,
+        cells_above_threshold and first_danger_index of cells with influence more than threshold above normal,
+        and seen_cells that are seen or detected in the current vision map.
+        """
+        return self._map.evaluate_path(map_type, path, threshold)
+
+    def distance_field(
+        self,
+        map_type: MapType,
+        goals: List[Tuple[float, float]],
+        large: bool = False,
+        influence: bool = False,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None,
+    ) -> DistanceField:
+        """
+        Calculates walking distances to the closest goal for the whole map.
+        Use this when multiple units are moving to the same location,
+        each unit can query `next_step` or `direction` from the returned field.
+        With radius set, only cells with enough room for a unit with the given radius are used.
+        With max_influence set, cells above it are not used, or cost soft_cap times more if it is set.
+        """
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        return self._map.distance_field(map_type, goals, large, influence, radius, influence_cap)
+
+    def flee_path(
+        self,
+        map_type: MapType,
+        start: Tuple[float, float],
+        threats: List[Tuple[float, float]],
+        distance: float,
+    ) -> Tuple[List[Tuple[int, int]], float]:
+        """
+        Finds a path away from the threats that takes terrain and current influence into account.
+        Instead of running straight away from the threats, the path prefers open space over dead ends,
+        even if that means running past a threat.
+        :param start: position of the fleeing unit
+        :param threats: positions of the threats to flee from
+        :param distance: how far to flee, the path is shorter when there is nowhere safer to go
+        :return: Tuple of path and its walking distance
+        """
+        return self._map.flee_path(map_type, start, threats, distance)
+
+    def component_id(self, map_type: MapType, position: Tuple[float, float]) -> Optional[int]:
+        """
+        Id of the connected area the position belongs to, None if the position is not pathable.
+        Ids are kept up to date when blocks are created or removed, but they may change with every change.
+        """
+        return self._map.component_id(map_type, position)
+
+    def same_component(self, map_type: MapType, start: Tuple[float, float], end: Tuple[float, float]) -> bool:
+        """
+        Checks whether a unit at start can walk to end at all without doing a path search,
+        for example to an island base or past a ramp blocked by buildings.
+        """
+        return self._map.same_component(map_type, start, end)
+
+    def create_planner(
+        self,
+        map_type: MapType,
+        start: Tuple[float, float],
+        goal: Tuple[float, float],
+        large: bool = False,
+        influence: bool = False,
+    ) -> PathPlanner:
+        """
+        Creates a path that can be repaired with planner.update(changed_cells) after the map changes,
+        which is much cheaper than finding the whole path again.
+        """
+        planner = self._map.create_planner(map_type, start, goal, large, influence)
+        return PathPlanner(planner, lambda p, cells: self._map.update_planner(map_type, p, cells))
+
     def safest_spot(
         self, map_type: MapType, destination_center: Tuple[float, float], walk_distance: float
     ) -> Tuple[Tuple[int, int], float]:
@@ -15,4 +15,19 @@ class MapType(IntEnum):
 class VisionStatus(IntEnum):
     NotSeen = 0
     Seen = 1
-    Detected = 2
\ No newline at end of file
+    Detected = 2
+
+class PathStatus(IntEnum):
+    Complete = 0
+    Partial = 1
+    Unreachable = 2
+
+class Footprint(IntEnum):
+    TownHall = 0
+    Building3x3 = 1
+    Building2x2 = 2
+    Building1x1 = 3
+    SupplyDepotLowered = 4
+    MineralField = 5
+    VespeneGeyser = 6
+    Rocks2x2 = 7
\ No newline at end of file
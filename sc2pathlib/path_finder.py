@@ -1,4 +1,6 @@
-from .sc2pathlib import PathFind
+from .sc2pathlib import DistanceField, PathFind, PathStats
+from .path_planner import PathPlanner
+from .mappings import PathStatus
 
 import numpy as np
 from typing import Union, List, Tuple, Optional
@@ -8,6 +10,14 @@ def to_float2(original: Tuple[int, int]) -> Tuple[float, float]:
     return (original[0] + 0.5, original[1] + 0.5)
 
 
+def to_influence_cap(
+    max_influence: Optional[float], soft_cap: Optional[float]
+) -> Optional[Union[float, Tuple[float, float]]]:
+    if max_influence is None or soft_cap is None:
+        return max_influence
+    return (max_influence, soft_cap)
+
+
 class PathFinder:
     def __init__(self, maze: Union[List[List[int]], np.array]):
         """
@@ -15,7 +25,7 @@ class PathFinder:
         Initialization should be done with array consisting values of 0 and 1.
         """
         self._path_find = PathFind(maze)
-        self.heuristic_accuracy = 1  # Octile distance
+        self.heuristic_accuracy = 1  # Octile distance, 3 for jump point search without influence
 
     def normalize_influence(self, value: int):
         """
@@ -46,6 +56,17 @@ class PathFinder:
         """
         return self._path_find.map
 
+    @property
+    def auto_correct_distance(self) -> float:
+        """
+        :return: How far unpathable start and end positions are moved to the closest pathable cell, 4 by default
+        """
+        return self._path_find.auto_correct_distance
+
+    @auto_correct_distance.setter
+    def auto_correct_distance(self, value: float):
+        self._path_find.auto_correct_distance = value
+
     def reset(self):
         """
         Reset the pathfind map data to it's original state
@@ -55,11 +76,17 @@ class PathFinder:
     def set_map(self, data: List[List[int]]):
         self._path_find.map = data
 
-    def create_block(self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]):
+    def create_block(
+        self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]
+    ) -> Union[int, List[int]]:
+        """
+        Makes the area unpathable.
+        :return: Id of the block, or a list of ids when multiple centers are given, for remove_block_by_id
+        """
         if isinstance(center, list):
-            self._path_find.create_blocks(center, size)
+            return self._path_find.create_blocks(center, size)
         else:
-            self._path_find.create_block(center, size)
+            return self._path_find.create_block(center, size)
 
     def remove_block(self, center: Union[Tuple[float, float], List[Tuple[float, float]]], size: Tuple[int, int]):
         if isinstance(center, list):
@@ -67,13 +94,27 @@ class PathFinder:
         else:
             self._path_find.remove_block(center, size)
 
+    def remove_block_by_id(self, block_id: int) -> bool:
+        """
+        Removes a block created with create_block.
+        Cells under it get back the terrain and influence they had before,
+        cells that other blocks also cover stay unpathable until those are removed too.
+        :return: False if there is no block with the id
+        """
+        return self._path_find.remove_block_by_id(block_id)
+
     def find_path(
         self, start: Tuple[float, float], end: Tuple[float, float],
         large: bool = False,
         influence: bool = False,
         window: Optional[Tuple[Tuple[float, float], Tuple[float, float]]] = None,
-        distance_from_target: Optional[float] = None
-    ) -> Tuple[List[Tuple[int, int]], float]:
+        distance_from_target: Optional[float] = None,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None,
+        return_corrections: bool = False
+    ) -> Union[Tuple[List[Tuple[int, int]], float],
+               Tuple[List[Tuple[int, int]], float, Tuple[Tuple[int, int], Tuple[int, int]]]]:
         """
         Finds a path ignoring influence.
 
@@ -84,20 +125,256 @@ class PathFinder:
         :param window: Restrict path finding to the given window
         :param distance_from_target: Short circuit when finding a point closer
             than the given distance from the target
-        :return: Tuple of points and total distance.
+        :param radius: Only path through cells with enough room for a unit with the given radius
+        :param max_influence: Cells with influence above this are never entered
+        :param soft_cap: With max_influence, cells above it can be entered but cost soft_cap times more
+        :param return_corrections: Also return the start and end that were used after moving them
+            to the closest cells the unit can use
+        :return: Tuple of points and total distance, and corrected start and end if requested.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        end_int = (int(round(end[0])), int(round(end[1])))
+        if window is not None:
+            window = ((int(round(window[0][0])), int(round(window[0][1]))),
+                      (int(round(window[1][0])), int(round(window[1][1]))))
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        find = self._path_find.find_path_corrected if return_corrections else self._path_find.find_path
+        return find(
+            start_int, end_int, large, influence, self.heuristic_accuracy, window, distance_from_target,
+            possible_radius=radius, possible_influence_cap=influence_cap
+        )
+
+    def closest_pathable(
+        self, point: Tuple[float, float], max_distance: Optional[float] = None
+    ) -> Optional[Tuple[int, int]]:
+        """
+        Finds the closest pathable cell, this is how find_path corrects unpathable start and end positions.
+        :param point: Position to correct, returned as is when it's pathable
+        :param max_distance: How far to search, defaults to auto_correct_distance
+        :return: Closest pathable cell or None if there is none within max_distance
+        """
+        point_int = (int(round(point[0])), int(round(point[1])))
+        return self._path_find.closest_pathable(point_int, max_distance)
+
+    def find_path_budget(
+        self, start: Tuple[float, float], end: Tuple[float, float],
+        large: bool = False,
+        influence: bool = False,
+        max_expansions: Optional[int] = None,
+        max_time: Optional[float] = None
+    ) -> Tuple[List[Tuple[int, int]], float, PathStatus]:
+        """
+        Same as find_path, but gives up when the budget runs out.
+        When the end was not reached, the path leads to the position closest to the end that was found.
+
+        :param max_expansions: Stop after expanding this many positions
+        :param max_time: Stop after this many milliseconds
+        :return: Tuple of points, total distance and PathStatus.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        end_int = (int(round(end[0])), int(round(end[1])))
+        path, distance, status = self._path_find.find_path_budget(
+            start_int, end_int, large, influence, (max_expansions, max_time)
+        )
+        return path, distance, PathStatus(status)
+
+    def find_alternative_paths(
+        self, start: Tuple[float, float], end: Tuple[float, float],
+        k: int = 3,
+        overlap_penalty: float = 1.0,
+        influence: bool = False
+    ) -> List[Tuple[List[Tuple[int, int]], float, float]]:
+        """
+        Finds up to k different routes from start to end, the first one is the shortest path.
+        Every following path avoids the cells used by the earlier paths and their neighbours,
+        moving into such a cell costs overlap_penalty times more for each earlier path that used it.
+
+        :param k: Maximum number of paths, fewer are returned if the routes can't be told apart
+        :param overlap_penalty: Extra cost multiplier for sharing cells with earlier paths
+        :param influence: Account for enemy influence
+        :return: List of tuples of points, path length and share of the points that are also in the best path.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        end_int = (int(round(end[0])), int(round(end[1])))
+        return self._path_find.find_alternative_paths(start_int, end_int, k, overlap_penalty, influence)
+
+    def find_nearest_goal(
+        self, start: Tuple[float, float], goals: List[Tuple[float, float]],
+        weights: Optional[List[float]] = None,
+        large: bool = False,
+        influence: bool = False
+    ) -> Optional[Tuple[int, List[Tuple[int, int]], float]]:
+        """
+        Finds the goal that can be reached first by walking with a single search.
+
+        :param goals: Candidate goals, for example enemy units or expansions
+        :param weights: Extra distance for each goal, the goal with the lowest distance plus weight is selected
+        :param large: Unit is large and requires path to have width of 2 to pass
+        :param influence: Account for enemy influence
+        :return: Tuple of the index of the goal, points and distance, None if no goal can be reached.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        goals_int = [(int(round(goal[0])), int(round(goal[1]))) for goal in goals]
+        return self._path_find.find_nearest_goal(start_int, goals_int, weights, large, influence)
+
+    def find_paths_batch(self, queries: List[tuple]) -> List[Tuple[List[Tuple[int, int]], float]]:
+        """
+        Finds multiple paths in parallel.
+
+        :param queries: List of tuples in format
+            (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap),
+            everything after end is optional. influence_cap is max_influence or (max_influence, soft_cap).
+        :return: List of path and distance tuples in the same order as the queries.
+        """
+        return self._path_find.find_paths_batch(queries)
+
+    def find_cooperative_paths(
+        self, agents: List[tuple],
+        max_expansions: Optional[int] = None,
+        max_time: Optional[float] = None
+    ) -> List[List[Tuple[Tuple[int, int], float]]]:
+        """
+        Plans paths for multiple units so that they don't walk through the same narrow cells at the same time.
+        Units are planned in the given order, later units path around the earlier ones or wait for them to pass.
+        Units stay at their goal after reaching it.
+
+        :param agents: List of tuples in format (start, goal, size, speed), size and speed are optional.
+            size is the width of the unit in cells, units larger than 1 use large unit path finding.
+            speed is in cells per second and defaults to 1.
+        :param max_expansions: Stop planning a unit after expanding this many positions, defaults to 200 000
+        :param max_time: Stop planning after this many milliseconds
+        :return: List of timed paths in the same order as the agents, empty when there is no path or the budget ran out.
+            Every cell has the time in seconds when the unit reaches it, repeated cells mean waiting.
+        """
+        budget = None if max_expansions is None and max_time is None else (max_expansions, max_time)
+        return self._path_find.find_cooperative_paths(agents, budget)
+
+    def find_path_smooth(
+        self, start: Tuple[float, float], end: Tuple[float, float],
+        large: bool = False,
+        influence: bool = False,
+        window: Optional[Tuple[Tuple[float, float], Tuple[float, float]]] = None,
+        distance_from_target: Optional[float] = None,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None
+    ) -> Tuple[List[Tuple[float, float]], float]:
+        """
+        Same as find_path, but returns only the waypoints where the path needs to turn.
+        Straight segments between waypoints never cross unpathable cells,
+        cells without room for the radius or, without soft_cap, cells above max_influence.
+
+        :return: Tuple of waypoints and euclidean length of the smoothed path.
         """
         start_int = (int(round(start[0])), int(round(start[1])))
         end_int = (int(round(end[0])), int(round(end[1])))
         if window is not None:
             window = ((int(round(window[0][0])), int(round(window[0][1]))),
                       (int(round(window[1][0])), int(round(window[1][1]))))
-        return self._path_find.find_path(start_int, end_int, large, influence, self.heuristic_accuracy, window, distance_from_target)
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        return self._path_find.find_path_smooth(
+            start_int, end_int, large, influence, self.heuristic_accuracy, window, distance_from_target,
+            possible_radius=radius, possible_influence_cap=influence_cap
+        )
 
     def find_path_influence(
         self, start: Tuple[float, float], end: Tuple[float, float], large: bool = False
     ) -> Tuple[List[Tuple[int, int]], float]:
         return self.find_path(start, end, large, influence=True)
 
+    def distance_field(
+        self,
+        goals: List[Tuple[float, float]],
+        large: bool = False,
+        influence: bool = False,
+        radius: Optional[float] = None,
+        max_influence: Optional[float] = None,
+        soft_cap: Optional[float] = None,
+    ) -> DistanceField:
+        """
+        Calculates walking distances to the closest goal for the whole map.
+        Use this when multiple units are moving to the same location,
+        each unit can query `next_step` or `direction` from the returned field.
+        With radius set, only cells with enough room for a unit with the given radius are used.
+        With max_influence set, cells above it are not used, or cost soft_cap times more if it is set.
+        """
+        goals_int = [(int(round(goal[0])), int(round(goal[1]))) for goal in goals]
+        influence_cap = to_influence_cap(max_influence, soft_cap)
+        return self._path_find.distance_field(goals_int, large, influence, radius, influence_cap)
+
+    def flee_path(
+        self, start: Tuple[float, float], threats: List[Tuple[float, float]], distance: float
+    ) -> Tuple[List[Tuple[int, int]], float]:
+        """
+        Finds a path away from the threats that takes terrain and current influence into account.
+        Instead of running straight away from the threats, the path prefers open space over dead ends,
+        even if that means running past a threat.
+        :param start: position of the fleeing unit
+        :param threats: positions of the threats to flee from
+        :param distance: how far to flee, the path is shorter when there is nowhere safer to go
+        :return: Tuple of path and its walking distance
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        threats_int = [(int(round(threat[0])), int(round(threat[1]))) for threat in threats]
+        return self._path_find.flee_path(start_int, threats_int, distance)
+
+    def component_id(self, position: Tuple[float, float]) -> Optional[int]:
+        """
+        Id of the connected area the position belongs to, None if the position is not pathable.
+        Ids are kept up to date when blocks are created or removed, but they may change with every change.
+        """
+        position_int = (int(round(position[0])), int(round(position[1])))
+        return self._path_find.component_id(position_int)
+
+    def same_component(self, start: Tuple[float, float], end: Tuple[float, float]) -> bool:
+        """
+        Checks whether a unit at start can walk to end at all without doing a path search.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        end_int = (int(round(end[0])), int(round(end[1])))
+        return self._path_find.same_component(start_int, end_int)
+
+    def create_planner(
+        self, start: Tuple[float, float], goal: Tuple[float, float], large: bool = False, influence: bool = False
+    ) -> PathPlanner:
+        """
+        Creates a path that can be repaired with planner.update(changed_cells) after the map changes,
+        which is much cheaper than finding the whole path again.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        goal_int = (int(round(goal[0])), int(round(goal[1])))
+        planner = self._path_find.create_planner(start_int, goal_int, large, influence)
+        return PathPlanner(planner, self._path_find.update_planner)
+
+    def raycast(
+        self, start: Tuple[float, float], end: Tuple[float, float], include_cells: bool = False
+    ) -> Tuple[bool, Optional[Tuple[int, int]], List[Tuple[int, int]]]:
+        """
+        Checks whether a unit can walk straight from start to end, using every cell the segment touches.
+
+        :param include_cells: Also return all traversed cells up to the blocking cell
+        :return: Tuple of whether the segment is clear, the first unpathable cell or None and the traversed cells.
+        """
+        start_int = (int(round(start[0])), int(round(start[1])))
+        end_int = (int(round(end[0])), int(round(end[1])))
+        return self._path_find.raycast(start_int, end_int, include_cells)
+
+    def clearance(self, position: Tuple[float, float]) -> float:
+        """
+        Distance from the cell to the closest unpathable cell, capped at 10.
+        A unit with radius r fits in the cell when clearance is at least r + 0.5.
+        """
+        return self._path_find.clearance((int(round(position[0])), int(round(position[1]))))
+
+    def evaluate_path(self, path: List[Tuple[int, int]], threshold: float) -> PathStats:
+        """
+        Calculates danger along the path.
+        Returns total_influence and max_influence above the normal influence, max_influence_index,
+        cells_above_threshold and first_danger_index of cells with influence more than threshold above normal.
+        seen_cells is always None, use Sc2Map.evaluate_path to count cells seen by the enemy.
+        """
+        return self._path_find.evaluate_path(path, threshold)
+
     def safest_spot(self, destination_center: Tuple[float, float], walk_distance: float) -> Tuple[Tuple[int, int], float]:
         destination_int = (round(destination_center[0]), round(destination_center[1]))
         return self._path_find.lowest_influence_walk(destination_int, walk_distance)
@@ -0,0 +1,34 @@
+from typing import Callable, List, Tuple
+
+
+class PathPlanner:
+    """
+    Keeps the path between two positions up to date when the map changes.
+    Only the part of the search that the changes affect is recalculated.
+    Create with PathFinder.create_planner or Sc2Map.create_planner.
+    """
+
+    def __init__(self, planner, update: Callable):
+        self._planner = planner
+        self._update = update
+
+    @property
+    def start(self) -> Tuple[int, int]:
+        return self._planner.start
+
+    @property
+    def goal(self) -> Tuple[int, int]:
+        return self._planner.goal
+
+    def update(self, changed_cells: List[Tuple[int, int]]):
+        """
+        Repairs the path after map changes such as create_block or remove_block.
+        changed_cells needs to contain every cell whose pathing or influence value changed.
+        """
+        self._update(self._planner, changed_cells)
+
+    def current_path(self) -> Tuple[List[Tuple[int, int]], float]:
+        """
+        :return: path and distance after the latest update, an empty path if the goal cannot be reached
+        """
+        return self._planner.current_path()
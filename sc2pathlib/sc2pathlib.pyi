@@ -0,0 +1,474 @@
+from typing import List, Optional, Tuple, Union
+
+Point = Tuple[int, int]
+PointF = Tuple[float, float]
+# Max influence for a hard cap or (max influence, multiplier) for a soft cap.
+InfluenceCap = Union[float, Tuple[float, float]]
+# (max expansions, max time in milliseconds), either can be None.
+SearchBudget = Tuple[Optional[int], Optional[float]]
+# (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap),
+# everything after end can be left out.
+PathQuery = tuple
+# (start, goal, size, speed), size and speed can be left out.
+Agent = tuple
+# (clear, first blocked cell, cells on the line)
+RaycastResult = Tuple[bool, Optional[Point], List[Point]]
+
+
+class PathStats:
+    total_influence: int
+    max_influence: int
+    max_influence_index: Optional[int]
+    cells_above_threshold: int
+    first_danger_index: Optional[int]
+    seen_cells: Optional[int]
+
+
+class PathPlanner:
+    start: Point
+    goal: Point
+
+    def current_path(self) -> Tuple[List[Point], float]:
+        ...
+
+
+class DistanceField:
+    width: int
+    height: int
+
+    def distance(self, position: PointF) -> Optional[float]:
+        ...
+
+    def next_step(self, position: PointF) -> Optional[Point]:
+        ...
+
+    def direction(self, position: PointF) -> Optional[PointF]:
+        ...
+
+    def path(self, position: PointF, max_steps: int) -> List[Point]:
+        ...
+
+
+class PathFind:
+    width: int
+    height: int
+    normal_influence: int
+    map: List[List[int]]
+    auto_correct: bool
+    auto_correct_distance: float
+
+    def __init__(self, map: List[List[int]]) -> None:
+        ...
+
+    def closest_pathable(self, position: Point, possible_max_distance: Optional[float] = None) -> Optional[Point]:
+        ...
+
+    def reset(self) -> None:
+        ...
+
+    def reset_void(self) -> None:
+        ...
+
+    def component_id(self, position: Point) -> Optional[int]:
+        ...
+
+    def same_component(self, start: Point, end: Point) -> bool:
+        ...
+
+    def clearance(self, position: Point) -> float:
+        ...
+
+    def create_block(self, center: PointF, size: Point) -> int:
+        ...
+
+    def create_blocks(self, centers: List[PointF], size: Point) -> List[int]:
+        ...
+
+    def remove_block(self, center: PointF, size: Point) -> None:
+        ...
+
+    def remove_blocks(self, centers: List[PointF], size: Point) -> None:
+        ...
+
+    def remove_block_by_id(self, id: int) -> bool:
+        ...
+
+    def normalize_influence(self, value: int) -> None:
+        ...
+
+    def add_influence_spot(self, position: Point, influence: int) -> None:
+        ...
+
+    def add_influence(self, positions: List[Point], max: float, distance: float) -> None:
+        ...
+
+    def add_influence_flat(self, positions: List[Point], max: float, distance: float) -> None:
+        ...
+
+    def add_walk_influence(self, positions: List[Point], max: float, distance: float) -> None:
+        ...
+
+    def add_walk_influence_flat(self, positions: List[Point], max: float, distance: float) -> None:
+        ...
+
+    def current_influence(self, position: Point) -> int:
+        ...
+
+    def lowest_influence_walk(self, center: Point, distance: float) -> Tuple[Point, float]:
+        ...
+
+    def lowest_influence(self, center: PointF, distance: int) -> Tuple[Point, float]:
+        ...
+
+    def find_path(self,
+                  start: Point,
+                  end: Point,
+                  large: bool,
+                  influence: bool,
+                  possible_heuristic: Optional[int] = None,
+                  possible_window: Optional[Tuple[Point, Point]] = None,
+                  possible_distance_from_target: Optional[float] = None,
+                  possible_radius: Optional[float] = None,
+                  possible_influence_cap: Optional[InfluenceCap] = None) -> Tuple[List[Point], float]:
+        ...
+
+    def find_path_corrected(self,
+                            start: Point,
+                            end: Point,
+                            large: bool,
+                            influence: bool,
+                            possible_heuristic: Optional[int] = None,
+                            possible_window: Optional[Tuple[Point, Point]] = None,
+                            possible_distance_from_target: Optional[float] = None,
+                            possible_radius: Optional[float] = None,
+                            possible_influence_cap: Optional[InfluenceCap] = None
+                            ) -> Tuple[List[Point], float, Tuple[Point, Point]]:
+        ...
+
+    def find_path_smooth(self,
+                         start: Point,
+                         end: Point,
+                         large: bool,
+                         influence: bool,
+                         possible_heuristic: Optional[int] = None,
+                         possible_window: Optional[Tuple[Point, Point]] = None,
+                         possible_distance_from_target: Optional[float] = None,
+                         possible_radius: Optional[float] = None,
+                         possible_influence_cap: Optional[InfluenceCap] = None) -> Tuple[List[PointF], float]:
+        ...
+
+    def find_path_basic(self, start: Point, end: Point,
+                        possible_heuristic: Optional[int] = None) -> Tuple[List[Point], float]:
+        ...
+
+    def find_path_budget(self, start: Point, end: Point, large: bool, influence: bool,
+                         budget: SearchBudget) -> Tuple[List[Point], float, int]:
+        ...
+
+    def find_paths_batch(self, queries: List[PathQuery]) -> List[Tuple[List[Point], float]]:
+        ...
+
+    def find_cooperative_paths(self, agents: List[Agent],
+                               possible_budget: Optional[SearchBudget] = None) -> List[List[Tuple[Point, float]]]:
+        ...
+
+    def find_alternative_paths(self, start: Point, end: Point, k: int, overlap_penalty: float,
+                               influence: bool) -> List[Tuple[List[Point], float, float]]:
+        ...
+
+    def find_nearest_goal(self, start: Point, goals: List[Point], possible_weights: Optional[List[float]],
+                          large: bool, influence: bool) -> Optional[Tuple[int, List[Point], float]]:
+        ...
+
+    def evaluate_path(self, path: List[Point], threshold: float) -> PathStats:
+        ...
+
+    def raycast(self, start: Point, end: Point, include_cells: bool) -> RaycastResult:
+        ...
+
+    def distance_field(self,
+                       goals: List[Point],
+                       large: bool,
+                       influence: bool,
+                       possible_radius: Optional[float] = None,
+                       possible_influence_cap: Optional[InfluenceCap] = None) -> DistanceField:
+        ...
+
+    def flee_path(self, start: Point, threats: List[Point], distance: float) -> Tuple[List[Point], float]:
+        ...
+
+    def create_planner(self, start: Point, goal: Point, large: bool, influence: bool) -> PathPlanner:
+        ...
+
+    def update_planner(self, planner: PathPlanner, changed_cells: List[Point]) -> None:
+        ...
+
+    def find_all_destinations(self, start: Point) -> List[Tuple[Point, float]]:
+        ...
+
+    def find_destinations_in(self,
+                             start: Point,
+                             distance: float,
+                             possible_radius: Optional[float] = None,
+                             possible_influence_cap: Optional[InfluenceCap] = None) -> List[Tuple[Point, float]]:
+        ...
+
+    def find_low_inside_walk(self, start: PointF, target: PointF, distance: float) -> Tuple[PointF, float]:
+        ...
+
+    def djiktra(self, start: PointF, distance: float) -> List[Tuple[Point, float]]:
+        ...
+
+
+class Ramp:
+    cells: List[Point]
+    upper: List[Point]
+    lower: List[Point]
+    top_center: PointF
+    bottom_center: PointF
+    direction: PointF
+    width: float
+    zones: Tuple[int, int]
+    is_main: bool
+
+
+# Not exported from the module, instances are only returned by Map.chokes.
+class Choke:
+    lines: List[Tuple[Point, Point]]
+    side1: List[Point]
+    side2: List[Point]
+    main_line: Tuple[PointF, PointF]
+    pixels: List[Point]
+    min_length: float
+
+
+class VisionUnit:
+    def __init__(self, detector: bool, flying: bool, position: PointF, sight_range: float) -> None:
+        ...
+
+
+class Map:
+    ground_pathing: List[List[int]]
+    air_pathing: List[List[int]]
+    reaper_pathing: List[List[int]]
+    colossus_pathing: List[List[int]]
+    vision_map: List[List[int]]
+    overlord_spots: List[PointF]
+    chokes: List[Choke]
+    ramps: List[Ramp]
+    placement: List[List[int]]
+
+    def __init__(self,
+                 pathing: List[List[int]],
+                 placement: List[List[int]],
+                 height_map: List[List[int]],
+                 x_start: int,
+                 y_start: int,
+                 x_end: int,
+                 y_end: int) -> None:
+        ...
+
+    def draw_climbs(self) -> List[List[int]]:
+        ...
+
+    def draw_chokes(self) -> List[List[int]]:
+        ...
+
+    def reset(self) -> None:
+        ...
+
+    def create_block(self, center: PointF, size: Point) -> int:
+        ...
+
+    def create_footprint(self, center: PointF, footprint: int) -> int:
+        ...
+
+    def create_blocks(self, centers: List[PointF], size: Point) -> List[int]:
+        ...
+
+    def remove_block_by_id(self, id: int) -> bool:
+        ...
+
+    def remove_block(self, center: PointF, size: Point) -> None:
+        ...
+
+    def remove_blocks(self, centers: List[PointF], size: Point) -> None:
+        ...
+
+    def get_borders(self) -> List[Point]:
+        ...
+
+    def normalize_influence(self, value: int) -> None:
+        ...
+
+    def add_influence_walk(self, positions: List[PointF], influence: float, distance: float) -> None:
+        ...
+
+    def add_influence_flat_hollow(self, positions: List[PointF], influence: float, min: float, max: float) -> None:
+        ...
+
+    def add_influence_fading(self, map_type: int, positions: List[PointF], influence: float, min: float,
+                             max: float) -> None:
+        ...
+
+    def current_influence(self, map_type: int, position: PointF) -> float:
+        ...
+
+    def lowest_influence_walk(self, map_type: int, center: PointF, distance: float) -> Tuple[Point, float]:
+        ...
+
+    def lowest_influence(self, map_type: int, center: PointF, distance: int) -> Tuple[Point, float]:
+        ...
+
+    def find_path(self,
+                  map_type: int,
+                  start: PointF,
+                  end: PointF,
+                  large: bool,
+                  influence: bool,
+                  possible_heuristic: Optional[int] = None,
+                  possible_window: Optional[Tuple[PointF, PointF]] = None,
+                  possible_distance_from_target: Optional[float] = None,
+                  possible_radius: Optional[float] = None,
+                  possible_influence_cap: Optional[InfluenceCap] = None) -> Tuple[List[Point], float]:
+        ...
+
+    def find_path_corrected(self,
+                            map_type: int,
+                            start: PointF,
+                            end: PointF,
+                            large: bool,
+                            influence: bool,
+                            possible_heuristic: Optional[int] = None,
+                            possible_window: Optional[Tuple[PointF, PointF]] = None,
+                            possible_distance_from_target: Optional[float] = None,
+                            possible_radius: Optional[float] = None,
+                            possible_influence_cap: Optional[InfluenceCap] = None
+                            ) -> Tuple[List[Point], float, Tuple[Point, Point]]:
+        ...
+
+    def find_path_smooth(self,
+                         map_type: int,
+                         start: PointF,
+                         end: PointF,
+                         large: bool,
+                         influence: bool,
+                         possible_heuristic: Optional[int] = None,
+                         possible_window: Optional[Tuple[PointF, PointF]] = None,
+                         possible_distance_from_target: Optional[float] = None,
+                         possible_radius: Optional[float] = None,
+                         possible_influence_cap: Optional[InfluenceCap] = None) -> Tuple[List[PointF], float]:
+        ...
+
+    def find_paths_batch(self, map_type: int, queries: List[PathQuery]) -> List[Tuple[List[Point], float]]:
+        ...
+
+    def find_cooperative_paths(self, map_type: int, agents: List[Agent],
+                               possible_budget: Optional[SearchBudget] = None) -> List[List[Tuple[Point, float]]]:
+        ...
+
+    def find_path_basic(self, map_type: int, start: PointF, end: PointF,
+                        possible_heuristic: Optional[int] = None) -> Tuple[List[Point], float]:
+        ...
+
+    def find_path_budget(self, map_type: int, start: PointF, end: PointF, large: bool, influence: bool,
+                         budget: SearchBudget) -> Tuple[List[Point], float, int]:
+        ...
+
+    def find_alternative_paths(self, map_type: int, start: PointF, end: PointF, k: int, overlap_penalty: float,
+                               influence: bool) -> List[Tuple[List[Point], float, float]]:
+        ...
+
+    def find_nearest_goal(self, map_type: int, start: PointF, goals: List[PointF],
+                          possible_weights: Optional[List[float]], large: bool,
+                          influence: bool) -> Optional[Tuple[int, List[Point], float]]:
+        ...
+
+    def find_path_abstract(self, start: PointF, end: PointF) -> Tuple[List[int], float]:
+        ...
+
+    def find_path_hierarchical(self, start: PointF, end: PointF, large: bool, influence: bool,
+                               possible_heuristic: Optional[int] = None) -> Tuple[List[Point], float]:
+        ...
+
+    def evaluate_path(self, map_type: int, path: List[Point], threshold: float) -> PathStats:
+        ...
+
+    def distance_field(self,
+                       map_type: int,
+                       goals: List[PointF],
+                       large: bool,
+                       influence: bool,
+                       possible_radius: Optional[float] = None,
+                       possible_influence_cap: Optional[InfluenceCap] = None) -> DistanceField:
+        ...
+
+    def flee_path(self, map_type: int, start: PointF, threats: List[PointF],
+                  distance: float) -> Tuple[List[Point], float]:
+        ...
+
+    def set_auto_correct_distance(self, distance: float) -> None:
+        ...
+
+    def closest_pathable(self, map_type: int, position: PointF,
+                         possible_max_distance: Optional[float] = None) -> Optional[Point]:
+        ...
+
+    def component_id(self, map_type: int, position: PointF) -> Optional[int]:
+        ...
+
+    def same_component(self, map_type: int, start: PointF, end: PointF) -> bool:
+        ...
+
+    def create_planner(self, map_type: int, start: PointF, goal: PointF, large: bool,
+                       influence: bool) -> PathPlanner:
+        ...
+
+    def update_planner(self, map_type: int, planner: PathPlanner, changed_cells: List[Point]) -> None:
+        ...
+
+    def find_low_inside_walk(self, map_type: int, start: PointF, target: PointF,
+                             distance: float) -> Tuple[PointF, float]:
+        ...
+
+    def calculate_connections(self, location: PointF) -> None:
+        ...
+
+    def is_connected(self, location: PointF) -> bool:
+        ...
+
+    def remove_connection(self, location: PointF) -> None:
+        ...
+
+    def clear_vision(self) -> None:
+        ...
+
+    def add_vision_unit(self, unit: VisionUnit) -> None:
+        ...
+
+    def calculate_vision_map(self) -> None:
+        ...
+
+    def vision_status(self, point: PointF) -> int:
+        ...
+
+    def raycast(self, map_type: int, start: PointF, end: PointF, include_cells: bool) -> RaycastResult:
+        ...
+
+    def vision_raycast(self, start: PointF, end: PointF, include_cells: bool) -> RaycastResult:
+        ...
+
+    def add_influence_to_vision(self, map_type: int, seen_value: int, detection_value: int) -> None:
+        ...
+
+    def find_placement(self,
+                       near: PointF,
+                       footprint: int,
+                       max_distance: float,
+                       pylons: Optional[List[PointF]] = None,
+                       creep: Optional[List[List[int]]] = None) -> Optional[PointF]:
+        ...
+
+    def plan_wall(self, center: PointF, inside: PointF, footprints: List[int],
+                  gap: int) -> Optional[List[Tuple[int, PointF]]]:
+        ...
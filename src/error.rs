@@ -0,0 +1,99 @@
+use pyo3::exceptions::{PyIndexError, PyRuntimeError, PyValueError};
+use pyo3::PyErr;
+use std::fmt;
+
+pub type PathlibResult<T> = Result<T, PathlibError>;
+
+/// Errors from invalid input, raised in python as `IndexError` or `ValueError`,
+/// and failures of the library itself, raised as `RuntimeError`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathlibError {
+    /// Position is outside of the map.
+    OutOfBounds { position: (usize, usize), width: usize, height: usize },
+    /// Window is empty or does not fit inside the map.
+    InvalidWindow { window: ((usize, usize), (usize, usize)), width: usize, height: usize },
+    /// Map type is not one of the types in `MapType`.
+    UnknownMapType(u8),
+    /// Grid given from python does not have the expected shape.
+    InvalidGrid(String),
+    /// Other argument from python is not valid.
+    InvalidArgument(String),
+    /// Worker thread of a parallel search panicked.
+    ThreadPanicked(String),
+}
+
+impl fmt::Display for PathlibError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathlibError::OutOfBounds { position, width, height } => {
+                write!(f, "Position {:?} is outside of the {}x{} map", position, width, height)
+            }
+            PathlibError::InvalidWindow { window, width, height } => {
+                write!(f, "Window {:?} does not fit inside the {}x{} map", window, width, height)
+            }
+            PathlibError::UnknownMapType(map_type) => write!(f, "Map type {} does not exist", map_type),
+            PathlibError::InvalidGrid(reason) => write!(f, "Invalid grid: {}", reason),
+            PathlibError::InvalidArgument(reason) => write!(f, "Invalid argument: {}", reason),
+            PathlibError::ThreadPanicked(reason) => write!(f, "Path finding thread panicked: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for PathlibError {}
+
+impl From<PathlibError> for PyErr {
+    fn from(error: PathlibError) -> PyErr {
+        match error {
+            PathlibError::OutOfBounds { .. } => PyIndexError::new_err(error.to_string()),
+            PathlibError::ThreadPanicked(_) => PyRuntimeError::new_err(error.to_string()),
+            _ => PyValueError::new_err(error.to_string()),
+        }
+    }
+}
+
+/// Checks that the position is inside a map of the given size.
+#[inline]
+pub fn check_position(position: (usize, usize), width: usize, height: usize) -> PathlibResult<()> {
+    if position.0 < width && position.1 < height {
+        Ok(())
+    } else {
+        Err(PathlibError::OutOfBounds { position, width, height })
+    }
+}
+
+/// Checks that the window is not empty and fits inside a map of the given size.
+#[inline]
+pub fn check_window(window: ((usize, usize), (usize, usize)), width: usize, height: usize) -> PathlibResult<()> {
+    let ((x0, y0), (x1, y1)) = window;
+
+    if x0 < x1 && y0 < y1 && x1 <= width && y1 <= height {
+        Ok(())
+    } else {
+        Err(PathlibError::InvalidWindow { window, width, height })
+    }
+}
+
+/// Checks that the distance is a finite number that is not negative.
+#[inline]
+pub fn check_distance(name: &str, distance: f32) -> PathlibResult<()> {
+    if distance.is_finite() && distance >= 0.0 {
+        Ok(())
+    } else {
+        Err(PathlibError::InvalidArgument(format!("{} must be finite and not negative, got {}", name, distance)))
+    }
+}
+
+/// Checks that the grid from python is not empty and every column has the same height.
+pub fn check_grid(grid: &[Vec<usize>]) -> PathlibResult<()> {
+    let height = grid.first().map_or(0, |column| column.len());
+
+    if height == 0 {
+        return Err(PathlibError::InvalidGrid("grid is empty".to_string()));
+    }
+
+    if grid.iter().any(|column| column.len() != height) {
+        return Err(PathlibError::InvalidGrid("columns have different heights".to_string()));
+    }
+
+    Ok(())
+}
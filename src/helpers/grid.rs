@@ -0,0 +1,126 @@
+use std::ops::{Index, IndexMut};
+
+/// Two dimensional grid stored in a single contiguous buffer.
+/// Cells are stored column by column, so `grid[(x, y)]` matches `list[x][y]` of the python grids
+/// and walking along y stays in the same cache line.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Grid<T> {
+    data: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T: Clone> Grid<T> {
+    pub fn new(width: usize, height: usize, value: T) -> Self {
+        Grid { data: vec![value; width * height],
+               width,
+               height }
+    }
+
+    /// Converts the grid back to the list of columns layout used by python.
+    pub fn to_vec(&self) -> Vec<Vec<T>> {
+        if self.height == 0 {
+            return vec![Vec::new(); self.width];
+        }
+
+        self.data.chunks(self.height).map(|column| column.to_vec()).collect()
+    }
+
+    pub fn fill(&mut self, value: T) {
+        for cell in &mut self.data {
+            *cell = value.clone();
+        }
+    }
+
+    /// Copies all values from another grid, reusing the existing buffer when the sizes match.
+    pub fn copy_from(&mut self, other: &Grid<T>) {
+        self.data.clone_from(&other.data);
+        self.width = other.width;
+        self.height = other.height;
+    }
+}
+
+impl<T> Grid<T> {
+    #[inline]
+    pub fn width(&self) -> usize { self.width }
+
+    #[inline]
+    pub fn height(&self) -> usize { self.height }
+
+    /// Index of the cell in the flat buffer.
+    #[inline]
+    pub fn flat_index(&self, x: usize, y: usize) -> usize { x * self.height + y }
+
+    /// Position of the cell at the flat buffer index.
+    #[inline]
+    pub fn position(&self, index: usize) -> (usize, usize) { (index / self.height, index % self.height) }
+
+    #[inline]
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool { x < self.width && y < self.height }
+
+    #[inline]
+    pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+        if self.in_bounds(x, y) {
+            Some(&self.data[x * self.height + y])
+        } else {
+            None
+        }
+    }
+
+    /// All values along y at the given x.
+    #[inline]
+    pub fn column(&self, x: usize) -> &[T] { &self.data[x * self.height..(x + 1) * self.height] }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[T] { &self.data }
+
+    #[inline]
+    pub fn as_mut_slice(&mut self) -> &mut [T] { &mut self.data }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, T> { self.data.iter() }
+
+    pub fn iter_mut(&mut self) -> std::slice::IterMut<'_, T> { self.data.iter_mut() }
+
+    /// Creates a grid of the same size with every value converted.
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+        where F: FnMut(&T) -> U
+    {
+        Grid { data: self.data.iter().map(f).collect(),
+               width: self.width,
+               height: self.height }
+    }
+}
+
+impl<T> From<Vec<Vec<T>>> for Grid<T> {
+    /// Flattens a list of columns, all columns must have the same height.
+    fn from(columns: Vec<Vec<T>>) -> Self {
+        let width = columns.len();
+        let height = columns.first().map_or(0, |column| column.len());
+        let mut data = Vec::with_capacity(width * height);
+
+        for column in columns {
+            assert!(column.len() == height, "All grid columns must have the same height");
+            data.extend(column);
+        }
+
+        Grid { data, width, height }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, (x, y): (usize, usize)) -> &T {
+        assert!(y < self.height, "Grid index out of bounds");
+        &self.data[x * self.height + y]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    #[inline]
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut T {
+        assert!(y < self.height, "Grid index out of bounds");
+        &mut self.data[x * self.height + y]
+    }
+}
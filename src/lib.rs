@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
 use pyo3::prelude::*;
+pub mod error;
 pub mod helpers;
 pub mod mapping;
 pub mod path_find;
@@ -9,7 +10,11 @@ pub mod path_find;
 #[pymodule]
 fn sc2pathlib(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<path_find::PathFind>()?;
+    m.add_class::<path_find::distance_field::DistanceField>()?;
+    m.add_class::<path_find::planner::PathPlanner>()?;
+    m.add_class::<path_find::path_stats::PathStats>()?;
     m.add_class::<mapping::map::Map>()?;
+    m.add_class::<mapping::ramps::Ramp>()?;
     m.add_class::<mapping::vision::VisionUnit>()?;
     Ok(())
 }
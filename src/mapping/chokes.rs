@@ -1,3 +1,4 @@
+use crate::helpers::grid::Grid;
 use crate::mapping::map_point;
 use crate::path_find::pos::{NormalPosAPI, Pos, PositionAPI};
 use crate::path_find::pos::{DIAGONAL_MINUS_CARDINAL, MULT, MULTF32, SQRT2};
@@ -6,7 +7,7 @@ use pyo3::prelude::*;
 use std::cmp;
 use std::collections::HashSet;
 
-pub fn solve_chokes(points: &mut Vec<Vec<map_point::MapPoint>>,
+pub fn solve_chokes(points: &mut Grid<map_point::MapPoint>,
                     ground_pathing: &PathFind,
                     chokes: &mut Vec<((usize, usize), (usize, usize))>,
                     x: usize,
@@ -19,8 +20,8 @@ pub fn solve_chokes(points: &mut Vec<Vec<map_point::MapPoint>>,
     let choke_distance = 13f32;
     let choke_border_distance = 30f32;
 
-    if points[pos_start.0][pos_start.1].is_border {
-        let reachable_borders = ground_pathing.djiktra((x as f32, y as f32), choke_border_distance);
+    if points[(pos_start.0, pos_start.1)].is_border {
+        let reachable_borders = ground_pathing.djiktra_inline((x, y), choke_border_distance);
         let xmin = x;
         let xmax = cmp::min(x as i64 + choke_distance as i64, x_end as i64) as usize;
         let ymin = cmp::max(y as i64 - choke_distance as i64, y_start as i64) as usize;
@@ -28,7 +29,7 @@ pub fn solve_chokes(points: &mut Vec<Vec<map_point::MapPoint>>,
 
         for x_new in xmin..xmax {
             for y_new in ymin..ymax {
-                if !points[x_new][y_new].is_border {
+                if !points[(x_new, y_new)].is_border {
                     // Needs to be a border to be acceptable position
                     continue;
                 }
@@ -63,7 +64,7 @@ pub fn solve_chokes(points: &mut Vec<Vec<map_point::MapPoint>>,
                     if (draw_x == x && draw_y == y) || (draw_x == pos.0 && draw_y == pos.1) {
                         continue;
                     }
-                    if !points[draw_x][draw_y].walkable {
+                    if !points[(draw_x, draw_y)].walkable {
                         wall_hit = true;
                         break;
                     }
@@ -78,7 +79,7 @@ pub fn solve_chokes(points: &mut Vec<Vec<map_point::MapPoint>>,
                         let draw_x = (center.0 as f32 + perdicular_unit_vector.0 * i as f32) as usize;
                         let draw_y = (center.1 as f32 + perdicular_unit_vector.1 * i as f32) as usize;
 
-                        if !points[draw_x][draw_y].walkable {
+                        if !points[(draw_x, draw_y)].walkable {
                             wall_hit = true;
                             break;
                         }
@@ -153,7 +154,7 @@ impl Choke {
         }
     }
 
-    fn finalize(&mut self, _points: &mut Vec<Vec<map_point::MapPoint>>) {
+    fn finalize(&mut self, _points: &mut Grid<map_point::MapPoint>) {
         self.remove_excess_lines();
         self.calc_final_line();
     }
@@ -179,13 +180,13 @@ impl Choke {
         self.min_length = min_distance;
     }
 
-    fn set_points(&mut self, points: &mut Vec<Vec<map_point::MapPoint>>) {
+    fn set_points(&mut self, points: &mut Grid<map_point::MapPoint>) {
         for line in &self.lines {
             let pos1 = Pos((line.0).0, (line.0).1);
             let pos2 = Pos((line.1).0, (line.1).1);
 
-            points[pos1.0][pos1.1].is_choke = true;
-            points[pos2.0][pos2.1].is_choke = true;
+            points[(pos1.0, pos1.1)].is_choke = true;
+            points[(pos2.0, pos2.1)].is_choke = true;
 
             let flight_distance = NormalPosAPI().euclidean_distance(&pos1, &pos2) as f32 / MULTF32;
 
@@ -200,7 +201,7 @@ impl Choke {
                     continue;
                 }
 
-                points[draw_x][draw_y].is_choke = true;
+                points[(draw_x, draw_y)].is_choke = true;
                 let new_point = (draw_x, draw_y);
 
                 if !self.pixels.contains(&new_point) {
@@ -232,7 +233,7 @@ impl Choke {
 }
 
 pub fn group_chokes(choke_lines: &mut Vec<((usize, usize), (usize, usize))>,
-                    points: &mut Vec<Vec<map_point::MapPoint>>)
+                    points: &mut Grid<map_point::MapPoint>)
                     -> Vec<Choke> {
     let mut result = Vec::<Choke>::new();
     let mut used_indices = HashSet::new();
@@ -1,8 +1,15 @@
+use crate::helpers::grid::Grid;
 use crate::mapping::map_point;
 use crate::mapping::map_point::Cliff;
 const DIFFERENCE: usize = 16;
 
-pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32, x_dir: i32, y_dir: i32) {
+pub fn modify_climb(points: &mut Grid<map_point::MapPoint>, x: i32, y: i32, x_dir: i32, y_dir: i32) {
+    // Climbing looks two cells ahead and at the corners past the next cell, all of them must be on the grid
+    let in_bounds = |x: i32, y: i32| x >= 0 && y >= 0 && points.in_bounds(x as usize, y as usize);
+    if !in_bounds(x + x_dir * 2, y + y_dir * 2) || !in_bounds(x + x_dir + 1, y + y_dir + 1) {
+        return;
+    }
+
     let x0 = x as usize;
     let y0 = y as usize;
     let x1 = (x + x_dir) as usize;
@@ -13,7 +20,7 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
     // let mut next_point = points[(x + x_dir) as usize][(y + y_dir) as usize];
     // let mut last_point = points[(x + x_dir * 2) as usize][(y + y_dir * 2) as usize];
 
-    if points[x1][y1].walkable || !points[x2][y2].walkable {
+    if points[(x1, y1)].walkable || !points[(x2, y2)].walkable {
         return; // Not climbable
     }
 
@@ -24,10 +31,10 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
     // 01
     // 23
 
-    let h0 = points[x1][y1 + 1].height;
-    let h1 = points[x1 + 1][y1 + 1].height;
-    let h2 = points[x1][y1].height;
-    let h3 = points[x1 + 1][y1].height;
+    let h0 = points[(x1, y1 + 1)].height;
+    let h1 = points[(x1 + 1, y1 + 1)].height;
+    let h2 = points[(x1, y1)].height;
+    let h3 = points[(x1 + 1, y1)].height;
 
     // Difference between levels is 15.9375 in standard map height maps
     // Difference between levels is 2 in standard sc2 measurement units.
@@ -56,25 +63,25 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
             if (h0 == h1 || h0 == h2) && h2 == h1 + DIFFERENCE && h0 == h3 {
                 // 10 00
                 // 11 10
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 }
             } else if (h0 == h1 && h0 == h3 && h0 == h2 + DIFFERENCE) || (h0 == h2 && h0 == h3 && h1 == h2 + DIFFERENCE)
             {
                 // 11 01
                 // 01 00
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 }
             }
         } else {
@@ -84,25 +91,25 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
             if (h1 == h2 && h1 == h3 && h1 == h0 + DIFFERENCE) || (h0 == h1 && h0 == h2 && h3 == h0 + DIFFERENCE) {
                 // 01 00
                 // 11 01
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 }
             } else if (h0 == h1 && h0 == h2 && h0 == h3 + DIFFERENCE) || (h1 == h2 && h1 == h3 && h0 == h3 + DIFFERENCE)
             {
                 // 11 10
                 // 10 00
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 }
             }
         }
@@ -114,24 +121,24 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
             if h0 == h2 && h1 == h3 && h0 + DIFFERENCE == h1 {
                 // 01
                 // 01
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 }
             } else if h0 == h2 && h1 == h3 && h0 == h1 + DIFFERENCE {
                 // 10
                 // 10
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if x_dir > 0 {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 }
             }
         } else if y_dir != 0 {
@@ -141,24 +148,24 @@ pub fn modify_climb(points: &mut Vec<Vec<map_point::MapPoint>>, x: i32, y: i32,
             if h0 == h1 && h2 == h3 && h0 + DIFFERENCE == h2 {
                 // 00
                 // 11
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if y_dir > 0 {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 }
             } else if h0 == h1 && h2 == h3 && h0 == h2 + DIFFERENCE {
                 // 11
                 // 00
-                points[x1][y1].climbable = true;
+                points[(x1, y1)].climbable = true;
                 if y_dir > 0 {
-                    points[x0][y0].cliff_type = set_low(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_high(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_low(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_high(points[(x2, y2)].cliff_type);
                 } else {
-                    points[x0][y0].cliff_type = set_high(points[x0][y0].cliff_type);
-                    points[x2][y2].cliff_type = set_low(points[x2][y2].cliff_type);
+                    points[(x0, y0)].cliff_type = set_high(points[(x0, y0)].cliff_type);
+                    points[(x2, y2)].cliff_type = set_low(points[(x2, y2)].cliff_type);
                 }
             }
         }
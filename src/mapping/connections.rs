@@ -1,5 +1,6 @@
 use pyo3::prelude::*;
 
+use crate::error::PathlibResult;
 use crate::helpers::round_point2;
 
 use super::map::Map;
@@ -9,35 +10,38 @@ const Y_MULT: usize = 1000000;
 
 #[pymethods]
 impl Map {
-    pub fn calculate_connections(&mut self, location: (f32, f32)) {
-        let pf = self.get_map_mut(0);
+    pub fn calculate_connections(&mut self, location: (f32, f32)) -> PathlibResult<()> {
+        let result = self.ground_pathing.djiktra(location, 400f32)?;
 
-        let result = pf.djiktra(location, 400f32);
-
-        let width = self.ground_pathing.map.len();
-        let height = self.ground_pathing.map[0].len();
+        let width = self.ground_pathing.map.width();
+        let height = self.ground_pathing.map.height();
 
         // Reset
         for x in 0..width {
             for y in 0..height {
-                self.points[x][y].connected = false;
+                self.points[(x, y)].connected = false;
             }
         }
 
         // Set non ground connected locations
         for data_point in result {
             let point = data_point.0;
-            self.points[point.0][point.1].connected = true;
+            self.points[point].connected = true;
         }
+
+        Ok(())
     }
 
-    pub fn is_connected(&mut self, location: (f32, f32)) -> bool {
+    pub fn is_connected(&mut self, location: (f32, f32)) -> PathlibResult<bool> {
         let location_int = round_point2(location);
-        return self.points[location_int.0][location_int.1].connected;
+        self.check_position(location_int)?;
+        Ok(self.points[location_int].connected)
     }
 
-    pub fn remove_connection(&mut self, location: (f32, f32)) {
+    pub fn remove_connection(&mut self, location: (f32, f32)) -> PathlibResult<()> {
         let location_int = round_point2(location);
-        self.points[location_int.0][location_int.1].connected = false;
+        self.check_position(location_int)?;
+        self.points[location_int].connected = false;
+        Ok(())
     }
 }
@@ -0,0 +1,102 @@
+use crate::error::{PathlibError, PathlibResult};
+
+/// Building and resource footprints of SC2, values match `Footprint` in python.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Footprint {
+    /// Command center, nexus and hatchery, pathing has cut corners.
+    TownHall = 0,
+    /// Barracks, gateway, spawning pool and other 3x3 buildings.
+    Building3x3 = 1,
+    /// Supply depot, pylon, turrets, add-ons and other 2x2 buildings.
+    Building2x2 = 2,
+    /// Sensor tower and other 1x1 buildings.
+    Building1x1 = 3,
+    /// Lowered supply depot can be walked over, but nothing can be built on it.
+    SupplyDepotLowered = 4,
+    MineralField = 5,
+    /// Geysers and the refineries, assimilators and extractors on them.
+    VespeneGeyser = 6,
+    /// Small destructible rocks.
+    Rocks2x2 = 7,
+}
+
+// Masks have one row for each y starting from the bottom, 'x' marks a covered cell
+const TOWN_HALL_PATHING: &[&str] = &[".xxx.", "xxxxx", "xxxxx", "xxxxx", ".xxx."];
+const FULL_5X5: &[&str] = &["xxxxx", "xxxxx", "xxxxx", "xxxxx", "xxxxx"];
+const FULL_3X3: &[&str] = &["xxx", "xxx", "xxx"];
+const FULL_2X2: &[&str] = &["xx", "xx"];
+const FULL_2X1: &[&str] = &["xx"];
+const FULL_1X1: &[&str] = &["x"];
+const EMPTY_2X2: &[&str] = &["..", ".."];
+
+impl Footprint {
+    pub fn from_u8(value: u8) -> PathlibResult<Self> {
+        match value {
+            0 => Ok(Footprint::TownHall),
+            1 => Ok(Footprint::Building3x3),
+            2 => Ok(Footprint::Building2x2),
+            3 => Ok(Footprint::Building1x1),
+            4 => Ok(Footprint::SupplyDepotLowered),
+            5 => Ok(Footprint::MineralField),
+            6 => Ok(Footprint::VespeneGeyser),
+            7 => Ok(Footprint::Rocks2x2),
+            _ => Err(PathlibError::InvalidArgument(format!("Footprint {} does not exist", value))),
+        }
+    }
+
+    /// Width and height in cells.
+    pub fn size(self) -> (usize, usize) {
+        let mask = self.placement_mask();
+        (mask[0].len(), mask.len())
+    }
+
+    /// Cells that ground units can't walk through.
+    pub fn pathing_mask(self) -> &'static [&'static str] {
+        match self {
+            Footprint::TownHall => TOWN_HALL_PATHING,
+            Footprint::SupplyDepotLowered => EMPTY_2X2,
+            _ => self.placement_mask(),
+        }
+    }
+
+    /// Cells where nothing else can be built.
+    pub fn placement_mask(self) -> &'static [&'static str] {
+        match self {
+            Footprint::TownHall => FULL_5X5,
+            Footprint::Building3x3 | Footprint::VespeneGeyser => FULL_3X3,
+            Footprint::Building2x2 | Footprint::SupplyDepotLowered | Footprint::Rocks2x2 => FULL_2X2,
+            Footprint::Building1x1 => FULL_1X1,
+            Footprint::MineralField => FULL_2X1,
+        }
+    }
+
+    /// Cells of the pathing mask inside the map when the footprint is placed at the center.
+    pub fn pathing_cells(self, center: (f32, f32), width: usize, height: usize) -> Vec<(usize, usize)> {
+        mask_cells(self.pathing_mask(), center, width, height)
+    }
+
+    /// Cells of the placement mask inside the map when the footprint is placed at the center.
+    pub fn placement_cells(self, center: (f32, f32), width: usize, height: usize) -> Vec<(usize, usize)> {
+        mask_cells(self.placement_mask(), center, width, height)
+    }
+}
+
+/// Positions the mask around the center with the same rounding as create_block:
+/// odd sizes are centered on the middle of a cell (x.5) and even sizes on a cell corner (x.0).
+fn mask_cells(mask: &[&str], center: (f32, f32), width: usize, height: usize) -> Vec<(usize, usize)> {
+    let x0 = (center.0.floor() - mask[0].len() as f32 / 2.0).ceil() as i64;
+    let y0 = (center.1.floor() - mask.len() as f32 / 2.0).ceil() as i64;
+    let mut cells = Vec::new();
+
+    for (dy, row) in mask.iter().enumerate() {
+        for (dx, mark) in row.chars().enumerate() {
+            let (x, y) = (x0 + dx as i64, y0 + dy as i64);
+
+            if mark == 'x' && x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+                cells.push((x as usize, y as usize));
+            }
+        }
+    }
+
+    cells
+}
@@ -0,0 +1,536 @@
+use arrayvec::ArrayVec;
+use pathfinding::prelude::dijkstra_partial;
+use pyo3::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+
+use crate::error::PathlibResult;
+use crate::helpers::grid::Grid;
+use crate::helpers::round_point2;
+use crate::mapping::chokes::Choke;
+use crate::mapping::map::Map;
+use crate::mapping::map_point;
+use crate::path_find::batch::PathQuery;
+use crate::path_find::pos::{NormalPosAPI, Pos, PositionAPI, MULTF32};
+use crate::path_find::rectangle::Rectangle;
+
+const NO_CLUSTER: usize = 0;
+
+/// Smallest and largest cell of an area, both inclusive.
+type Bounds = ((usize, usize), (usize, usize));
+
+#[derive(Clone, Copy, Debug)]
+struct Edge {
+    target: usize,
+    cluster: usize,
+    distance: usize,
+}
+
+/// Abstract graph for hierarchical path finding.
+/// Nodes are the entrances of the chokes and edges are walking distances between chokes
+/// that share a cluster, a walkable area separated from other areas by chokes.
+pub struct AbstractGraph {
+    clusters: Grid<usize>,
+    entrances: Vec<(usize, usize)>,
+    cluster_chokes: Vec<Vec<usize>>,
+    edges: Vec<Vec<Edge>>,
+    cluster_bounds: Vec<Option<Bounds>>,
+    choke_bounds: Vec<Option<Bounds>>,
+}
+
+impl AbstractGraph {
+    pub fn new(chokes: &[Choke], points: &Grid<map_point::MapPoint>, grid: &Grid<usize>) -> Self {
+        let width = grid.width();
+        let height = grid.height();
+        let mut clusters = Grid::new(width, height, NO_CLUSTER);
+        let mut cluster_count = 1;
+
+        for x in 0..width {
+            for y in 0..height {
+                if clusters[(x, y)] != NO_CLUSTER || !is_cluster_cell(points, grid, x, y) {
+                    continue;
+                }
+
+                flood_fill_cluster(&mut clusters, points, grid, x, y, cluster_count);
+                cluster_count += 1;
+            }
+        }
+
+        let mut cluster_bounds = vec![None; cluster_count];
+
+        for x in 0..width {
+            for y in 0..height {
+                let cluster = clusters[(x, y)];
+                if cluster != NO_CLUSTER {
+                    extend_bounds(&mut cluster_bounds[cluster], (x, y));
+                }
+            }
+        }
+
+        let mut entrances = Vec::<(usize, usize)>::with_capacity(chokes.len());
+        let mut cluster_chokes = vec![Vec::<usize>::new(); cluster_count];
+        let mut choke_bounds = vec![None; chokes.len()];
+
+        for (index, choke) in chokes.iter().enumerate() {
+            entrances.push(choke_entrance(choke, grid));
+
+            let mut touched = HashSet::<usize>::new();
+
+            for pixel in choke.pixels.iter().chain(choke.side1.iter()).chain(choke.side2.iter()) {
+                extend_bounds(&mut choke_bounds[index], *pixel);
+
+                for neighbour in neighbours(*pixel, width, height) {
+                    let cluster = clusters[neighbour];
+                    if cluster != NO_CLUSTER {
+                        touched.insert(cluster);
+                    }
+                }
+            }
+
+            for cluster in touched {
+                cluster_chokes[cluster].push(index);
+            }
+        }
+
+        let mut graph = AbstractGraph { clusters,
+                                        entrances,
+                                        cluster_chokes,
+                                        edges: vec![Vec::new(); chokes.len()],
+                                        cluster_bounds,
+                                        choke_bounds };
+
+        graph.rebuild(grid);
+        graph
+    }
+
+    /// Recalculates all edges of the graph.
+    pub fn rebuild(&mut self, grid: &Grid<usize>) {
+        for edges in &mut self.edges {
+            edges.clear();
+        }
+
+        for cluster in 1..self.cluster_chokes.len() {
+            self.rebuild_cluster(grid, cluster);
+        }
+    }
+
+    /// Recalculates edges of the clusters that the rectangle touches.
+    pub fn update_area(&mut self, grid: &Grid<usize>, rect: &Rectangle) {
+        let width = self.clusters.width();
+        let height = self.clusters.height();
+        let mut dirty = HashSet::<usize>::new();
+
+        for x in rect.x.saturating_sub(2)..usize::min(rect.x_end + 2, width) {
+            for y in rect.y.saturating_sub(2)..usize::min(rect.y_end + 2, height) {
+                let cluster = self.clusters[(x, y)];
+                if cluster != NO_CLUSTER {
+                    dirty.insert(cluster);
+                }
+            }
+        }
+
+        for cluster in dirty {
+            self.rebuild_cluster(grid, cluster);
+        }
+    }
+
+    /// Finds the choke indices to pass through and the estimated distance in `MULT` units.
+    /// Returns None when the abstract graph cannot answer the query.
+    pub fn find_route(&self,
+                      grid: &Grid<usize>,
+                      start: (usize, usize),
+                      end: (usize, usize))
+                      -> Option<(Vec<usize>, usize)> {
+        let start_cluster = self.cluster_near(start)?;
+        let end_cluster = self.cluster_near(end)?;
+
+        let start_chokes = &self.cluster_chokes[start_cluster];
+        let end_chokes = &self.cluster_chokes[end_cluster];
+
+        let mut start_targets: Vec<(usize, usize)> = start_chokes.iter().map(|c| self.entrances[*c]).collect();
+        if start_cluster == end_cluster {
+            start_targets.push(end);
+        }
+
+        let end_targets: Vec<(usize, usize)> = end_chokes.iter().map(|c| self.entrances[*c]).collect();
+        let start_distances = self.cluster_distances(grid, start, start_cluster, &start_targets);
+        let end_distances = self.cluster_distances(grid, end, end_cluster, &end_targets);
+
+        // Chokes are nodes 0..n and the end position is node n
+        let goal = self.entrances.len();
+        let mut distances = vec![usize::MAX; goal + 1];
+        let mut parents = vec![usize::MAX; goal + 1];
+        let mut open = BinaryHeap::new();
+
+        for (choke, distance) in start_chokes.iter().zip(start_distances.iter()) {
+            if let Some(d) = distance {
+                if *d < distances[*choke] {
+                    distances[*choke] = *d;
+                    open.push(Reverse((*d, *choke)));
+                }
+            }
+        }
+
+        if start_cluster == end_cluster {
+            if let Some(Some(d)) = start_distances.last() {
+                distances[goal] = *d;
+                open.push(Reverse((*d, goal)));
+            }
+        }
+
+        while let Some(Reverse((distance, node))) = open.pop() {
+            if distance > distances[node] {
+                continue;
+            }
+
+            if node == goal {
+                let mut route = Vec::<usize>::new();
+                let mut current = parents[goal];
+
+                while current != usize::MAX {
+                    route.push(current);
+                    current = parents[current];
+                }
+
+                route.reverse();
+                return Some((route, distance));
+            }
+
+            for edge in &self.edges[node] {
+                let new_distance = distance + edge.distance;
+                if new_distance < distances[edge.target] {
+                    distances[edge.target] = new_distance;
+                    parents[edge.target] = node;
+                    open.push(Reverse((new_distance, edge.target)));
+                }
+            }
+
+            if let Some(index) = end_chokes.iter().position(|c| *c == node) {
+                if let Some(d) = end_distances[index] {
+                    let new_distance = distance + d;
+                    if new_distance < distances[goal] {
+                        distances[goal] = new_distance;
+                        parents[goal] = node;
+                        open.push(Reverse((new_distance, goal)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    pub fn entrance(&self, choke: usize) -> (usize, usize) { self.entrances[choke] }
+
+    /// Search windows for each part of the route from start to end through the choke entrances.
+    /// A window covers the cluster that the part walks through and the chokes at both ends of it.
+    pub fn route_windows(&self,
+                         start: (usize, usize),
+                         end: (usize, usize),
+                         route: &[usize])
+                         -> Vec<((usize, usize), (usize, usize))> {
+        let width = self.clusters.width();
+        let height = self.clusters.height();
+        let mut windows = Vec::with_capacity(route.len() + 1);
+
+        for part in 0..=route.len() {
+            let possible_first = part.checked_sub(1).map(|i| route[i]);
+            let possible_second = route.get(part).copied();
+            let from = possible_first.map_or(start, |choke| self.entrances[choke]);
+            let to = possible_second.map_or(end, |choke| self.entrances[choke]);
+
+            let possible_cluster = match (possible_first, possible_second) {
+                (Some(first), Some(second)) => self.edges[first].iter()
+                                                                .filter(|e| e.target == second)
+                                                                .min_by_key(|e| e.distance)
+                                                                .map(|e| e.cluster),
+                (None, _) => self.cluster_near(start),
+                (_, None) => self.cluster_near(end),
+            };
+
+            let mut bounds = merge_bounds((from, from), (to, to));
+            let areas = [possible_cluster.and_then(|cluster| self.cluster_bounds[cluster]),
+                         possible_first.and_then(|choke| self.choke_bounds[choke]),
+                         possible_second.and_then(|choke| self.choke_bounds[choke])];
+
+            for area in areas.iter().flatten() {
+                bounds = merge_bounds(bounds, *area);
+            }
+
+            let ((x0, y0), (x1, y1)) = bounds;
+            windows.push(((x0.saturating_sub(1), y0.saturating_sub(1)),
+                          (usize::min(x1 + 2, width), usize::min(y1 + 2, height))));
+        }
+
+        windows
+    }
+
+    fn rebuild_cluster(&mut self, grid: &Grid<usize>, cluster: usize) {
+        for edges in &mut self.edges {
+            edges.retain(|e| e.cluster != cluster);
+        }
+
+        let chokes = self.cluster_chokes[cluster].clone();
+
+        for (i, first) in chokes.iter().enumerate() {
+            let others = &chokes[(i + 1)..];
+            if others.is_empty() {
+                continue;
+            }
+
+            let targets: Vec<(usize, usize)> = others.iter().map(|c| self.entrances[*c]).collect();
+            let distances = self.cluster_distances(grid, self.entrances[*first], cluster, &targets);
+
+            for (second, distance) in others.iter().zip(distances) {
+                if let Some(distance) = distance {
+                    self.edges[*first].push(Edge { target: *second,
+                                                   cluster,
+                                                   distance });
+                    self.edges[*second].push(Edge { target: *first,
+                                                    cluster,
+                                                    distance });
+                }
+            }
+        }
+    }
+
+    /// Walking distances from source to targets while staying inside the cluster and its chokes.
+    fn cluster_distances(&self,
+                         grid: &Grid<usize>,
+                         source: (usize, usize),
+                         cluster: usize,
+                         targets: &[(usize, usize)])
+                         -> Vec<Option<usize>> {
+        let api = NormalPosAPI();
+        let start = Pos(source.0, source.1);
+        let mut remaining: HashSet<(usize, usize)> = targets.iter().copied().collect();
+        remaining.remove(&source);
+
+        if grid[source] == 0 {
+            return targets.iter().map(|_| None).collect();
+        }
+
+        let allowed = |p: &Pos| {
+            let c = self.clusters[(p.0, p.1)];
+            c == cluster || c == NO_CLUSTER
+        };
+
+        let (reached, _) = dijkstra_partial(&start,
+                                            |p| {
+                                                api.successors(p, grid)
+                                                   .into_iter()
+                                                   .filter(|(n, _)| allowed(n))
+                                                   .collect::<ArrayVec<(Pos, usize), 8>>()
+                                            },
+                                            |p| {
+                                                remaining.remove(&(p.0, p.1));
+                                                remaining.is_empty()
+                                            });
+
+        targets.iter()
+               .map(|t| {
+                   if *t == source {
+                       Some(0)
+                   } else {
+                       reached.get(&Pos(t.0, t.1)).map(|v| v.1)
+                   }
+               })
+               .collect()
+    }
+
+    fn cluster_near(&self, position: (usize, usize)) -> Option<usize> {
+        let width = self.clusters.width();
+        let height = self.clusters.height();
+
+        if position.0 >= width || position.1 >= height {
+            return None;
+        }
+
+        let cluster = self.clusters[position];
+        if cluster != NO_CLUSTER {
+            return Some(cluster);
+        }
+
+        neighbours(position, width, height).into_iter()
+                                           .map(|n| self.clusters[n])
+                                           .find(|c| *c != NO_CLUSTER)
+    }
+}
+
+#[pymethods]
+impl Map {
+    /// Finds the chokes that a ground unit needs to pass through to get from start to end.
+    /// Only uses the abstract graph and returns indices to `chokes` and the estimated distance.
+    /// Returns an empty list and distance of -1 when the route could not be solved.
+    pub fn find_path_abstract(&self, start: (f32, f32), end: (f32, f32)) -> PathlibResult<(Vec<usize>, f32)> {
+        let start_int = round_point2(start);
+        let end_int = round_point2(end);
+        self.ground_pathing.check_path_query(start_int, end_int, None)?;
+
+        match self.abstract_graph.find_route(&self.ground_pathing.map, start_int, end_int) {
+            Some((route, distance)) => Ok((route, distance as f32 / MULTF32)),
+            None => Ok((Vec::new(), -1.0)),
+        }
+    }
+
+    /// Ground version of find_path that uses the abstract choke graph to split the path into shorter searches.
+    /// Falls back to a full path search when the abstract graph cannot solve the route.
+    /// Parameters are the same as in find_path.
+    pub fn find_path_hierarchical(&self,
+                                  start: (f32, f32),
+                                  end: (f32, f32),
+                                  large: bool,
+                                  influence: bool,
+                                  possible_heuristic: Option<u8>)
+                                  -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        let start_int = round_point2(start);
+        let end_int = round_point2(end);
+        let grid = &self.ground_pathing;
+        grid.check_path_query(start_int, end_int, None)?;
+
+        if let Some((route, _)) = self.abstract_graph.find_route(&grid.map, start_int, end_int) {
+            let mut waypoints = vec![start_int];
+            waypoints.extend(route.iter().map(|c| self.abstract_graph.entrance(*c)));
+            waypoints.push(end_int);
+
+            let windows = self.abstract_graph.route_windows(start_int, end_int, &route);
+            let mut path = vec![start_int];
+            let mut distance = 0f32;
+            let mut failed = false;
+
+            for (pair, window) in waypoints.windows(2).zip(windows) {
+                if pair[0] == pair[1] {
+                    continue;
+                }
+
+                // Only searches the cluster between the two waypoints instead of the whole map
+                let query = PathQuery { large,
+                                        influence,
+                                        possible_heuristic,
+                                        possible_window: Some(window),
+                                        ..PathQuery::new(pair[0], pair[1]) };
+                let segment = grid.find_path(query)?;
+                if segment.0.is_empty() {
+                    failed = true;
+                    break;
+                }
+
+                path.extend(segment.0.into_iter().skip(1));
+                distance += segment.1;
+            }
+
+            if !failed {
+                return Ok((path, distance));
+            }
+        }
+
+        let query = PathQuery { large,
+                                influence,
+                                possible_heuristic,
+                                ..PathQuery::new(start_int, end_int) };
+        grid.find_path(query)
+    }
+}
+
+fn merge_bounds(first: Bounds, second: Bounds) -> Bounds {
+    let ((x0, y0), (x1, y1)) = first;
+    let ((ox0, oy0), (ox1, oy1)) = second;
+    ((x0.min(ox0), y0.min(oy0)), (x1.max(ox1), y1.max(oy1)))
+}
+
+fn extend_bounds(bounds: &mut Option<Bounds>, position: (usize, usize)) {
+    let point = (position, position);
+    *bounds = Some(bounds.map_or(point, |b| merge_bounds(b, point)));
+}
+
+#[inline]
+fn is_cluster_cell(points: &Grid<map_point::MapPoint>, grid: &Grid<usize>, x: usize, y: usize) -> bool {
+    grid[(x, y)] > 0 && !points[(x, y)].is_choke
+}
+
+fn flood_fill_cluster(clusters: &mut Grid<usize>,
+                      points: &Grid<map_point::MapPoint>,
+                      grid: &Grid<usize>,
+                      x: usize,
+                      y: usize,
+                      cluster: usize) {
+    let width = grid.width();
+    let height = grid.height();
+    let mut stack = vec![(x, y)];
+    clusters[(x, y)] = cluster;
+
+    while let Some((cx, cy)) = stack.pop() {
+        let mut candidates = ArrayVec::<(usize, usize), 4>::new();
+        if cx > 0 {
+            candidates.push((cx - 1, cy));
+        }
+        if cy > 0 {
+            candidates.push((cx, cy - 1));
+        }
+        if cx + 1 < width {
+            candidates.push((cx + 1, cy));
+        }
+        if cy + 1 < height {
+            candidates.push((cx, cy + 1));
+        }
+
+        for (nx, ny) in candidates {
+            if clusters[(nx, ny)] == NO_CLUSTER && is_cluster_cell(points, grid, nx, ny) {
+                clusters[(nx, ny)] = cluster;
+                stack.push((nx, ny));
+            }
+        }
+    }
+}
+
+/// Walkable cell on the choke line that is closest to the center of the choke.
+fn choke_entrance(choke: &Choke, grid: &Grid<usize>) -> (usize, usize) {
+    let ((x0, y0), (x1, y1)) = choke.main_line;
+    let center = ((x0 + x1) * 0.5, (y0 + y1) * 0.5);
+    let center_int = round_point2(center);
+
+    if grid[center_int] > 0 {
+        return center_int;
+    }
+
+    let mut best = center_int;
+    let mut best_distance = f32::MAX;
+
+    for pixel in &choke.pixels {
+        if grid[*pixel] == 0 {
+            continue;
+        }
+
+        let dx = pixel.0 as f32 - center.0;
+        let dy = pixel.1 as f32 - center.1;
+        let d = dx * dx + dy * dy;
+
+        if d < best_distance {
+            best_distance = d;
+            best = *pixel;
+        }
+    }
+
+    best
+}
+
+fn neighbours(position: (usize, usize), width: usize, height: usize) -> ArrayVec<(usize, usize), 8> {
+    let mut result = ArrayVec::<(usize, usize), 8>::new();
+
+    for dx in -1i64..=1 {
+        for dy in -1i64..=1 {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+
+            let x = position.0 as i64 + dx;
+            let y = position.1 as i64 + dy;
+
+            if x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height {
+                result.push((x as usize, y as usize));
+            }
+        }
+    }
+
+    result
+}
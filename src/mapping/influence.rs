@@ -1,3 +1,4 @@
+use crate::error::PathlibResult;
 use crate::path_find::pos;
 use crate::path_find::{octile_distance, rectangle, PathFind};
 use pyo3::prelude::*;
@@ -22,19 +23,27 @@ impl Map {
         self.reaper_pathing.normalize_influence(value);
     }
 
-    pub fn add_influence_walk(&mut self, positions: Vec<(f32, f32)>, influence: f32, distance: f32) {
+    pub fn add_influence_walk(&mut self,
+                              positions: Vec<(f32, f32)>,
+                              influence: f32,
+                              distance: f32)
+                              -> PathlibResult<()> {
+        for position in &positions {
+            self.check_position((position.0.round() as usize, position.1.round() as usize))?;
+        }
+
         let mult = 1.0 / distance;
         let max_int = influence as usize;
         let mut maps = self.get_ground_influence_maps();
 
         for position_f in &positions {
             let position = (position_f.0.round() as usize, position_f.1.round() as usize);
-            if maps[0].map[position.0][position.1] == 0 {
+            if maps[0].map[position] == 0 {
                 continue;
             }
 
             let destinations = maps[0].find_destinations_in_inline(position, distance);
-            maps[0].map[position.0][position.1] += max_int;
+            maps[0].map[position] += max_int;
 
             for destination in destinations {
                 let end_point = destination.0;
@@ -43,11 +52,13 @@ impl Map {
 
                 if current_distance < distance {
                     for mapping in maps.iter_mut() {
-                        mapping.map[end_point.0][end_point.1] += value as usize
+                        mapping.map[end_point] += value as usize
                     }
                 }
             }
         }
+
+        Ok(())
     }
 
     pub fn add_influence_flat_hollow(&mut self, positions: Vec<(f32, f32)>, influence: f32, min: f32, max: f32) {
@@ -69,9 +80,9 @@ impl Map {
                     let d = octile_distance(position, (x, y)) as f32;
                     if d < mult_max && d > mult_min {
                         for mapping in maps.iter_mut() {
-                            let old_val = mapping.map[x][y];
+                            let old_val = mapping.map[(x, y)];
                             if old_val > 0 {
-                                mapping.map[x][y] = old_val + value;
+                                mapping.map[(x, y)] = old_val + value;
                             }
                         }
                     }
@@ -116,18 +127,18 @@ impl Map {
                     if d < mult_max {
                         if d < mult_min {
                             for mapping in maps.iter_mut() {
-                                let old_val = mapping.map[x][y];
+                                let old_val = mapping.map[(x, y)];
                                 if old_val > 0 {
-                                    mapping.map[x][y] = old_val + value;
+                                    mapping.map[(x, y)] = old_val + value;
                                 }
                             }
                         } else {
                             // Fading threshold
                             let value_fading = (influence * (1.0 - (d * mult - min) * mult2)) as usize;
                             for mapping in maps.iter_mut() {
-                                let old_val = mapping.map[x][y];
+                                let old_val = mapping.map[(x, y)];
                                 if old_val > 0 && value_fading > 0 {
-                                    mapping.map[x][y] = old_val + value_fading;
+                                    mapping.map[(x, y)] = old_val + value_fading;
                                 }
                             }
                         }
@@ -1,15 +1,33 @@
-use crate::{helpers::round_point2, path_find::PathFind};
+use crate::error::{self, PathlibError, PathlibResult};
+use crate::path_find::alternatives::AlternativePath;
+use crate::path_find::batch::{PathAndDistance, PathQuery};
+use crate::path_find::cooperative::{Agent, TimedPath};
+use crate::path_find::distance_field::DistanceField;
+use crate::path_find::influence_cap::InfluenceCap;
+use crate::path_find::line_of_sight::{self, RaycastResult};
+use crate::path_find::nearest::NearestGoal;
+use crate::path_find::obstacles::Obstacle;
+use crate::path_find::path_stats::PathStats;
+use crate::path_find::search::SearchBudget;
+use crate::path_find::planner::PathPlanner;
+use crate::helpers::grid::Grid;
+use crate::{helpers::round_point2, path_find::{CorrectedPath, PathAndStatus, PathFind}};
 use pyo3::prelude::*;
 
 // extern crate test;
 use std::collections::HashSet;
 
 use super::chokes::{group_chokes, Choke};
-use super::vision::{VisionMap, VisionUnit};
+use super::footprint::Footprint;
+use super::hierarchy::AbstractGraph;
+use super::placement::Placement;
+use super::ramps::{find_ramps, Ramp};
+use super::vision::{self, VisionMap, VisionUnit};
 use crate::mapping::chokes::solve_chokes;
 use crate::mapping::climb::modify_climb;
 use crate::mapping::map_point;
 use crate::mapping::map_point::Cliff;
+use crate::path_find::rectangle::Rectangle;
 
 const DIFFERENCE: usize = 16;
 const Y_MULT: usize = 1000000;
@@ -21,14 +39,17 @@ pub struct Map {
     pub air_pathing: PathFind,
     pub colossus_pathing: PathFind,
     pub reaper_pathing: PathFind,
-    pub points: Vec<Vec<map_point::MapPoint>>,
+    pub points: Grid<map_point::MapPoint>,
+    pub placement: Placement,
     pub overlord_spots: Vec<(f32, f32)>,
     #[pyo3(get, set)]
     pub influence_colossus_map: bool,
     #[pyo3(get, set)]
     pub influence_reaper_map: bool,
     pub chokes: Vec<Choke>,
+    pub ramps: Vec<Ramp>,
     pub vision_map: VisionMap,
+    pub abstract_graph: AbstractGraph,
 }
 
 #[pymethods]
@@ -41,21 +62,21 @@ impl Map {
               y_start: usize,
               x_end: usize,
               y_end: usize)
-              -> Self {
-        Map::new(pathing, placement, height_map, x_start, y_start, x_end, y_end)
+              -> PyResult<Self> {
+        Ok(Map::new(pathing, placement, height_map, x_start, y_start, x_end, y_end)?)
     }
 
     #[getter(ground_pathing)]
-    fn get_ground_pathing(&self) -> Vec<Vec<usize>> { self.ground_pathing.map.clone() }
+    fn get_ground_pathing(&self) -> Vec<Vec<usize>> { self.ground_pathing.map.to_vec() }
 
     #[getter(air_pathing)]
-    fn get_air_pathing(&self) -> Vec<Vec<usize>> { self.air_pathing.map.clone() }
+    fn get_air_pathing(&self) -> Vec<Vec<usize>> { self.air_pathing.map.to_vec() }
 
     #[getter(reaper_pathing)]
-    fn get_reaper_pathing(&self) -> Vec<Vec<usize>> { self.reaper_pathing.map.clone() }
+    fn get_reaper_pathing(&self) -> Vec<Vec<usize>> { self.reaper_pathing.map.to_vec() }
 
     #[getter(colossus_pathing)]
-    fn get_colossus_pathing(&self) -> Vec<Vec<usize>> { self.colossus_pathing.map.clone() }
+    fn get_colossus_pathing(&self) -> Vec<Vec<usize>> { self.colossus_pathing.map.to_vec() }
 
     #[getter(vision_map)]
     fn get_vision_map(&self) -> Vec<Vec<usize>> { self.vision_map.draw_vision() }
@@ -66,56 +87,59 @@ impl Map {
     #[getter(chokes)]
     pub fn get_chokes(&self) -> Vec<Choke> { self.chokes.clone() }
 
+    #[getter(ramps)]
+    pub fn get_ramps(&self) -> Vec<Ramp> { self.ramps.clone() }
+
     fn draw_climbs(&self) -> Vec<Vec<usize>> {
-        let width = self.ground_pathing.map.len();
-        let height = self.ground_pathing.map[0].len();
-        let mut walk_map = vec![vec![0; height]; width];
+        let width = self.ground_pathing.map.width();
+        let height = self.ground_pathing.map.height();
+        let mut walk_map = Grid::new(width, height, 0);
         let path = &self.ground_pathing.map;
 
         for x in 0..width {
             for y in 0..height {
-                if path[x][y] > 0 {
-                    if self.points[x][y].cliff_type == Cliff::High {
-                        walk_map[x][y] = 5;
-                    } else if self.points[x][y].cliff_type == Cliff::Both {
-                        walk_map[x][y] = 4;
-                    } else if self.points[x][y].cliff_type == Cliff::Low {
-                        walk_map[x][y] = 3;
+                if path[(x, y)] > 0 {
+                    if self.points[(x, y)].cliff_type == Cliff::High {
+                        walk_map[(x, y)] = 5;
+                    } else if self.points[(x, y)].cliff_type == Cliff::Both {
+                        walk_map[(x, y)] = 4;
+                    } else if self.points[(x, y)].cliff_type == Cliff::Low {
+                        walk_map[(x, y)] = 3;
                     } else {
-                        walk_map[x][y] = 2;
+                        walk_map[(x, y)] = 2;
                     }
-                } else if self.points[x][y].climbable {
-                    walk_map[x][y] = 1;
-                } else if self.points[x][y].overlord_spot {
-                    walk_map[x][y] = 6;
+                } else if self.points[(x, y)].climbable {
+                    walk_map[(x, y)] = 1;
+                } else if self.points[(x, y)].overlord_spot {
+                    walk_map[(x, y)] = 6;
                 }
             }
         }
 
-        walk_map
+        walk_map.to_vec()
     }
 
     fn draw_chokes(&self) -> Vec<Vec<usize>> {
-        let width = self.ground_pathing.map.len();
-        let height = self.ground_pathing.map[0].len();
-        let mut walk_map = vec![vec![0; height]; width];
+        let width = self.ground_pathing.map.width();
+        let height = self.ground_pathing.map.height();
+        let mut walk_map = Grid::new(width, height, 0);
 
         for x in 0..width {
             for y in 0..height {
-                let point = &self.points[x][y];
+                let point = &self.points[(x, y)];
                 if point.is_border {
                     if point.is_choke {
-                        walk_map[x][y] = 175;
+                        walk_map[(x, y)] = 175;
                     } else {
-                        walk_map[x][y] = 255;
+                        walk_map[(x, y)] = 255;
                     }
                 } else if point.is_choke {
-                    walk_map[x][y] = 100;
+                    walk_map[(x, y)] = 100;
                 }
             }
         }
 
-        walk_map
+        walk_map.to_vec()
     }
 
     /// Reset all mapping to their originals.
@@ -124,24 +148,73 @@ impl Map {
         self.air_pathing.reset_void();
         self.colossus_pathing.reset_void();
         self.reaper_pathing.reset_void();
+        self.placement.reset();
+        self.abstract_graph.rebuild(&self.ground_pathing.map);
+    }
+
+    /// Creates a block that is not pathable for ground units, returns its id for remove_block_by_id.
+    pub fn create_block(&mut self, center: (f32, f32), size: (usize, usize)) -> usize {
+        let (width, height) = (self.ground_pathing.width, self.ground_pathing.height);
+        let obstacle = Obstacle::rectangle(center, size, width, height);
+        let placement = obstacle.cells.clone();
+        self.add_obstacle(obstacle, placement, false)
     }
 
-    pub fn create_block(&mut self, center: (f32, f32), size: (usize, usize)) {
-        self.ground_pathing.create_block(center, size);
-        self.colossus_pathing.create_block(center, size);
-        self.reaper_pathing.create_block(center, size);
+    /// Creates a block with the pathing footprint of a building or resource, see `Footprint`.
+    /// Returns the id of the block for remove_block_by_id.
+    pub fn create_footprint(&mut self, center: (f32, f32), footprint: u8) -> PathlibResult<usize> {
+        let footprint = Footprint::from_u8(footprint)?;
+        let (width, height) = (self.ground_pathing.width, self.ground_pathing.height);
+        let cells = footprint.pathing_cells(center, width, height);
+        let placement = footprint.placement_cells(center, width, height);
+        let resource = matches!(footprint, Footprint::MineralField | Footprint::VespeneGeyser);
+
+        Ok(self.add_obstacle(Obstacle { center,
+                                        size: footprint.size(),
+                                        cells },
+                             placement,
+                             resource))
     }
 
-    pub fn create_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) {
-        self.ground_pathing.create_blocks_rust(&centers, size);
-        self.colossus_pathing.create_blocks_rust(&centers, size);
-        self.reaper_pathing.create_blocks_rust(&centers, size);
+    pub fn create_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) -> Vec<usize> {
+        centers.into_iter().map(|center| self.create_block(center, size)).collect()
+    }
+
+    /// Removes the block created with the id, cells under it get back the values they had before.
+    /// Cells that other blocks cover stay unpathable. Returns false if there is no block with the id.
+    pub fn remove_block_by_id(&mut self, id: usize) -> bool {
+        let ground = self.ground_pathing.remove_obstacle(id);
+        let colossus = self.colossus_pathing.remove_obstacle(id);
+        let reaper = self.reaper_pathing.remove_obstacle(id);
+        self.placement.remove(id);
+
+        match ground.or(colossus).or(reaper) {
+            Some(obstacle) => {
+                self.update_abstract_graph(&[obstacle.center], obstacle.size);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_block(&mut self, center: (f32, f32), size: (usize, usize)) {
+        self.ground_pathing.remove_block(center, size);
+        self.colossus_pathing.remove_block(center, size);
+        self.reaper_pathing.remove_block(center, size);
+        self.placement.remove_block(center, size);
+        self.update_abstract_graph(&[center], size);
     }
 
     pub fn remove_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) {
         self.ground_pathing.remove_blocks_rust(&centers, size);
         self.colossus_pathing.remove_blocks_rust(&centers, size);
         self.reaper_pathing.remove_blocks_rust(&centers, size);
+
+        for center in &centers {
+            self.placement.remove_block(*center, size);
+        }
+
+        self.update_abstract_graph(&centers, size);
     }
 
     pub fn get_borders(&self) -> Vec<(usize, usize)> {
@@ -149,7 +222,7 @@ impl Map {
 
         for x in 0..self.ground_pathing.width {
             for y in 0..self.ground_pathing.height {
-                if self.points[x][y].is_border {
+                if self.points[(x, y)].is_border {
                     result.push((x, y));
                 }
             }
@@ -159,68 +232,142 @@ impl Map {
     }
 
     /// Returns current influence value
-    fn current_influence(&self, map_type: u8, position: (f32, f32)) -> f32 {
-        let map = self.get_map(map_type);
+    fn current_influence(&self, map_type: u8, position: (f32, f32)) -> PathlibResult<f32> {
+        let map = self.get_map(map_type)?;
         let position_int = round_point2(position);
 
-        map.current_influence(position_int) as f32
+        Ok(map.current_influence(position_int)? as f32)
     }
 
     /// Finds the first reachable position within specified walking distance from the center point with lowest value
-    fn lowest_influence_walk(&self, map_type: u8, center: (f32, f32), distance: f32) -> ((usize, usize), f32) {
-        let map = self.get_map(map_type);
+    fn lowest_influence_walk(&self,
+                             map_type: u8,
+                             center: (f32, f32),
+                             distance: f32)
+                             -> PathlibResult<((usize, usize), f32)> {
+        let map = self.get_map(map_type)?;
         let center_int = round_point2(center);
 
         map.lowest_influence_walk(center_int, distance)
     }
 
     /// Finds the first reachable position within specified distance from the center point with lowest value
-    pub fn lowest_influence(&self, map_type: u8, center: (f32, f32), distance: usize) -> ((usize, usize), f32) {
-        let map = self.get_map(map_type);
-        map.inline_lowest_value(center, distance)
-    }
-
-    /// Find the shortest path and returns the path and distance.
-    /// Parameters:
-    ///     large:
-    ///         find path for a large unit
-    ///     influence:
-    ///         take influence into account
-    ///     possible_heuristic:
-    ///         distance heuristic for the A* algorithm
-    ///         0 - manhattan distance (default)
-    ///         1 - octile distance
-    ///         2 - euclidean distance
-    ///     possible_window:
-    ///         if set, restrict path to the given window of the map
-    ///     possible_distance_from_target:
-    ///         if set, short circuit when finding a position closer than the given distance from
-    ///         the target
-    pub fn find_path(&self,
-                     map_type: u8,
-                     start: (f32, f32),
-                     end: (f32, f32),
-                     large: bool,
-                     influence: bool,
-                     possible_heuristic: Option<u8>,
-                     possible_window: Option<((f32, f32), (f32, f32))>,
-                     possible_distance_from_target: Option<f32>)
-                     -> (Vec<(usize, usize)>, f32) {
-        let start_int = (start.0.round() as usize, start.1.round() as usize);
-        let end_int = (end.0.round() as usize, end.1.round() as usize);
-        let window_int = possible_window.map(|((x0, y0), (x1, y1))| {
-                                            ((x0.round() as usize, y0.round() as usize),
-                                             (x1.round() as usize, y1.round() as usize))
-                                        });
+    pub fn lowest_influence(&self,
+                            map_type: u8,
+                            center: (f32, f32),
+                            distance: usize)
+                            -> PathlibResult<((usize, usize), f32)> {
+        let map = self.get_map(map_type)?;
+        map.lowest_influence(center, distance)
+    }
 
-        let map = self.get_map(map_type);
-        map.find_path(start_int,
-                      end_int,
-                      large,
-                      influence,
-                      possible_heuristic,
-                      window_int,
-                      possible_distance_from_target)
+    /// Find the shortest path on the map of the given type and returns the path and distance.
+    /// See `PathFind::find_path` for the parameters, everything after influence can be left out
+    /// or given as keyword arguments.
+    #[pyo3(name = "find_path")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_args(&self,
+                          map_type: u8,
+                          start: (f32, f32),
+                          end: (f32, f32),
+                          large: bool,
+                          influence: bool,
+                          possible_heuristic: Option<u8>,
+                          possible_window: Option<((f32, f32), (f32, f32))>,
+                          possible_distance_from_target: Option<f32>,
+                          possible_radius: Option<f32>,
+                          possible_influence_cap: Option<InfluenceCap>)
+                          -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        let possible_window = possible_window.map(|(p0, p1)| (round_point2(p0), round_point2(p1)));
+        self.find_path(map_type,
+                       PathQuery { large,
+                                   influence,
+                                   possible_heuristic,
+                                   possible_window,
+                                   possible_distance_from_target,
+                                   possible_radius,
+                                   possible_influence_cap,
+                                   ..PathQuery::new(round_point2(start), round_point2(end)) })
+    }
+
+    /// Same as find_path, but also returns the start and end that the search used after auto correction.
+    #[pyo3(name = "find_path_corrected")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_corrected_args(&self,
+                                    map_type: u8,
+                                    start: (f32, f32),
+                                    end: (f32, f32),
+                                    large: bool,
+                                    influence: bool,
+                                    possible_heuristic: Option<u8>,
+                                    possible_window: Option<((f32, f32), (f32, f32))>,
+                                    possible_distance_from_target: Option<f32>,
+                                    possible_radius: Option<f32>,
+                                    possible_influence_cap: Option<InfluenceCap>)
+                                    -> PathlibResult<CorrectedPath> {
+        let possible_window = possible_window.map(|(p0, p1)| (round_point2(p0), round_point2(p1)));
+        self.find_path_corrected(map_type,
+                                 PathQuery { large,
+                                             influence,
+                                             possible_heuristic,
+                                             possible_window,
+                                             possible_distance_from_target,
+                                             possible_radius,
+                                             possible_influence_cap,
+                                             ..PathQuery::new(round_point2(start), round_point2(end)) })
+    }
+
+    /// Finds the shortest path and smooths it into straight segments that do not cross unpathable cells.
+    /// Returns the waypoints and the euclidean length of the smoothed path.
+    /// Parameters are the same as in find_path.
+    #[pyo3(name = "find_path_smooth")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_smooth_args(&self,
+                                 map_type: u8,
+                                 start: (f32, f32),
+                                 end: (f32, f32),
+                                 large: bool,
+                                 influence: bool,
+                                 possible_heuristic: Option<u8>,
+                                 possible_window: Option<((f32, f32), (f32, f32))>,
+                                 possible_distance_from_target: Option<f32>,
+                                 possible_radius: Option<f32>,
+                                 possible_influence_cap: Option<InfluenceCap>)
+                                 -> PathlibResult<(Vec<(f32, f32)>, f32)> {
+        let possible_window = possible_window.map(|(p0, p1)| (round_point2(p0), round_point2(p1)));
+        self.find_path_smooth(map_type,
+                              PathQuery { large,
+                                          influence,
+                                          possible_heuristic,
+                                          possible_window,
+                                          possible_distance_from_target,
+                                          possible_radius,
+                                          possible_influence_cap,
+                                          ..PathQuery::new(round_point2(start), round_point2(end)) })
+    }
+
+    /// Finds multiple paths in parallel with the GIL released.
+    /// Each query is a tuple of
+    /// (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap),
+    /// where everything after end is optional. Results are returned in the same order as the queries.
+    pub fn find_paths_batch(&self,
+                            py: Python,
+                            map_type: u8,
+                            queries: Vec<PathQuery>)
+                            -> PathlibResult<Vec<PathAndDistance>> {
+        let map = self.get_map(map_type)?;
+        py.allow_threads(|| map.find_paths_batch_rust(&queries))
+    }
+
+    /// Plans paths for the agents so that they don't walk into each other, see `PathFind::find_cooperative_paths`.
+    /// Each agent is a tuple of (start, goal, size, speed) where size and speed are optional.
+    pub fn find_cooperative_paths(&self,
+                                  map_type: u8,
+                                  agents: Vec<Agent>,
+                                  possible_budget: Option<SearchBudget>)
+                                  -> PathlibResult<Vec<TimedPath>> {
+        let map = self.get_map(map_type)?;
+        map.find_cooperative_paths(agents, possible_budget)
     }
 
     /// Basic version of find_path with all parameters except heuristic set to false or None.
@@ -229,32 +376,195 @@ impl Map {
                            start: (f32, f32),
                            end: (f32, f32),
                            possible_heuristic: Option<u8>)
-                           -> (Vec<(usize, usize)>, f32) {
+                           -> PathlibResult<(Vec<(usize, usize)>, f32)> {
         let start_int = (start.0.round() as usize, start.1.round() as usize);
         let end_int = (end.0.round() as usize, end.1.round() as usize);
 
-        let map = self.get_map(map_type);
+        let map = self.get_map(map_type)?;
         map.find_path_basic(start_int, end_int, possible_heuristic)
     }
 
+    /// Same as find_path with octile heuristic, but gives up when the budget runs out.
+    /// Returns the path, distance and status, see `PathStatus`.
+    pub fn find_path_budget(&self,
+                            map_type: u8,
+                            start: (f32, f32),
+                            end: (f32, f32),
+                            large: bool,
+                            influence: bool,
+                            budget: SearchBudget)
+                            -> PathlibResult<PathAndStatus> {
+        let map = self.get_map(map_type)?;
+        map.find_path_budget(round_point2(start), round_point2(end), large, influence, budget)
+    }
+
+    /// Finds up to k different paths from start to end, see `PathFind.find_alternative_paths`.
+    pub fn find_alternative_paths(&self,
+                                  map_type: u8,
+                                  start: (f32, f32),
+                                  end: (f32, f32),
+                                  k: usize,
+                                  overlap_penalty: f32,
+                                  influence: bool)
+                                  -> PathlibResult<Vec<AlternativePath>> {
+        let map = self.get_map(map_type)?;
+        map.find_alternative_paths(round_point2(start), round_point2(end), k, overlap_penalty, influence)
+    }
+
+    /// Finds the goal that can be reached first by walking, see `PathFind.find_nearest_goal`.
+    pub fn find_nearest_goal(&self,
+                             map_type: u8,
+                             start: (f32, f32),
+                             goals: Vec<(f32, f32)>,
+                             possible_weights: Option<Vec<f32>>,
+                             large: bool,
+                             influence: bool)
+                             -> PathlibResult<Option<NearestGoal>> {
+        let goals_int: Vec<(usize, usize)> = goals.into_iter().map(round_point2).collect();
+        let map = self.get_map(map_type)?;
+        map.find_nearest_goal(round_point2(start), goals_int, possible_weights, large, influence)
+    }
+
+    /// Calculates the danger along the path, see `PathStats`.
+    /// Seen cells are counted with the current vision map.
+    pub fn evaluate_path(&self, map_type: u8, path: Vec<(usize, usize)>, threshold: f32) -> PathlibResult<PathStats> {
+        let map = self.get_map(map_type)?;
+        map.evaluate_path_rust(&path, threshold, Some(&self.vision_map))
+    }
+
+    /// Calculates walking distances from every cell to the closest goal.
+    /// Units can then query next_step or direction from the field instead of finding their own path.
+    pub fn distance_field(&self,
+                          map_type: u8,
+                          goals: Vec<(f32, f32)>,
+                          large: bool,
+                          influence: bool,
+                          possible_radius: Option<f32>,
+                          possible_influence_cap: Option<InfluenceCap>)
+                          -> PathlibResult<DistanceField> {
+        let goals_int: Vec<(usize, usize)> = goals.into_iter().map(round_point2).collect();
+        let map = self.get_map(map_type)?;
+        map.distance_field(goals_int, large, influence, possible_radius, possible_influence_cap)
+    }
+
+    /// Finds a path away from the threats by following a safety map that avoids dead ends.
+    pub fn flee_path(&self,
+                     map_type: u8,
+                     start: (f32, f32),
+                     threats: Vec<(f32, f32)>,
+                     distance: f32)
+                     -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        let threats_int: Vec<(usize, usize)> = threats.into_iter().map(round_point2).collect();
+        let map = self.get_map(map_type)?;
+        map.flee_path(round_point2(start), threats_int, distance)
+    }
+
+    /// Sets how far positions are moved to the closest pathable cell in all map types.
+    pub fn set_auto_correct_distance(&mut self, distance: f32) -> PathlibResult<()> {
+        self.ground_pathing.set_auto_correct_distance(distance)?;
+        self.reaper_pathing.set_auto_correct_distance(distance)?;
+        self.colossus_pathing.set_auto_correct_distance(distance)?;
+        self.air_pathing.set_auto_correct_distance(distance)
+    }
+
+    /// Closest pathable cell in the map type at most max_distance away, see `PathFind::closest_pathable`.
+    pub fn closest_pathable(&self,
+                            map_type: u8,
+                            position: (f32, f32),
+                            possible_max_distance: Option<f32>)
+                            -> PathlibResult<Option<(usize, usize)>> {
+        let map = self.get_map(map_type)?;
+        map.closest_pathable(round_point2(position), possible_max_distance)
+    }
+
+    /// Id of the connected component of the position in the map type, None if it's not pathable.
+    pub fn component_id(&self, map_type: u8, position: (f32, f32)) -> PathlibResult<Option<usize>> {
+        let map = self.get_map(map_type)?;
+        map.component_id(round_point2(position))
+    }
+
+    /// Whether start can reach end at all in the map type, without doing a path search.
+    pub fn same_component(&self, map_type: u8, start: (f32, f32), end: (f32, f32)) -> PathlibResult<bool> {
+        let map = self.get_map(map_type)?;
+        map.same_component(round_point2(start), round_point2(end))
+    }
+
+    /// Creates a path planner that keeps the path from start to goal up to date with
+    /// incremental repairs, see update_planner.
+    pub fn create_planner(&self,
+                          map_type: u8,
+                          start: (f32, f32),
+                          goal: (f32, f32),
+                          large: bool,
+                          influence: bool)
+                          -> PathlibResult<PathPlanner> {
+        let map = self.get_map(map_type)?;
+        map.create_planner(round_point2(start), round_point2(goal), large, influence)
+    }
+
+    /// Repairs the path of the planner after the changed cells have been modified,
+    /// e.g. with create_block or remove_block.
+    pub fn update_planner(&self,
+                          map_type: u8,
+                          planner: PyRefMut<PathPlanner>,
+                          changed_cells: Vec<(usize, usize)>)
+                          -> PathlibResult<()> {
+        let map = self.get_map(map_type)?;
+        map.update_planner(planner, changed_cells)
+    }
+
     /// Finds a compromise where low influence matches with close position to the start position.
     fn find_low_inside_walk(&self,
                             map_type: u8,
                             start: (f32, f32),
                             target: (f32, f32),
                             distance: f32)
-                            -> ((f32, f32), f32) {
-        let map = self.get_map(map_type);
+                            -> PathlibResult<((f32, f32), f32)> {
+        let map = self.get_map(map_type)?;
         map.find_low_inside_walk(start, target, distance)
     }
 
     // Vision map calls
     pub fn clear_vision(&mut self) { self.vision_map.clear(); }
-    pub fn add_vision_unit(&mut self, unit: VisionUnit) { self.vision_map.add_unit(unit); }
+    pub fn add_vision_unit(&mut self, unit: VisionUnit) -> PathlibResult<()> { self.vision_map.add_unit(unit) }
     pub fn calculate_vision_map(&mut self) { self.vision_map.calculate_vision_map(&self.points); }
-    pub fn vision_status(&self, point: (f32, f32)) -> usize { self.vision_map.vision_status(point) }
+    pub fn vision_status(&self, point: (f32, f32)) -> PathlibResult<usize> { self.vision_map.vision_status(point) }
+
+    /// Checks whether units can walk straight from start to end, see `PathFind.raycast`.
+    pub fn raycast(&self,
+                   map_type: u8,
+                   start: (f32, f32),
+                   end: (f32, f32),
+                   include_cells: bool)
+                   -> PathlibResult<RaycastResult> {
+        let map = self.get_map(map_type)?;
+        map.raycast(round_point2(start), round_point2(end), include_cells)
+    }
 
-    pub fn add_influence_to_vision(&mut self, map_type: u8, seen_value: usize, detection_value: usize) {
+    /// Checks whether ground vision from start reaches end, terrain higher than what can be seen from the start
+    /// height blocks the vision. Returns whether the vision is clear, the first blocking cell and all traversed
+    /// cells up to the blocking cell when include_cells is set.
+    pub fn vision_raycast(&self,
+                          start: (f32, f32),
+                          end: (f32, f32),
+                          include_cells: bool)
+                          -> PathlibResult<RaycastResult> {
+        let start_int = round_point2(start);
+        let end_int = round_point2(end);
+        self.check_position(start_int)?;
+        self.check_position(end_int)?;
+
+        let max_height_seen = vision::max_height_seen(self.points[start_int].height);
+        Ok(line_of_sight::raycast(start_int, end_int, include_cells, |x, y| {
+               self.points[(x, y)].height > max_height_seen
+           }))
+    }
+
+    pub fn add_influence_to_vision(&mut self,
+                                   map_type: u8,
+                                   seen_value: usize,
+                                   detection_value: usize)
+                                   -> PathlibResult<()> {
         let vision_map = &self.vision_map; // self.get_vision();
         let map = {
             if map_type == 0 {
@@ -266,15 +576,33 @@ impl Map {
             } else if map_type == 3 {
                 &mut self.air_pathing
             } else {
-                panic!("Map type {} does not exist", map_type.to_string())
+                return Err(PathlibError::UnknownMapType(map_type));
             }
         };
 
-        map.add_influence_to_map_by_vision(&vision_map, seen_value, detection_value);
+        map.add_influence_to_map_by_vision(vision_map, seen_value, detection_value)
     }
 }
 
 impl Map {
+    /// Same as find_path from python, with the parameters in a `PathQuery`.
+    pub fn find_path(&self, map_type: u8, query: PathQuery) -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        let map = self.get_map(map_type)?;
+        map.find_path(query)
+    }
+
+    /// Same as find_path_corrected from python, with the parameters in a `PathQuery`.
+    pub fn find_path_corrected(&self, map_type: u8, query: PathQuery) -> PathlibResult<CorrectedPath> {
+        let map = self.get_map(map_type)?;
+        map.find_path_corrected(query)
+    }
+
+    /// Same as find_path_smooth from python, with the parameters in a `PathQuery`.
+    pub fn find_path_smooth(&self, map_type: u8, query: PathQuery) -> PathlibResult<(Vec<(f32, f32)>, f32)> {
+        let map = self.get_map(map_type)?;
+        map.find_path_smooth(query)
+    }
+
     pub fn new(pathing: Vec<Vec<usize>>,
                placement: Vec<Vec<usize>>,
                height_map: Vec<Vec<usize>>,
@@ -282,15 +610,40 @@ impl Map {
                y_start: usize,
                x_end: usize,
                y_end: usize)
-               -> Self {
-        let width = pathing.len();
-        let height = pathing[0].len();
-        let mut points = vec![vec![map_point::MapPoint::new(); height]; width];
-
-        let mut walk_map = vec![vec![0; height]; width];
-        let mut border_map = vec![vec![0; height]; width];
-        let mut fly_map = vec![vec![0; height]; width];
-        let mut reaper_map = vec![vec![0; height]; width];
+               -> PathlibResult<Self> {
+        error::check_grid(&pathing)?;
+        error::check_grid(&placement)?;
+        error::check_grid(&height_map)?;
+
+        if placement.len() != pathing.len()
+           || height_map.len() != pathing.len()
+           || placement[0].len() != pathing[0].len()
+           || height_map[0].len() != pathing[0].len()
+        {
+            let reason = "pathing, placement and height grids have different sizes".to_string();
+            return Err(PathlibError::InvalidGrid(reason));
+        }
+
+        // The playable area needs a border cell on every side
+        let playable_area = ((x_start, y_start), (x_end, y_end));
+        error::check_window(playable_area, pathing.len(), pathing[0].len())?;
+        if x_start == 0 || y_start == 0 || x_end >= pathing.len() || y_end >= pathing[0].len() {
+            return Err(PathlibError::InvalidWindow { window: playable_area,
+                                                     width: pathing.len(),
+                                                     height: pathing[0].len() });
+        }
+
+        let pathing = Grid::from(pathing);
+        let placement = Grid::from(placement);
+        let height_map = Grid::from(height_map);
+        let width = pathing.width();
+        let height = pathing.height();
+        let mut points = Grid::new(width, height, map_point::MapPoint::new());
+
+        let mut walk_map = Grid::new(width, height, 0);
+        let mut border_map = Grid::new(width, height, 0);
+        let mut fly_map = Grid::new(width, height, 0);
+        let mut reaper_map = Grid::new(width, height, 0);
         let mut overlord_spots: Vec<(f32, f32)> = Vec::new();
 
         let mut choke_lines = Vec::<((usize, usize), (usize, usize))>::new();
@@ -299,22 +652,22 @@ impl Map {
         // Pass 1
         for x in 0..width {
             for y in 0..height {
-                let walkable = pathing[x][y] > 0 || placement[x][y] > 0;
+                let walkable = pathing[(x, y)] > 0 || placement[(x, y)] > 0;
                 let pathable = x_start <= x && x <= x_end && y_start <= y && y <= y_end;
-                points[x][y].walkable = walkable;
-                points[x][y].pathable = pathable;
-                points[x][y].height = height_map[x][y];
+                points[(x, y)].walkable = walkable;
+                points[(x, y)].pathable = pathable;
+                points[(x, y)].height = height_map[(x, y)];
 
                 if pathable {
-                    fly_map[x][y] = 1;
+                    fly_map[(x, y)] = 1;
                 }
                 if walkable {
-                    walk_map[x][y] = 1;
-                    reaper_map[x][y] = 1;
+                    walk_map[(x, y)] = 1;
+                    reaper_map[(x, y)] = 1;
                 }
 
                 if x == x_left_border || x == x_end || y == y_top_border || y == y_end {
-                    border_map[x][y] = 1;
+                    border_map[(x, y)] = 1;
                 }
             }
         }
@@ -322,26 +675,26 @@ impl Map {
         // Pass 2
         for x in x_start..x_end {
             for y in y_start..y_end {
-                if !points[x][y].walkable {
-                    let h0 = points[x][y + 1].height;
-                    let h1 = points[x][y - 1].height;
-                    if (points[x][y].height >= h0 + DIFFERENCE && h0 > 0)
-                       || (points[x][y].height >= h1 + DIFFERENCE && h1 > 0)
+                if !points[(x, y)].walkable {
+                    let h0 = points[(x, y + 1)].height;
+                    let h1 = points[(x, y - 1)].height;
+                    if (points[(x, y)].height >= h0 + DIFFERENCE && h0 > 0)
+                       || (points[(x, y)].height >= h1 + DIFFERENCE && h1 > 0)
                     {
-                        points[x][y].overlord_spot = true;
+                        points[(x, y)].overlord_spot = true;
                     }
 
-                    if points[x + 1][y + 1].walkable
-                       || points[x - 1][y + 1].walkable
-                       || points[x + 1][y].walkable
-                       || points[x - 1][y].walkable
-                       || points[x + 1][y - 1].walkable
-                       || points[x - 1][y - 1].walkable
-                       || points[x][y + 1].walkable
-                       || points[x][y - 1].walkable
+                    if points[(x + 1, y + 1)].walkable
+                       || points[(x - 1, y + 1)].walkable
+                       || points[(x + 1, y)].walkable
+                       || points[(x - 1, y)].walkable
+                       || points[(x + 1, y - 1)].walkable
+                       || points[(x - 1, y - 1)].walkable
+                       || points[(x, y + 1)].walkable
+                       || points[(x, y - 1)].walkable
                     {
-                        points[x][y].is_border = true;
-                        border_map[x][y] = 1;
+                        points[(x, y)].is_border = true;
+                        border_map[(x, y)] = 1;
                     }
 
                     continue;
@@ -363,31 +716,31 @@ impl Map {
         for x in x_start..x_end {
             for y in y_start..y_end {
                 let point_hash = x + y * Y_MULT;
-                if points[x][y].climbable {
-                    points[x][y].climbable = points[x + 1][y].climbable
-                                             || points[x - 1][y].climbable
-                                             || points[x][y + 1].climbable
-                                             || points[x][y - 1].climbable;
-                    if points[x][y].climbable {
-                        reaper_map[x][y] = 1;
+                if points[(x, y)].climbable {
+                    points[(x, y)].climbable = points[(x + 1, y)].climbable
+                                             || points[(x - 1, y)].climbable
+                                             || points[(x, y + 1)].climbable
+                                             || points[(x, y - 1)].climbable;
+                    if points[(x, y)].climbable {
+                        reaper_map[(x, y)] = 1;
                     }
                 }
 
                 solve_chokes(&mut points, &border_pathing, &mut choke_lines, x, y, x_start, y_start, x_end, y_end);
 
-                let c = points[x][y].cliff_type;
+                let c = points[(x, y)].cliff_type;
 
                 if c != Cliff::None
-                   && points[x + 1][y].cliff_type != c
-                   && points[x - 1][y].cliff_type != c
-                   && points[x][y + 1].cliff_type != c
-                   && points[x][y - 1].cliff_type != c
+                   && points[(x + 1, y)].cliff_type != c
+                   && points[(x - 1, y)].cliff_type != c
+                   && points[(x, y + 1)].cliff_type != c
+                   && points[(x, y - 1)].cliff_type != c
                 {
-                    points[x][y].cliff_type = Cliff::None;
+                    points[(x, y)].cliff_type = Cliff::None;
                 }
 
-                if !set_handled_overlord_spots.contains(&point_hash) && points[x][y].overlord_spot {
-                    let target_height = points[x][y].height;
+                if !set_handled_overlord_spots.contains(&point_hash) && points[(x, y)].overlord_spot {
+                    let target_height = points[(x, y)].height;
                     let mut set: HashSet<usize> = HashSet::new();
 
                     if flood_fill_overlord(&mut points, x, y, target_height, true, &mut set) {
@@ -418,57 +771,85 @@ impl Map {
         let influence_colossus_map = false;
         let influence_reaper_map = false;
         let chokes = group_chokes(&mut choke_lines, &mut points);
+        let abstract_graph = AbstractGraph::new(&chokes, &points, &ground_pathing.map);
+        let ramps = find_ramps(&points, &pathing, &placement);
+        let placement = Placement::new(placement, &pathing);
+
+        Ok(Map { ground_pathing,
+                 air_pathing,
+                 colossus_pathing,
+                 reaper_pathing,
+                 points,
+                 placement,
+                 overlord_spots,
+                 influence_colossus_map,
+                 influence_reaper_map,
+                 chokes,
+                 ramps,
+                 vision_map,
+                 abstract_graph })
+    }
 
-        Map { ground_pathing,
-              air_pathing,
-              colossus_pathing,
-              reaper_pathing,
-              points,
-              overlord_spots,
-              influence_colossus_map,
-              influence_reaper_map,
-              chokes,
-              vision_map }
-    }
-
-    fn get_map(&self, map_type: u8) -> &PathFind {
-        if map_type == 0 {
-            return &self.ground_pathing;
-        }
-        if map_type == 1 {
-            return &self.reaper_pathing;
-        }
-        if map_type == 2 {
-            return &self.colossus_pathing;
-        }
-        if map_type == 3 {
-            return &self.air_pathing;
+    /// Adds the obstacle to the ground, colossus and reaper maps with the same id
+    /// and removes the placement under the placement cells.
+    fn add_obstacle(&mut self, obstacle: Obstacle, placement: Vec<(usize, usize)>, resource: bool) -> usize {
+        let id = self.ground_pathing
+                     .next_obstacle_id()
+                     .max(self.colossus_pathing.next_obstacle_id())
+                     .max(self.reaper_pathing.next_obstacle_id())
+                     .max(self.placement.next_obstacle_id());
+        let (center, size) = (obstacle.center, obstacle.size);
+        self.placement.add(id,
+                           Obstacle { center,
+                                      size,
+                                      cells: placement },
+                           resource);
+
+        self.colossus_pathing.add_obstacle(id, obstacle.clone());
+        self.reaper_pathing.add_obstacle(id, obstacle.clone());
+        self.ground_pathing.add_obstacle(id, obstacle);
+        self.update_abstract_graph(&[center], size);
+        id
+    }
+
+    fn update_abstract_graph(&mut self, centers: &[(f32, f32)], size: (usize, usize)) {
+        let width = self.ground_pathing.width;
+        let height = self.ground_pathing.height;
+
+        for center in centers {
+            let rect = Rectangle::init_from_center(*center, size, width, height);
+            self.abstract_graph.update_area(&self.ground_pathing.map, &rect);
         }
+    }
 
-        panic!("Map type {} does not exist", map_type.to_string());
+    fn get_map(&self, map_type: u8) -> PathlibResult<&PathFind> {
+        match map_type {
+            0 => Ok(&self.ground_pathing),
+            1 => Ok(&self.reaper_pathing),
+            2 => Ok(&self.colossus_pathing),
+            3 => Ok(&self.air_pathing),
+            _ => Err(PathlibError::UnknownMapType(map_type)),
+        }
     }
 
     fn get_vision(&mut self) -> &mut VisionMap { return &mut self.vision_map; }
 
-    pub fn get_map_mut(&mut self, map_type: u8) -> &mut PathFind {
-        if map_type == 0 {
-            return &mut self.ground_pathing;
-        }
-        if map_type == 1 {
-            return &mut self.reaper_pathing;
-        }
-        if map_type == 2 {
-            return &mut self.colossus_pathing;
-        }
-        if map_type == 3 {
-            return &mut self.air_pathing;
+    pub fn get_map_mut(&mut self, map_type: u8) -> PathlibResult<&mut PathFind> {
+        match map_type {
+            0 => Ok(&mut self.ground_pathing),
+            1 => Ok(&mut self.reaper_pathing),
+            2 => Ok(&mut self.colossus_pathing),
+            3 => Ok(&mut self.air_pathing),
+            _ => Err(PathlibError::UnknownMapType(map_type)),
         }
+    }
 
-        panic!("Map type {} does not exist", map_type.to_string());
+    pub fn check_position(&self, position: (usize, usize)) -> PathlibResult<()> {
+        error::check_position(position, self.points.width(), self.points.height())
     }
 }
 
-fn flood_fill_overlord(points: &mut Vec<Vec<map_point::MapPoint>>,
+fn flood_fill_overlord(points: &mut Grid<map_point::MapPoint>,
                        x: usize,
                        y: usize,
                        target_height: usize,
@@ -482,9 +863,9 @@ fn flood_fill_overlord(points: &mut Vec<Vec<map_point::MapPoint>>,
 
     set.insert(key);
 
-    if target_height != points[x][y].height {
+    if target_height != points[(x, y)].height {
         // Height difference must be at least 16 below target
-        if target_height < points[x][y].height + DIFFERENCE {
+        if target_height < points[(x, y)].height + DIFFERENCE {
             return false;
         }
 
@@ -492,7 +873,7 @@ fn flood_fill_overlord(points: &mut Vec<Vec<map_point::MapPoint>>,
     }
 
     let mut result = true;
-    points[x][y].overlord_spot = replacement;
+    points[(x, y)].overlord_spot = replacement;
 
     if y > 0 {
         result &= flood_fill_overlord(points, x, ((y as u32) - 1) as usize, target_height, replacement, set);
@@ -500,10 +881,10 @@ fn flood_fill_overlord(points: &mut Vec<Vec<map_point::MapPoint>>,
     if x > 0 {
         result &= flood_fill_overlord(points, ((x as u32) - 1) as usize, y, target_height, replacement, set);
     }
-    if y < points[0].len() - 1 {
+    if y < points.height() - 1 {
         result &= flood_fill_overlord(points, x, y + 1, target_height, replacement, set);
     }
-    if x < points.len() - 1 {
+    if x < points.width() - 1 {
         result &= flood_fill_overlord(points, x + 1, y, target_height, replacement, set);
     }
 
@@ -1,8 +1,13 @@
 mod chokes;
 mod climb;
+pub mod footprint;
 pub mod connections;
+pub mod hierarchy;
 pub mod influence;
 pub mod map;
 pub mod map_point;
+pub mod placement;
+pub mod ramps;
 pub mod vision;
+pub mod wall;
 pub mod zones;
@@ -0,0 +1,214 @@
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::error::{self, PathlibError, PathlibResult};
+use crate::helpers::grid::Grid;
+use crate::helpers::round_point2;
+use crate::mapping::footprint::Footprint;
+use crate::mapping::map::Map;
+use crate::path_find::obstacles::{Obstacle, Obstacles};
+use crate::path_find::rectangle::Rectangle;
+
+/// Buildings can't have cells this close to minerals and geysers, same as the town hall rule of the game.
+const MINERAL_LINE_DISTANCE: usize = 3;
+/// Buildings keep this many cells free between them and a ramp.
+const RAMP_MARGIN: usize = 1;
+/// Buildings whose center is this close to a pylon are powered.
+const PYLON_POWER_RADIUS: f32 = 6.5;
+
+/// Placement grid of the map, kept up to date with the blocks.
+#[derive(Clone)]
+pub struct Placement {
+    /// Cells where something can be built right now, 0 where nothing can be built.
+    grid: Grid<usize>,
+    original: Grid<usize>,
+    obstacles: Obstacles,
+    /// Cells of the minerals and geysers created with create_footprint by block id.
+    resources: HashMap<usize, Vec<(usize, usize)>>,
+    /// Number of resource cells close enough to each cell to block building there.
+    near_resources: Grid<usize>,
+    /// Cells close to walkable cells without placement, such as ramps.
+    near_ramp: Grid<bool>,
+}
+
+impl Placement {
+    pub fn new(placement: Grid<usize>, pathing: &Grid<usize>) -> Self {
+        let (width, height) = (placement.width(), placement.height());
+        let mut near_ramp = Grid::new(width, height, false);
+
+        for x in 0..width {
+            for y in 0..height {
+                if pathing[(x, y)] > 0 && placement[(x, y)] == 0 {
+                    for (nx, ny) in square(x, y, RAMP_MARGIN, width, height) {
+                        near_ramp[(nx, ny)] = true;
+                    }
+                }
+            }
+        }
+
+        Placement { grid: placement.clone(),
+                    original: placement,
+                    obstacles: Obstacles::new(width, height),
+                    resources: HashMap::new(),
+                    near_resources: Grid::new(width, height, 0),
+                    near_ramp }
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize { self.grid.width() }
+
+    #[inline]
+    pub fn height(&self) -> usize { self.grid.height() }
+
+    #[inline]
+    pub fn is_buildable(&self, position: (usize, usize)) -> bool { self.grid[position] > 0 }
+
+    #[inline]
+    pub fn next_obstacle_id(&self) -> usize { self.obstacles.next_id() }
+
+    /// Current placement grid in the python layout.
+    pub fn to_vec(&self) -> Vec<Vec<usize>> { self.grid.to_vec() }
+
+    /// Removes the placement under the cells of the obstacle, resources also block the mineral line around them.
+    pub fn add(&mut self, id: usize, obstacle: Obstacle, resource: bool) {
+        if resource {
+            self.change_near_resources(&obstacle.cells, true);
+            self.resources.insert(id, obstacle.cells.clone());
+        }
+
+        self.obstacles.add(id, obstacle, &mut self.grid);
+    }
+
+    /// Removes the obstacle created with the id, cells no other obstacle covers get their placement back.
+    pub fn remove(&mut self, id: usize) {
+        if let Some(cells) = self.resources.remove(&id) {
+            self.change_near_resources(&cells, false);
+        }
+
+        self.obstacles.remove(id, &mut self.grid);
+    }
+
+    /// Removes a block by its center and size, see `PathFind::remove_block`.
+    pub fn remove_block(&mut self, center: (f32, f32), size: (usize, usize)) {
+        if let Some(id) = self.obstacles.find(center, size) {
+            self.remove(id);
+            return;
+        }
+
+        let rect = Rectangle::init_from_center(center, size, self.width(), self.height());
+
+        for x in rect.x..rect.x_end {
+            for y in rect.y..rect.y_end {
+                if self.obstacles.uncover((x, y)).is_some() {
+                    self.grid[(x, y)] = self.original[(x, y)];
+                }
+            }
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.grid.copy_from(&self.original);
+        self.obstacles.clear();
+        self.resources.clear();
+        self.near_resources.fill(0);
+    }
+
+    fn change_near_resources(&mut self, cells: &[(usize, usize)], add: bool) {
+        let (width, height) = (self.width(), self.height());
+
+        for cell in cells {
+            for position in square(cell.0, cell.1, MINERAL_LINE_DISTANCE, width, height) {
+                if add {
+                    self.near_resources[position] += 1;
+                } else {
+                    self.near_resources[position] -= 1;
+                }
+            }
+        }
+    }
+}
+
+/// Cells at most the distance away from the cell on both axes.
+fn square(x: usize, y: usize, distance: usize, width: usize, height: usize) -> impl Iterator<Item = (usize, usize)> {
+    let (x_start, x_end) = (x.saturating_sub(distance), usize::min(x + distance + 1, width));
+    let (y_start, y_end) = (y.saturating_sub(distance), usize::min(y + distance + 1, height));
+    (x_start..x_end).flat_map(move |nx| (y_start..y_end).map(move |ny| (nx, ny)))
+}
+
+#[pymethods]
+impl Map {
+    /// Current placement grid, blocks and footprints remove placement under them.
+    #[getter(placement)]
+    fn get_placement(&self) -> Vec<Vec<usize>> { self.placement.to_vec() }
+
+    /// Finds the closest legal position for the footprint to the point, None if there is none within max distance.
+    /// Placements next to ramps and in mineral lines are skipped, minerals and geysers
+    /// are known from footprints created with create_footprint.
+    /// With pylons the center of the building needs to be in the power field of one of them,
+    /// with a creep grid all cells of the building need creep.
+    pub fn find_placement(&self,
+                          near: (f32, f32),
+                          footprint: u8,
+                          max_distance: f32,
+                          pylons: Option<Vec<(f32, f32)>>,
+                          creep: Option<Vec<Vec<usize>>>)
+                          -> PathlibResult<Option<(f32, f32)>> {
+        self.check_position(round_point2(near))?;
+        let footprint = Footprint::from_u8(footprint)?;
+        let (width, height) = (self.placement.width(), self.placement.height());
+
+        let creep = match creep {
+            Some(creep) => {
+                error::check_grid(&creep)?;
+                if creep.len() != width || creep[0].len() != height {
+                    return Err(PathlibError::InvalidGrid("creep grid has a different size than the map".to_string()));
+                }
+                Some(Grid::from(creep))
+            }
+            None => None,
+        };
+
+        let (size_x, size_y) = footprint.size();
+        let x_start = (near.0 - max_distance).floor().max(0.0) as usize;
+        let y_start = (near.1 - max_distance).floor().max(0.0) as usize;
+        let x_end = usize::min((near.0 + max_distance).ceil().max(0.0) as usize, width);
+        let y_end = usize::min((near.1 + max_distance).ceil().max(0.0) as usize, height);
+        let mut best: Option<((f32, f32), f32)> = None;
+
+        for x in x_start..x_end {
+            for y in y_start..y_end {
+                let center = (x as f32 + size_x as f32 / 2.0, y as f32 + size_y as f32 / 2.0);
+                let distance = ((center.0 - near.0).powi(2) + (center.1 - near.1).powi(2)).sqrt();
+
+                if distance > max_distance || best.is_some_and(|(_, best_distance)| best_distance <= distance) {
+                    continue;
+                }
+
+                if let Some(pylons) = &pylons {
+                    let powered = pylons.iter().any(|pylon| {
+                                                   (pylon.0 - center.0).powi(2) + (pylon.1 - center.1).powi(2)
+                                                   <= PYLON_POWER_RADIUS * PYLON_POWER_RADIUS
+                                               });
+                    if !powered {
+                        continue;
+                    }
+                }
+
+                let cells = footprint.placement_cells(center, width, height);
+                let legal = cells.len() == size_x * size_y
+                            && cells.iter().all(|cell| {
+                                               self.placement.is_buildable(*cell)
+                                               && self.placement.near_resources[*cell] == 0
+                                               && !self.placement.near_ramp[*cell]
+                                               && creep.as_ref().is_none_or(|creep| creep[*cell] > 0)
+                                           });
+
+                if legal {
+                    best = Some((center, distance));
+                }
+            }
+        }
+
+        Ok(best.map(|(center, _)| center))
+    }
+}
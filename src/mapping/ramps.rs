@@ -0,0 +1,232 @@
+use pyo3::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+
+use crate::helpers::grid::Grid;
+use crate::mapping::map_point::MapPoint;
+
+/// Neighbouring cells of the same ramp or level differ at most this much in height.
+const MAX_STEP: usize = 4;
+/// Ramps connect levels that differ at least this much in height.
+const MIN_LEVEL_DIFFERENCE: usize = 8;
+const MIN_RAMP_CELLS: usize = 8;
+/// Levels smaller than this are not main bases even when a single ramp leads to them.
+const MIN_MAIN_BASE_AREA: usize = 400;
+const NO_REGION: usize = usize::MAX;
+
+/// Walkable slope between two height levels, cells are walkable but have no placement.
+#[pyclass]
+#[derive(Clone)]
+pub struct Ramp {
+    pub cells: Vec<(usize, usize)>,
+    /// Cells of the ramp next to the upper level.
+    pub upper: Vec<(usize, usize)>,
+    /// Cells of the ramp next to the lower level.
+    pub lower: Vec<(usize, usize)>,
+    pub top_center: (f32, f32),
+    pub bottom_center: (f32, f32),
+    /// Unit vector from the bottom center towards the top center.
+    pub direction: (f32, f32),
+    /// Width of the ramp across the direction in cells.
+    pub width: f32,
+    /// Zones of the upper and lower level, 0 until zones are calculated.
+    pub zones: (i8, i8),
+    /// The upper level is a main base, a large area that this ramp is the only way to.
+    pub is_main: bool,
+    /// Cells of the upper and lower level next to the ramp.
+    upper_border: Vec<(usize, usize)>,
+    lower_border: Vec<(usize, usize)>,
+}
+
+#[pymethods]
+impl Ramp {
+    #[getter(cells)]
+    fn get_cells(&self) -> Vec<(usize, usize)> { self.cells.clone() }
+
+    #[getter(upper)]
+    fn get_upper(&self) -> Vec<(usize, usize)> { self.upper.clone() }
+
+    #[getter(lower)]
+    fn get_lower(&self) -> Vec<(usize, usize)> { self.lower.clone() }
+
+    #[getter(top_center)]
+    fn get_top_center(&self) -> (f32, f32) { self.top_center }
+
+    #[getter(bottom_center)]
+    fn get_bottom_center(&self) -> (f32, f32) { self.bottom_center }
+
+    #[getter(direction)]
+    fn get_direction(&self) -> (f32, f32) { self.direction }
+
+    #[getter(width)]
+    fn get_width(&self) -> f32 { self.width }
+
+    #[getter(zones)]
+    fn get_zones(&self) -> (i8, i8) { self.zones }
+
+    #[getter(is_main)]
+    fn get_is_main(&self) -> bool { self.is_main }
+}
+
+impl Ramp {
+    /// Takes the zones of the upper and lower level from the points, see `Map::calculate_zones`.
+    pub fn update_zones(&mut self, points: &Grid<MapPoint>) {
+        self.zones = (most_common_zone(&self.upper_border, points), most_common_zone(&self.lower_border, points));
+    }
+
+    fn new(cells: Vec<(usize, usize)>, points: &Grid<MapPoint>) -> Option<Self> {
+        let min_height = cells.iter().map(|p| points[*p].height).min()?;
+        let max_height = cells.iter().map(|p| points[*p].height).max()?;
+
+        if cells.len() < MIN_RAMP_CELLS || max_height - min_height < MIN_LEVEL_DIFFERENCE {
+            return None;
+        }
+
+        let ramp_cells: HashSet<(usize, usize)> = cells.iter().copied().collect();
+        let middle = (min_height + max_height) / 2;
+        let (mut upper, mut lower) = (Vec::new(), Vec::new());
+        let (mut upper_border, mut lower_border) = (HashSet::new(), HashSet::new());
+
+        for cell in &cells {
+            for next in neighbours(*cell, points) {
+                if ramp_cells.contains(&next) || !points[next].walkable || !is_step(*cell, next, points) {
+                    continue;
+                }
+
+                if points[next].height >= middle {
+                    upper.push(*cell);
+                    upper_border.insert(next);
+                } else {
+                    lower.push(*cell);
+                    lower_border.insert(next);
+                }
+            }
+        }
+
+        if upper.is_empty() || lower.is_empty() {
+            return None;
+        }
+
+        upper.sort_unstable();
+        upper.dedup();
+        lower.sort_unstable();
+        lower.dedup();
+
+        let top_center = center(&upper);
+        let bottom_center = center(&lower);
+        let (dx, dy) = (top_center.0 - bottom_center.0, top_center.1 - bottom_center.1);
+        let length = (dx * dx + dy * dy).sqrt().max(f32::EPSILON);
+        let direction = (dx / length, dy / length);
+
+        // Spread of the cells across the direction
+        let across = cells.iter().map(|(x, y)| -direction.1 * *x as f32 + direction.0 * *y as f32);
+        let (low, high) = across.fold((f32::MAX, f32::MIN), |(low, high), d| (low.min(d), high.max(d)));
+
+        Some(Ramp { cells,
+                    upper,
+                    lower,
+                    top_center,
+                    bottom_center,
+                    direction,
+                    width: high - low + 1.0,
+                    zones: (0, 0),
+                    is_main: false,
+                    upper_border: upper_border.into_iter().collect(),
+                    lower_border: lower_border.into_iter().collect() })
+    }
+}
+
+/// Finds ramps from cells that are pathable but have no placement and whose height changes in small steps.
+pub fn find_ramps(points: &Grid<MapPoint>, pathing: &Grid<usize>, placement: &Grid<usize>) -> Vec<Ramp> {
+    let (_, candidates) = regions(points, |p| pathing[p] > 0 && placement[p] == 0);
+    let mut ramps: Vec<Ramp> = candidates.into_iter().filter_map(|cells| Ramp::new(cells, points)).collect();
+
+    let ramp_cells: HashSet<(usize, usize)> = ramps.iter().flat_map(|ramp| ramp.cells.iter().copied()).collect();
+    let (labels, levels) = regions(points, |p| points[p].walkable && !ramp_cells.contains(&p));
+
+    // Ramps leading to each level
+    let mut level_ramps: HashMap<usize, HashSet<usize>> = HashMap::new();
+
+    for (index, ramp) in ramps.iter().enumerate() {
+        for cell in ramp.upper_border.iter().chain(ramp.lower_border.iter()) {
+            level_ramps.entry(labels[*cell]).or_default().insert(index);
+        }
+    }
+
+    for (index, ramp) in ramps.iter_mut().enumerate() {
+        let upper_levels: HashSet<usize> = ramp.upper_border.iter().map(|cell| labels[*cell]).collect();
+        let area: usize = upper_levels.iter().map(|level| levels[*level].len()).sum();
+        let only_ramp = upper_levels.iter()
+                                    .all(|level| level_ramps[level].iter().all(|other| *other == index));
+
+        ramp.is_main = only_ramp && area >= MIN_MAIN_BASE_AREA;
+    }
+
+    ramps
+}
+
+/// Connected regions of the included cells where neighbours differ at most `MAX_STEP` in height.
+/// Returns the region index of each cell and the cells of each region.
+fn regions<F>(points: &Grid<MapPoint>, include: F) -> (Grid<usize>, Vec<Vec<(usize, usize)>>)
+    where F: Fn((usize, usize)) -> bool {
+    let mut labels = Grid::new(points.width(), points.height(), NO_REGION);
+    let mut result = Vec::new();
+
+    for x in 0..points.width() {
+        for y in 0..points.height() {
+            if labels[(x, y)] != NO_REGION || !include((x, y)) {
+                continue;
+            }
+
+            let label = result.len();
+            let mut cells = Vec::new();
+            let mut stack = vec![(x, y)];
+            labels[(x, y)] = label;
+
+            while let Some(cell) = stack.pop() {
+                cells.push(cell);
+
+                for next in neighbours(cell, points) {
+                    if labels[next] == NO_REGION && include(next) && is_step(cell, next, points) {
+                        labels[next] = label;
+                        stack.push(next);
+                    }
+                }
+            }
+
+            result.push(cells);
+        }
+    }
+
+    (labels, result)
+}
+
+/// 4 neighbours of the cell inside the grid.
+fn neighbours((x, y): (usize, usize), points: &Grid<MapPoint>) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let candidates = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+    (0..4).map(move |i| candidates[i]).filter(move |(nx, ny)| points.in_bounds(*nx, *ny))
+}
+
+#[inline]
+fn is_step(a: (usize, usize), b: (usize, usize), points: &Grid<MapPoint>) -> bool {
+    points[a].height.abs_diff(points[b].height) <= MAX_STEP
+}
+
+fn center(cells: &[(usize, usize)]) -> (f32, f32) {
+    let count = cells.len() as f32;
+    let (sum_x, sum_y) = cells.iter().fold((0.0, 0.0), |(sx, sy), (x, y)| (sx + *x as f32, sy + *y as f32));
+    (sum_x / count + 0.5, sum_y / count + 0.5)
+}
+
+fn most_common_zone(cells: &[(usize, usize)], points: &Grid<MapPoint>) -> i8 {
+    let mut counts: HashMap<i8, usize> = HashMap::new();
+
+    for cell in cells {
+        let zone = points[*cell].zone_index;
+        if zone > 0 {
+            *counts.entry(zone).or_default() += 1;
+        }
+    }
+
+    counts.into_iter().max_by_key(|(zone, count)| (*count, Reverse(*zone))).map_or(0, |(zone, _)| zone)
+}
@@ -1,7 +1,8 @@
 use pyo3::prelude::*;
 
 use crate::{
-    helpers::round_point2,
+    error::{self, PathlibResult},
+    helpers::{grid::Grid, round_point2},
     path_find::{octile_distance_f32, rectangle},
 };
 
@@ -37,7 +38,7 @@ impl VisionUnit {
 #[pyclass]
 pub struct VisionMap {
     units: Vec<VisionUnit>,
-    pub points: Vec<Vec<VisionStatus>>,
+    pub points: Grid<VisionStatus>,
     width: usize,
     height: usize,
 }
@@ -47,22 +48,24 @@ impl VisionMap {
     pub fn clear(&mut self) {
         self.units.clear();
 
-        for y in &mut self.points {
-            for x in y {
-                *x = VisionStatus::NotSeen;
-            }
-        }
+        self.points.fill(VisionStatus::NotSeen);
     }
 
-    pub fn add_unit(&mut self, unit: VisionUnit) { self.units.push(unit); }
+    pub fn add_unit(&mut self, unit: VisionUnit) -> PathlibResult<()> {
+        error::check_position(round_point2(unit.position), self.width, self.height)?;
+        self.units.push(unit);
+        Ok(())
+    }
 
-    pub fn vision_status(&self, position: (f32, f32)) -> usize {
+    pub fn vision_status(&self, position: (f32, f32)) -> PathlibResult<usize> {
         let int_point = round_point2(position);
-        match self.points[int_point.0][int_point.1] {
-            VisionStatus::NotSeen => 0,
-            VisionStatus::NotSeenButDetected => 0,
-            VisionStatus::Seen => 1,
-            VisionStatus::Detected => 2,
+        error::check_position(int_point, self.width, self.height)?;
+
+        match self.points[int_point] {
+            VisionStatus::NotSeen => Ok(0),
+            VisionStatus::NotSeenButDetected => Ok(0),
+            VisionStatus::Seen => Ok(1),
+            VisionStatus::Detected => Ok(2),
         }
     }
 }
@@ -70,9 +73,9 @@ impl VisionMap {
 impl VisionMap {
     pub fn new_internal(width: usize, height: usize) -> Self {
         let units = vec![];
-        let points = vec![vec![VisionStatus::NotSeen; height]; width];
-        let width = points.len();
-        let height = points[0].len();
+        let points = Grid::new(width, height, VisionStatus::NotSeen);
+        let width = points.width();
+        let height = points.height();
 
         VisionMap { units,
                     points,
@@ -80,7 +83,7 @@ impl VisionMap {
                     height }
     }
 
-    pub fn calculate_vision_map(&mut self, map_points: &Vec<Vec<map_point::MapPoint>>) {
+    pub fn calculate_vision_map(&mut self, map_points: &Grid<map_point::MapPoint>) {
         for unit in self.units.iter() {
             if unit.flying {
                 if unit.detector {
@@ -99,28 +102,39 @@ impl VisionMap {
     }
 
     pub fn draw_vision(&self) -> Vec<Vec<usize>> {
-        let mut vision_map = vec![vec![0; self.height]; self.width];
+        let mut vision_map = Grid::new(self.width, self.height, 0);
 
         for x in 0..self.width {
             for y in 0..self.height {
-                match self.points[x][y] {
-                    VisionStatus::NotSeen => vision_map[x][y] = 0,
-                    VisionStatus::NotSeenButDetected => vision_map[x][y] = 0,
-                    VisionStatus::Seen => vision_map[x][y] = 1,
-                    VisionStatus::Detected => vision_map[x][y] = 2,
+                match self.points[(x, y)] {
+                    VisionStatus::NotSeen => vision_map[(x, y)] = 0,
+                    VisionStatus::NotSeenButDetected => vision_map[(x, y)] = 0,
+                    VisionStatus::Seen => vision_map[(x, y)] = 1,
+                    VisionStatus::Detected => vision_map[(x, y)] = 2,
                 }
             }
         }
 
-        vision_map
+        vision_map.to_vec()
+    }
+}
+
+/// Highest terrain that ground vision from the height can see, higher cells block the vision.
+pub fn max_height_seen(height: usize) -> usize {
+    let mut max_height_seen = height / 8 * 8 + 7;
+
+    if height % 8 >= 4 {
+        max_height_seen += 8;
     }
+
+    max_height_seen
 }
 
-fn set_detection(points: &mut Vec<Vec<VisionStatus>>, position: &(f32, f32), sight_range: f32) {
+fn set_detection(points: &mut Grid<VisionStatus>, position: &(f32, f32), sight_range: f32) {
     let u_position = round_point2(*position);
     let size = ((sight_range * 2f32) as usize, (sight_range * 2f32) as usize);
-    let width = points.len();
-    let height = points[0].len();
+    let width = points.width();
+    let height = points.height();
 
     let rect = rectangle::Rectangle::init_from_center2(u_position, size, width, height);
 
@@ -129,17 +143,17 @@ fn set_detection(points: &mut Vec<Vec<VisionStatus>>, position: &(f32, f32), sig
             let d = octile_distance_f32(u_position, (x, y));
 
             if d <= sight_range {
-                points[x][y] = VisionStatus::Detected;
+                points[(x, y)] = VisionStatus::Detected;
             }
         }
     }
 }
 
-fn set_vision(points: &mut Vec<Vec<VisionStatus>>, position: &(f32, f32), sight_range: f32) {
+fn set_vision(points: &mut Grid<VisionStatus>, position: &(f32, f32), sight_range: f32) {
     let u_position = round_point2(*position);
     let size = ((sight_range * 2f32) as usize, (sight_range * 2f32) as usize);
-    let width = points.len();
-    let height = points[0].len();
+    let width = points.width();
+    let height = points.height();
 
     let rect = rectangle::Rectangle::init_from_center2(u_position, size, width, height);
 
@@ -148,24 +162,24 @@ fn set_vision(points: &mut Vec<Vec<VisionStatus>>, position: &(f32, f32), sight_
             let d = octile_distance_f32(u_position, (x, y));
 
             if d <= sight_range {
-                if matches!(points[x][y], VisionStatus::NotSeen) {
-                    points[x][y] = VisionStatus::Seen;
-                } else if matches!(points[x][y], VisionStatus::NotSeenButDetected) {
-                    points[x][y] = VisionStatus::Detected;
+                if matches!(points[(x, y)], VisionStatus::NotSeen) {
+                    points[(x, y)] = VisionStatus::Seen;
+                } else if matches!(points[(x, y)], VisionStatus::NotSeenButDetected) {
+                    points[(x, y)] = VisionStatus::Detected;
                 }
             }
         }
     }
 }
 
-fn calc_ground_detection(points: &mut Vec<Vec<VisionStatus>>,
-                         map_points: &Vec<Vec<map_point::MapPoint>>,
+fn calc_ground_detection(points: &mut Grid<VisionStatus>,
+                         map_points: &Grid<map_point::MapPoint>,
                          position: &(f32, f32),
                          sight_range: f32) {
     let u_position = round_point2(*position);
     let size = ((sight_range * 2f32) as usize, (sight_range * 2f32) as usize);
-    let width = points.len();
-    let height = points[0].len();
+    let width = points.width();
+    let height = points.height();
 
     let rect = rectangle::Rectangle::init_from_center2(u_position, size, width, height);
 
@@ -174,12 +188,12 @@ fn calc_ground_detection(points: &mut Vec<Vec<VisionStatus>>,
             let d = octile_distance_f32(u_position, (x, y));
 
             if d <= sight_range {
-                if matches!(points[x][y], VisionStatus::NotSeen)
-                   || matches!(points[x][y], VisionStatus::NotSeenButDetected)
+                if matches!(points[(x, y)], VisionStatus::NotSeen)
+                   || matches!(points[(x, y)], VisionStatus::NotSeenButDetected)
                 {
-                    points[x][y] = VisionStatus::NotSeenButDetected;
+                    points[(x, y)] = VisionStatus::NotSeenButDetected;
                 } else {
-                    points[x][y] = VisionStatus::Detected;
+                    points[(x, y)] = VisionStatus::Detected;
                 }
             }
         }
@@ -191,12 +205,7 @@ fn calc_ground_detection(points: &mut Vec<Vec<VisionStatus>>,
     let steps = (sight_range * step_mult) as usize;
 
     let u_position = round_point2(*position);
-    let current_height = map_points[u_position.0][u_position.1].height;
-    let mut max_height_seen = current_height / 8 * 8 + 7;
-
-    if current_height % 8 >= 4 {
-        max_height_seen += 8;
-    }
+    let max_height_seen = max_height_seen(map_points[u_position].height);
 
     for index in 0..rays {
         let angle = index as f32 / rays as f32 * 2f32 * std::f32::consts::PI;
@@ -210,18 +219,18 @@ fn calc_ground_detection(points: &mut Vec<Vec<VisionStatus>>,
                 ((position.0 as f32 + v_x * step_f32) as usize, (position.1 as f32 + v_y * step_f32) as usize);
 
             // TODO: Same for height difference
-            if map_points[new_pos.0][new_pos.1].height > max_height_seen {
+            if map_points[new_pos].height > max_height_seen {
                 // Ray can't reach further
                 break;
             }
 
-            points[new_pos.0][new_pos.1] = VisionStatus::Detected;
+            points[new_pos] = VisionStatus::Detected;
         }
     }
 }
 
-fn calc_ground_vision(points: &mut Vec<Vec<VisionStatus>>,
-                      map_points: &Vec<Vec<map_point::MapPoint>>,
+fn calc_ground_vision(points: &mut Grid<VisionStatus>,
+                      map_points: &Grid<map_point::MapPoint>,
                       position: &(f32, f32),
                       sight_range: f32) {
     let circumference = 2f32 * sight_range * std::f32::consts::PI;
@@ -232,12 +241,7 @@ fn calc_ground_vision(points: &mut Vec<Vec<VisionStatus>>,
     // println!("Rays {} and steps {}", rays, steps);
 
     let u_position = round_point2(*position);
-    let current_height = map_points[u_position.0][u_position.1].height;
-    let mut max_height_seen = current_height / 8 * 8 + 7;
-
-    if current_height % 8 >= 4 {
-        max_height_seen += 8;
-    }
+    let max_height_seen = max_height_seen(map_points[u_position].height);
 
     for index in 0..rays {
         let angle = index as f32 / rays as f32 * 2f32 * std::f32::consts::PI;
@@ -249,26 +253,26 @@ fn calc_ground_vision(points: &mut Vec<Vec<VisionStatus>>,
             let step_f32 = step as f32 / step_mult;
             let new_pos = ((position.0 + v_x * step_f32) as usize, (position.1 + v_y * step_f32) as usize);
 
-            if map_points[new_pos.0][new_pos.1].height > max_height_seen {
+            if map_points[new_pos].height > max_height_seen {
                 // Ray can't reach further
                 // println!("Ray {} stopped at ({}, {}), angle was {} and vector was ({}, {}) with step {}",
                 //          index, new_pos.0, new_pos.1, angle, v_x, v_y, step_f32);
                 break;
             }
-            let status = points[new_pos.0][new_pos.1];
+            let status = points[new_pos];
 
             if matches!(status, VisionStatus::NotSeen) {
                 // if new_pos.0 == 25 && new_pos.1 == 8 {
                 // println!("Ray {} set vision to ({}, {}), angle was {} and vector was ({}, {}) with step {}",
                 //          index, new_pos.0, new_pos.1, angle, v_x, v_y, step_f32);
                 // }
-                points[new_pos.0][new_pos.1] = VisionStatus::Seen;
+                points[new_pos] = VisionStatus::Seen;
             } else if matches!(status, VisionStatus::NotSeenButDetected) {
                 // if new_pos.0 == 25 && new_pos.1 == 8 {
                 // println!("Ray {} set vision to ({}, {}), angle was {} and vector was ({}, {}) with step {}",
                 //          index, new_pos.0, new_pos.1, angle, v_x, v_y, step_f32);
                 // }
-                points[new_pos.0][new_pos.1] = VisionStatus::Detected;
+                points[new_pos] = VisionStatus::Detected;
             }
         }
     }
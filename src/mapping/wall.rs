@@ -0,0 +1,466 @@
+use arrayvec::ArrayVec;
+use pyo3::prelude::*;
+use std::cell::RefCell;
+use std::collections::{HashSet, VecDeque};
+
+use crate::error::{PathlibError, PathlibResult};
+use crate::helpers::grid::Grid;
+use crate::helpers::round_point2;
+use crate::mapping::footprint::Footprint;
+use crate::mapping::map::Map;
+
+/// Buildings of a wall have their centers at most this far from the center of the passage.
+const WALL_RADIUS: f32 = 8.0;
+/// Half width of the window around the passage where the connectivity is checked.
+const CHECK_RADIUS: usize = 12;
+/// Inside and outside of the wall are the cells around the points this far from the center.
+const SIDE_DISTANCE: f32 = 7.0;
+const SIDE_RADIUS: f32 = 3.0;
+/// Maximum number of placements to try before giving up, keeps a failing search in tens of milliseconds.
+const MAX_PLACEMENTS: usize = 500;
+/// Cells are inside or outside of the wall when these bits are set in `FlowBuffers::sides`.
+const INSIDE: u8 = 1;
+const OUTSIDE: u8 = 2;
+/// Moves to the 4 neighbours of a cell, the opposite of move `i` is `i ^ 1`.
+const STEPS: [(i64, i64); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+
+/// Building of a planned wall, the footprint as in python and the center of the building.
+pub type WallBuilding = (u8, (f32, f32));
+
+/// Cell in the graph where each cell is split to an entry and an exit, true for the exit.
+type FlowNode = ((usize, usize), bool);
+
+/// Grids over the checked window for finding the gap width, indexed relative to the window start.
+struct FlowBuffers {
+    /// `INSIDE` and `OUTSIDE` bits of the cells.
+    sides: Grid<u8>,
+    /// Cells that a path goes through.
+    through: Grid<bool>,
+    /// Moves from the cell to a neighbour on a path, bit `i` for `STEPS[i]`.
+    flow: Grid<u8>,
+    /// Parents of the entry and the exit of each cell in the breadth first search.
+    parents: Grid<[Option<FlowNode>; 2]>,
+    queue: VecDeque<FlowNode>,
+}
+
+struct PlannedBuilding {
+    footprint: Footprint,
+    center: (f32, f32),
+    placement: Vec<(usize, usize)>,
+    pathing: Vec<(usize, usize)>,
+}
+
+/// Depth first search over building placements around a passage.
+/// Every building after the first one touches the previous one, so the buildings form a chain from a wall.
+struct WallSearch<'a> {
+    map: &'a Map,
+    footprints: Vec<Footprint>,
+    used: Vec<bool>,
+    gap: usize,
+    center: (f32, f32),
+    /// Point slightly towards the inside, placements closer to it are tried first.
+    bias: (f32, f32),
+    inside: Vec<(usize, usize)>,
+    buffers: RefCell<FlowBuffers>,
+    window_start: (usize, usize),
+    window_end: (usize, usize),
+    planned: Vec<PlannedBuilding>,
+    /// Cells that the placement of planned buildings covers.
+    occupied: HashSet<(usize, usize)>,
+    /// Cells of the window that are pathable and not blocked by the planned buildings.
+    free: Grid<bool>,
+    placements_left: usize,
+}
+
+impl<'a> WallSearch<'a> {
+    fn search(&mut self) -> bool {
+        if self.is_finished() {
+            return true;
+        }
+        if self.planned.len() == self.footprints.len() {
+            return false;
+        }
+
+        let mut tried = Vec::new();
+
+        for index in 0..self.footprints.len() {
+            let footprint = self.footprints[index];
+
+            // Swapping buildings of the same footprint gives the same walls
+            if self.used[index] || tried.contains(&footprint) {
+                continue;
+            }
+            tried.push(footprint);
+
+            for building in self.candidates(footprint) {
+                if self.placements_left == 0 {
+                    return false;
+                }
+                self.placements_left -= 1;
+
+                self.place(index, building);
+                if self.search() {
+                    return true;
+                }
+                self.unplace(index);
+            }
+        }
+
+        false
+    }
+
+    /// Whether the planned buildings close the passage or leave a gap of the wanted width.
+    fn is_finished(&self) -> bool { self.gap_width(self.gap + 1) == self.gap }
+
+    fn place(&mut self, index: usize, building: PlannedBuilding) {
+        self.occupied.extend(building.placement.iter().copied());
+        self.set_free(&building.pathing, false);
+        self.used[index] = true;
+        self.planned.push(building);
+    }
+
+    fn unplace(&mut self, index: usize) {
+        if let Some(building) = self.planned.pop() {
+            for cell in &building.placement {
+                self.occupied.remove(cell);
+            }
+            self.set_free(&building.pathing, true);
+        }
+
+        self.used[index] = false;
+    }
+
+    /// Legal placements of the footprint next to a wall or the previous building, closest to the inside first.
+    fn candidates(&self, footprint: Footprint) -> Vec<PlannedBuilding> {
+        let (width, height) = (self.map.placement.width(), self.map.placement.height());
+        let (size_x, size_y) = footprint.size();
+        let x_start = (self.center.0 - WALL_RADIUS).floor().max(0.0) as usize;
+        let y_start = (self.center.1 - WALL_RADIUS).floor().max(0.0) as usize;
+        let x_end = usize::min((self.center.0 + WALL_RADIUS).ceil() as usize, width);
+        let y_end = usize::min((self.center.1 + WALL_RADIUS).ceil() as usize, height);
+        let mut result = Vec::new();
+
+        for x in x_start..x_end {
+            for y in y_start..y_end {
+                let center = (x as f32 + size_x as f32 / 2.0, y as f32 + size_y as f32 / 2.0);
+
+                if distance(center, self.center) > WALL_RADIUS {
+                    continue;
+                }
+
+                let placement = footprint.placement_cells(center, width, height);
+
+                if placement.len() != size_x * size_y
+                   || !placement.iter().all(|cell| self.is_buildable(*cell))
+                   || !self.touches_chain(&placement)
+                {
+                    continue;
+                }
+
+                let pathing = footprint.pathing_cells(center, width, height);
+                result.push(PlannedBuilding { footprint,
+                                              center,
+                                              placement,
+                                              pathing });
+            }
+        }
+
+        result.sort_by(|a, b| distance(a.center, self.bias).total_cmp(&distance(b.center, self.bias)));
+        result
+    }
+
+    #[inline]
+    fn is_buildable(&self, cell: (usize, usize)) -> bool {
+        self.map.placement.is_buildable(cell) && self.map.ground_pathing.map[cell] > 0 && !self.occupied.contains(&cell)
+    }
+
+    /// The first building has to touch unpathable terrain, later ones the previous building.
+    fn touches_chain(&self, cells: &[(usize, usize)]) -> bool {
+        let grid = &self.map.ground_pathing.map;
+
+        cells.iter().any(|(x, y)| {
+                        neighbours(*x, *y).iter().any(|&(nx, ny)| match self.planned.last() {
+                                              None => !grid.in_bounds(nx, ny) || grid[(nx, ny)] == 0,
+                                              Some(previous) => previous.placement.contains(&(nx, ny)),
+                                          })
+                    })
+    }
+
+    /// Marks the cells of a building in the window free or blocked, the building only covers pathable cells.
+    fn set_free(&mut self, cells: &[(usize, usize)], free: bool) {
+        for &(x, y) in cells {
+            if self.in_window(x, y) {
+                let local = self.local((x, y));
+                self.free[local] = free;
+            }
+        }
+    }
+
+    #[inline]
+    fn in_window(&self, x: usize, y: usize) -> bool {
+        self.window_start.0 <= x && x < self.window_end.0 && self.window_start.1 <= y && y < self.window_end.1
+    }
+
+    #[inline]
+    fn is_free(&self, x: usize, y: usize) -> bool { self.in_window(x, y) && self.free[self.local((x, y))] }
+
+    /// Number of cells that still need to be blocked to separate the inside from the outside, counted up to the limit.
+    /// This is the number of paths from side to side that share no cells, found with augmenting paths
+    /// where every cell can carry one path. Diagonal moves need both orthogonal cells, so 4 neighbours are enough.
+    fn gap_width(&self, limit: usize) -> usize {
+        let mut buffers = self.buffers.borrow_mut();
+        buffers.through.fill(false);
+        buffers.flow.fill(0);
+        let mut paths = 0;
+
+        while paths < limit {
+            let steps = match self.augmenting_path(&mut buffers) {
+                Some(steps) => steps,
+                None => break,
+            };
+
+            for pair in steps.windows(2) {
+                let ((from, from_exit), (to, to_exit)) = (pair[0], pair[1]);
+                let from_local = self.local(from);
+
+                if from == to {
+                    // Entering a cell starts a path through it, going back from its exit cancels one
+                    buffers.through[from_local] = !from_exit && to_exit;
+                } else if from_exit && !to_exit {
+                    buffers.flow[from_local] |= 1 << step_index(from, to);
+                } else if !from_exit && to_exit {
+                    // Going backwards over a move cancels it
+                    buffers.flow[self.local(to)] &= !(1 << step_index(to, from));
+                }
+            }
+
+            paths += 1;
+        }
+
+        paths
+    }
+
+    /// Position of the cell in the window.
+    #[inline]
+    fn local(&self, cell: (usize, usize)) -> (usize, usize) {
+        (cell.0 - self.window_start.0, cell.1 - self.window_start.1)
+    }
+
+    /// Breadth first search in the residual graph, returns the steps from an inside cell to an outside cell.
+    fn augmenting_path(&self, buffers: &mut FlowBuffers) -> Option<Vec<FlowNode>> {
+        let FlowBuffers { sides,
+                          through,
+                          flow,
+                          parents,
+                          queue } = buffers;
+        parents.fill([None, None]);
+        queue.clear();
+
+        for &cell in self.inside.iter().filter(|cell| self.is_free(cell.0, cell.1)) {
+            parents[self.local(cell)][0] = Some((cell, false));
+            queue.push_back((cell, false));
+        }
+
+        while let Some(state) = queue.pop_front() {
+            let (cell, exit) = state;
+            let local = self.local(cell);
+            // Cells on the sides can carry any number of paths
+            let is_side = sides[local] != 0;
+
+            if !exit && sides[local] & OUTSIDE != 0 {
+                let mut steps = vec![state];
+                let mut current = state;
+
+                while let Some(parent) = parents[self.local(current.0)][current.1 as usize].filter(|p| *p != current) {
+                    current = parent;
+                    steps.push(current);
+                }
+
+                steps.reverse();
+                return Some(steps);
+            }
+
+            let mut next = ArrayVec::<FlowNode, 5>::new();
+
+            if exit && !is_side && through[local] {
+                next.push((cell, false));
+            } else if !exit && (is_side || !through[local]) {
+                next.push((cell, true));
+            }
+
+            for (index, (dx, dy)) in STEPS.iter().enumerate() {
+                let (x, y) = (cell.0 as i64 + dx, cell.1 as i64 + dy);
+                if x < 0 || y < 0 || !self.is_free(x as usize, y as usize) {
+                    continue;
+                }
+
+                let other = (x as usize, y as usize);
+                if exit && flow[local] & (1 << index) == 0 {
+                    next.push((other, false));
+                } else if !exit && flow[self.local(other)] & (1 << (index ^ 1)) != 0 {
+                    next.push((other, true));
+                }
+            }
+
+            for state_next in next {
+                let parent = &mut parents[self.local(state_next.0)][state_next.1 as usize];
+                if parent.is_none() {
+                    *parent = Some(state);
+                    queue.push_back(state_next);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+/// Index in `STEPS` of the move between the neighbouring cells.
+#[inline]
+fn step_index(from: (usize, usize), to: (usize, usize)) -> usize {
+    match (to.0 as i64 - from.0 as i64, to.1 as i64 - from.1 as i64) {
+        (-1, 0) => 0,
+        (1, 0) => 1,
+        (0, -1) => 2,
+        _ => 3,
+    }
+}
+
+#[inline]
+fn distance(a: (f32, f32), b: (f32, f32)) -> f32 { ((a.0 - b.0).powi(2) + (a.1 - b.1).powi(2)).sqrt() }
+
+/// 8 neighbours of the cell, may be out of bounds.
+fn neighbours(x: usize, y: usize) -> [(usize, usize); 8] {
+    [(x.wrapping_sub(1), y.wrapping_sub(1)),
+     (x, y.wrapping_sub(1)),
+     (x + 1, y.wrapping_sub(1)),
+     (x.wrapping_sub(1), y),
+     (x + 1, y),
+     (x.wrapping_sub(1), y + 1),
+     (x, y + 1),
+     (x + 1, y + 1)]
+}
+
+/// Most cells of a straight or diagonal passage that the footprint can block, the longer side of its pathing.
+fn blocking_width(footprint: Footprint) -> usize {
+    let mask = footprint.pathing_mask();
+    let columns = (0..mask[0].len()).filter(|x| mask.iter().any(|row| row.as_bytes()[*x] == b'x')).count();
+    let rows = mask.iter().filter(|row| row.contains('x')).count();
+    usize::max(columns, rows)
+}
+
+impl Map {
+    /// Plans a wall without the GIL, see `plan_wall`.
+    pub fn plan_wall_rust(&self,
+                          center: (f32, f32),
+                          inside: (f32, f32),
+                          footprints: &[u8],
+                          gap: usize)
+                          -> PathlibResult<Option<Vec<WallBuilding>>> {
+        self.check_position(round_point2(center))?;
+        self.check_position(round_point2(inside))?;
+        let footprints = footprints.iter()
+                                   .map(|footprint| Footprint::from_u8(*footprint))
+                                   .collect::<PathlibResult<Vec<Footprint>>>()?;
+
+        let length = distance(center, inside);
+
+        if length == 0.0 {
+            return Err(PathlibError::InvalidArgument("inside must differ from the center".to_string()));
+        }
+
+        let direction = ((inside.0 - center.0) / length, (inside.1 - center.1) / length);
+        let (width, height) = (self.placement.width(), self.placement.height());
+        let (cx, cy) = round_point2(center);
+        let window_start = (cx.saturating_sub(CHECK_RADIUS), cy.saturating_sub(CHECK_RADIUS));
+        let window_end = (usize::min(cx + CHECK_RADIUS + 1, width), usize::min(cy + CHECK_RADIUS + 1, height));
+        let window_size = (window_end.0 - window_start.0, window_end.1 - window_start.1);
+        let mut sides = Grid::new(window_size.0, window_size.1, 0);
+        let mut free = Grid::new(window_size.0, window_size.1, false);
+        let (mut inside_cells, mut has_outside) = (Vec::new(), false);
+
+        let inside_point = (center.0 + direction.0 * SIDE_DISTANCE, center.1 + direction.1 * SIDE_DISTANCE);
+        let outside_point = (center.0 - direction.0 * SIDE_DISTANCE, center.1 - direction.1 * SIDE_DISTANCE);
+
+        for x in window_start.0..window_end.0 {
+            for y in window_start.1..window_end.1 {
+                let cell_center = (x as f32 + 0.5, y as f32 + 0.5);
+                let local = (x - window_start.0, y - window_start.1);
+
+                if self.ground_pathing.map[(x, y)] == 0 {
+                    continue;
+                }
+
+                free[local] = true;
+                if distance(cell_center, inside_point) <= SIDE_RADIUS {
+                    sides[local] = INSIDE;
+                    inside_cells.push((x, y));
+                } else if distance(cell_center, outside_point) <= SIDE_RADIUS {
+                    sides[local] = OUTSIDE;
+                    has_outside = true;
+                }
+            }
+        }
+
+        if inside_cells.is_empty() || !has_outside {
+            return Ok(None);
+        }
+
+        let buffers = FlowBuffers { sides,
+                                    through: Grid::new(window_size.0, window_size.1, false),
+                                    flow: Grid::new(window_size.0, window_size.1, 0),
+                                    parents: Grid::new(window_size.0, window_size.1, [None, None]),
+                                    queue: VecDeque::new() };
+        let total_width: usize = footprints.iter().map(|footprint| blocking_width(*footprint)).sum();
+        let mut search = WallSearch { map: self,
+                                      used: vec![false; footprints.len()],
+                                      footprints,
+                                      gap,
+                                      center,
+                                      bias: (center.0 + direction.0, center.1 + direction.1),
+                                      inside: inside_cells,
+                                      buffers: RefCell::new(buffers),
+                                      window_start,
+                                      window_end,
+                                      planned: Vec::new(),
+                                      occupied: HashSet::new(),
+                                      free,
+                                      placements_left: MAX_PLACEMENTS };
+
+        // Buildings only narrow the passage, and all of them together can't block more than their widths
+        let open_width = search.gap_width(gap + total_width + 1);
+        if open_width < gap || open_width > gap + total_width {
+            return Ok(None);
+        }
+
+        if !search.search() {
+            return Ok(None);
+        }
+
+        Ok(Some(search.planned
+                      .iter()
+                      .map(|building| (building.footprint as u8, building.center))
+                      .collect()))
+    }
+}
+
+#[pymethods]
+impl Map {
+    /// Plans a wall of buildings over a passage such as a ramp top or a choke.
+    /// Inside is a point on the side that the wall protects, the direction from the center to it
+    /// decides which cells are inside and outside of the wall.
+    /// With gap 0 the wall closes the passage, otherwise it leaves an opening exactly gap cells wide,
+    /// so that blocking gap more cells would close it.
+    /// Buildings are placed on the placement grid and the wall is verified on the ground pathing,
+    /// not all footprints need to be used. Returns the footprints and centers of the buildings,
+    /// None when no wall was found. Runs with the GIL released.
+    pub fn plan_wall(&self,
+                     py: Python,
+                     center: (f32, f32),
+                     inside: (f32, f32),
+                     footprints: Vec<u8>,
+                     gap: usize)
+                     -> PathlibResult<Option<Vec<WallBuilding>>> {
+        py.allow_threads(|| self.plan_wall_rust(center, inside, &footprints, gap))
+    }
+}
@@ -1,6 +1,10 @@
 use pyo3::prelude::*;
 
-use crate::{helpers::round_point2, path_find::euclidean_distance};
+use crate::{
+    error::PathlibResult,
+    helpers::{grid::Grid, round_point2},
+    path_find::euclidean_distance,
+};
 
 use super::{map::Map, map_point::MapPoint};
 
@@ -9,46 +13,56 @@ const Y_MULT: usize = 1000000;
 
 #[pymethods]
 impl Map {
-    pub fn calculate_zones(&mut self, sorted_base_locations: Vec<(f32, f32)>) {
+    pub fn calculate_zones(&mut self, sorted_base_locations: Vec<(f32, f32)>) -> PathlibResult<()> {
         let mut index = 1;
         let copy_loc = sorted_base_locations.clone();
 
+        for pos in &sorted_base_locations {
+            self.check_position((pos.0 as usize, pos.1 as usize))?;
+        }
+
         for pos in sorted_base_locations {
             let x = pos.0 as usize;
             let y = pos.1 as usize;
 
-            let target_height = self.points[x][y].height;
+            let target_height = self.points[(x, y)].height;
             // self.points[x][y].zone_index = index;
-            flood_fill(self, x, y, target_height, index, pos, &copy_loc);
+            flood_fill(self, x, y, target_height, index, pos, &copy_loc)?;
             index += 1;
         }
+
+        for ramp in &mut self.ramps {
+            ramp.update_zones(&self.points);
+        }
+
+        Ok(())
     }
 
     pub fn draw_zones(&self) -> Vec<Vec<usize>> {
-        let width = self.ground_pathing.map.len();
-        let height = self.ground_pathing.map[0].len();
-        let mut walk_map: Vec<Vec<usize>> = vec![vec![0; height]; width];
+        let width = self.ground_pathing.map.width();
+        let height = self.ground_pathing.map.height();
+        let mut walk_map = Grid::new(width, height, 0);
 
         for x in 0..width {
             for y in 0..height {
-                let point = &self.points[x][y];
+                let point = &self.points[(x, y)];
                 if point.walkable {
-                    walk_map[x][y] = 255;
+                    walk_map[(x, y)] = 255;
                     if point.zone_index > 0 {
-                        walk_map[x][y] = 50 + 20 * (point.zone_index as usize);
+                        walk_map[(x, y)] = 50 + 20 * (point.zone_index as usize);
                     }
                 } else if point.is_choke {
-                    walk_map[x][y] = 0;
+                    walk_map[(x, y)] = 0;
                 }
             }
         }
 
-        walk_map
+        walk_map.to_vec()
     }
 
     pub fn add_influence_without_zones(&mut self, influence_zones: Vec<i8>, value: usize) {
-        let width = self.ground_pathing.map.len();
-        let height = self.ground_pathing.map[0].len();
+        let width = self.ground_pathing.map.width();
+        let height = self.ground_pathing.map.height();
         // let mut maps =self.get_ground_influence_maps();
         for x in 0..width {
             for y in 0..height {
@@ -64,22 +78,23 @@ impl Map {
                 if !found {
                     for mapping in self.get_ground_influence_maps() {
                         // for mapping in maps.iter_mut() {
-                        mapping.map[x][y] += value;
+                        mapping.map[(x, y)] += value;
                     }
                 }
             }
         }
     }
 
-    pub fn get_zone(&self, position: (f32, f32)) -> i8 {
+    pub fn get_zone(&self, position: (f32, f32)) -> PathlibResult<i8> {
         let u_position = round_point2(position);
-        self.points[u_position.0][u_position.1].zone_index
+        self.check_position(u_position)?;
+        Ok(self.points[u_position].zone_index)
     }
 }
 
 impl Map {
-    fn borrow(&mut self, x: usize, y: usize) -> &mut MapPoint { &mut self.points[x][y] }
-    fn zone_index(&mut self, x: usize, y: usize) -> i8 { self.points[x][y].zone_index }
+    fn borrow(&mut self, x: usize, y: usize) -> &mut MapPoint { &mut self.points[(x, y)] }
+    fn zone_index(&mut self, x: usize, y: usize) -> i8 { self.points[(x, y)].zone_index }
 }
 
 fn flood_fill(map: &mut Map,
@@ -88,10 +103,11 @@ fn flood_fill(map: &mut Map,
               target_height: usize,
               zone_index: i8,
               origin: (f32, f32),
-              sorted_base_locations: &Vec<(f32, f32)>) {
+              sorted_base_locations: &Vec<(f32, f32)>)
+              -> PathlibResult<()> {
     let zone = map.borrow(x, y).zone_index as usize;
     if zone == zone_index as usize || !map.borrow(x, y).walkable {
-        return;
+        return Ok(());
     } else if zone > 0 {
         let start = (x, y);
         let pos = sorted_base_locations[zone - 1];
@@ -99,13 +115,13 @@ fn flood_fill(map: &mut Map,
         let end2 = (origin.0 as usize, origin.1 as usize);
         let heuristic = Some(2u8);
 
-        let mapping = map.get_map_mut(1);
-        let old_path = mapping.find_path_basic(start, end, heuristic);
-        let new_path = mapping.find_path_basic(start, end2, heuristic);
+        let mapping = map.get_map_mut(1)?;
+        let old_path = mapping.find_path_basic(start, end, heuristic)?;
+        let new_path = mapping.find_path_basic(start, end2, heuristic)?;
 
         if old_path.1 < new_path.1 {
             // Old zone definition is better
-            return;
+            return Ok(());
         }
     }
 
@@ -114,29 +130,31 @@ fn flood_fill(map: &mut Map,
     point.zone_index = zone_index;
 
     if target_height > point.height + DIFFERENCE || target_height < point.height - DIFFERENCE {
-        return; // Not the same zone anymore.
+        return Ok(()); // Not the same zone anymore.
     }
 
     if point.is_choke {
         // Let's color the first grid as being the same zone
-        return; // do not flood fill any of the following grid pixels.
+        return Ok(()); // do not flood fill any of the following grid pixels.
     }
 
     if euclidean_distance(origin, (x as f32, y as f32)) > 30f32 {
         // Too far to look for other grids.
-        return;
+        return Ok(());
     }
 
     if y > 0 {
-        flood_fill(map, x, ((y as u32) - 1) as usize, target_height, zone_index, origin, sorted_base_locations);
+        flood_fill(map, x, ((y as u32) - 1) as usize, target_height, zone_index, origin, sorted_base_locations)?;
     }
     if x > 0 {
-        flood_fill(map, ((x as u32) - 1) as usize, y, target_height, zone_index, origin, sorted_base_locations);
+        flood_fill(map, ((x as u32) - 1) as usize, y, target_height, zone_index, origin, sorted_base_locations)?;
     }
-    if y < map.points[0].len() - 1 {
-        flood_fill(map, x, y + 1, target_height, zone_index, origin, sorted_base_locations);
+    if y < map.points.height() - 1 {
+        flood_fill(map, x, y + 1, target_height, zone_index, origin, sorted_base_locations)?;
     }
-    if x < map.points.len() - 1 {
-        flood_fill(map, x + 1, y, target_height, zone_index, origin, sorted_base_locations);
+    if x < map.points.width() - 1 {
+        flood_fill(map, x + 1, y, target_height, zone_index, origin, sorted_base_locations)?;
     }
+
+    Ok(())
 }
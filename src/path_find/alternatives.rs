@@ -0,0 +1,145 @@
+use arrayvec::ArrayVec;
+use pathfinding::prelude::astar;
+use pyo3::prelude::*;
+use std::collections::HashSet;
+
+use crate::error::{PathlibError, PathlibResult};
+use crate::helpers::grid::Grid;
+use crate::path_find::pos::{Pos, PositionAPI};
+use crate::path_find::{octile_distance_f32, PathFind};
+
+/// Path, its walking length and the share of its cells that are also in the best path.
+pub type AlternativePath = (Vec<(usize, usize)>, f32, f32);
+
+/// Wraps another position api and makes moving into cells used by earlier paths more expensive.
+pub struct PenaltyPosAPI<'a> {
+    pub api: &'a dyn PositionAPI,
+    /// How many earlier paths have used each cell or its neighbours.
+    pub penalties: &'a Grid<usize>,
+    pub overlap_penalty: f32,
+}
+
+impl PositionAPI for PenaltyPosAPI<'_> {
+    #[inline]
+    fn manhattan_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.manhattan_distance(start, end) }
+
+    #[inline]
+    fn euclidean_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.euclidean_distance(start, end) }
+
+    #[inline]
+    fn octile_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.octile_distance(start, end) }
+
+    #[inline]
+    fn successors(&self, pos: &Pos, grid: &Grid<usize>) -> ArrayVec<(Pos, usize), 8> {
+        self.successors_within(pos, grid, ((0, 0), (grid.width(), grid.height())))
+    }
+
+    #[inline]
+    fn successors_within(&self,
+                         pos: &Pos,
+                         grid: &Grid<usize>,
+                         window: ((usize, usize), (usize, usize)))
+                         -> ArrayVec<(Pos, usize), 8> {
+        let mut arr = self.api.successors_within(pos, grid, window);
+
+        for (p, cost) in arr.iter_mut() {
+            let count = self.penalties[(p.0, p.1)];
+
+            if count > 0 {
+                *cost += (*cost as f32 * self.overlap_penalty * count as f32) as usize;
+            }
+        }
+
+        arr
+    }
+}
+
+/// Adds penalty to the cells of the path and their neighbours,
+/// so that the next path doesn't just run alongside it.
+fn add_penalty(penalties: &mut Grid<usize>, path: &[(usize, usize)]) {
+    let mut penalized = HashSet::new();
+
+    for &(x, y) in path {
+        for nx in x.saturating_sub(1)..=x + 1 {
+            for ny in y.saturating_sub(1)..=y + 1 {
+                if penalties.in_bounds(nx, ny) && penalized.insert((nx, ny)) {
+                    penalties[(nx, ny)] += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Walking length of the path in cells.
+fn path_length(path: &[(usize, usize)]) -> f32 { path.windows(2).map(|w| octile_distance_f32(w[0], w[1])).sum() }
+
+#[pymethods]
+impl PathFind {
+    /// Finds up to k different paths from start to end, the first one is the shortest path.
+    /// Every following path is found with extra cost on the cells used by the earlier paths,
+    /// moving into a cell costs overlap_penalty times more for each earlier path that used it,
+    /// the penalty can't be negative.
+    /// Returns a list of (path, length, overlap) where overlap is the share of the path's cells
+    /// that are also in the best path. Paths identical to earlier ones are left out.
+    pub fn find_alternative_paths(&self,
+                                  start: (usize, usize),
+                                  end: (usize, usize),
+                                  k: usize,
+                                  overlap_penalty: f32,
+                                  influence: bool)
+                                  -> PathlibResult<Vec<AlternativePath>> {
+        self.check_path_query(start, end, None)?;
+
+        if !(overlap_penalty.is_finite() && overlap_penalty >= 0.0) {
+            let reason = format!("overlap penalty must be finite and not negative, got {}", overlap_penalty);
+            return Err(PathlibError::InvalidArgument(reason));
+        }
+
+        let corrected_start = self.get_closest_pathable(start);
+        let corrected_end = self.get_closest_pathable(end);
+        let start_pos = Pos(corrected_start.0, corrected_start.1);
+        let goal = Pos(corrected_end.0, corrected_end.1);
+
+        let mut penalties = Grid::new(self.width, self.height, 0);
+        let mut paths: Vec<Vec<(usize, usize)>> = Vec::with_capacity(k);
+
+        // A path can come up again when there is no other way around, don't keep trying forever
+        for _ in 0..k * 2 {
+            if paths.len() >= k {
+                break;
+            }
+
+            let result = self.with_api(false, influence, None, None, |api| {
+                                 let penalty_api = PenaltyPosAPI { api,
+                                                                   penalties: &penalties,
+                                                                   overlap_penalty };
+                                 astar(&start_pos,
+                                       |p| penalty_api.successors(p, &self.map),
+                                       |p| penalty_api.octile_distance(p, &goal),
+                                       |p| *p == goal)
+                             });
+
+            let path: Vec<(usize, usize)> = match result {
+                Some((nodes, _)) => nodes.into_iter().map(|p| (p.0, p.1)).collect(),
+                None => break,
+            };
+
+            add_penalty(&mut penalties, &path);
+
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+
+        let best: HashSet<(usize, usize)> = paths.first().map(|p| p.iter().copied().collect()).unwrap_or_default();
+
+        Ok(paths.into_iter()
+                .map(|path| {
+                    let shared = path.iter().filter(|p| best.contains(p)).count();
+                    let overlap = shared as f32 / path.len() as f32;
+                    let length = path_length(&path);
+                    (path, length, overlap)
+                })
+                .collect())
+    }
+}
@@ -0,0 +1,134 @@
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use std::any::Any;
+use std::thread;
+
+use crate::error::{PathlibError, PathlibResult};
+use crate::helpers::round_point2;
+use crate::path_find::influence_cap::InfluenceCap;
+use crate::path_find::PathFind;
+
+/// Path and its distance, the result of a single query.
+pub type PathAndDistance = (Vec<(usize, usize)>, f32);
+
+/// Path finding query with the options of a single search, used by find_path and batched path finding.
+/// From python this is a tuple of
+/// (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap)
+/// where everything after end is optional.
+#[derive(Clone, Debug, Default)]
+pub struct PathQuery {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub large: bool,
+    pub influence: bool,
+    pub possible_heuristic: Option<u8>,
+    pub possible_window: Option<((usize, usize), (usize, usize))>,
+    pub possible_distance_from_target: Option<f32>,
+    pub possible_radius: Option<f32>,
+    pub possible_influence_cap: Option<InfluenceCap>,
+}
+
+impl PathQuery {
+    pub fn new(start: (usize, usize), end: (usize, usize)) -> Self {
+        PathQuery { start,
+                    end,
+                    ..Default::default() }
+    }
+}
+
+impl<'source> FromPyObject<'source> for PathQuery {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let tuple: &PyTuple = ob.downcast()?;
+        let start: (f32, f32) = tuple.get_item(0)?.extract()?;
+        let end: (f32, f32) = tuple.get_item(1)?.extract()?;
+        let mut query = PathQuery::new(round_point2(start), round_point2(end));
+
+        if tuple.len() > 2 {
+            query.large = tuple.get_item(2)?.extract()?;
+        }
+        if tuple.len() > 3 {
+            query.influence = tuple.get_item(3)?.extract()?;
+        }
+        if tuple.len() > 4 {
+            query.possible_heuristic = tuple.get_item(4)?.extract()?;
+        }
+        if tuple.len() > 5 {
+            let window: Option<((f32, f32), (f32, f32))> = tuple.get_item(5)?.extract()?;
+            query.possible_window = window.map(|(p0, p1)| (round_point2(p0), round_point2(p1)));
+        }
+        if tuple.len() > 6 {
+            query.possible_distance_from_target = tuple.get_item(6)?.extract()?;
+        }
+        if tuple.len() > 7 {
+            query.possible_radius = tuple.get_item(7)?.extract()?;
+        }
+        if tuple.len() > 8 {
+            query.possible_influence_cap = tuple.get_item(8)?.extract()?;
+        }
+
+        Ok(query)
+    }
+}
+
+impl PathFind {
+    /// Runs multiple path queries in parallel threads.
+    /// Results are returned in the same order as the queries.
+    /// Every query is validated before any search is started.
+    pub fn find_paths_batch_rust(&self, queries: &[PathQuery]) -> PathlibResult<Vec<PathAndDistance>> {
+        for query in queries {
+            self.check_path_query(query.start, query.end, query.possible_window)?;
+        }
+
+        let threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1).min(queries.len());
+
+        if threads <= 1 {
+            return Ok(queries.iter().map(|q| self.find_path_inline(q)).collect());
+        }
+
+        let chunk_size = queries.len().div_ceil(threads);
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = queries.chunks(chunk_size)
+                                         .map(|chunk| {
+                                             scope.spawn(move || {
+                                                      chunk.iter()
+                                                           .map(|q| self.find_path_inline(q))
+                                                           .collect::<Vec<_>>()
+                                                  })
+                                         })
+                                         .collect();
+
+            // Every thread is joined before reporting a failure, the scope panics on unjoined panicked threads
+            let joined: Vec<_> = handles.into_iter().map(|handle| handle.join()).collect();
+            let mut results = Vec::with_capacity(queries.len());
+
+            for chunk in joined {
+                results.extend(chunk.map_err(|payload| PathlibError::ThreadPanicked(panic_message(&*payload)))?);
+            }
+
+            Ok(results)
+        })
+    }
+}
+
+/// Message of a panic payload, panics with a formatted message carry a String and others a str.
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    match payload.downcast_ref::<String>() {
+        Some(message) => message.clone(),
+        None => payload.downcast_ref::<&str>().map_or("unknown panic", |message| message).to_string(),
+    }
+}
+
+#[pymethods]
+impl PathFind {
+    /// Finds multiple paths in parallel with the GIL released.
+    /// Each query is a tuple of
+    /// (start, end, large, influence, heuristic, window, distance_from_target, radius, influence_cap),
+    /// where everything after end is optional. Results are returned in the same order as the queries.
+    pub fn find_paths_batch(&self,
+                            py: Python,
+                            queries: Vec<PathQuery>)
+                            -> PathlibResult<Vec<PathAndDistance>> {
+        py.allow_threads(|| self.find_paths_batch_rust(&queries))
+    }
+}
@@ -0,0 +1,118 @@
+use arrayvec::ArrayVec;
+
+use crate::helpers::grid::Grid;
+use crate::path_find::pos::{Pos, PositionAPI, MULT, MULTF32, SQRT2};
+
+/// Clearance values are capped to this many cells, no unit needs more room than that.
+pub const MAX_CLEARANCE_CELLS: usize = 10;
+const MAX_CLEARANCE: usize = MAX_CLEARANCE_CELLS * MULT;
+
+/// Clearance that a cell needs for a unit with the radius to fit in it.
+/// A unit with radius 0.5 fits in every pathable cell.
+#[inline]
+pub fn required_clearance(radius: f32) -> usize { ((radius + 0.5) * MULTF32) as usize }
+
+/// Creates the clearance map of the grid, see `update_clearance`.
+pub fn create_clearance(grid: &Grid<usize>) -> Grid<usize> {
+    let mut clearance = Grid::new(grid.width(), grid.height(), 0);
+    update_clearance(&mut clearance, grid, (0, 0), (grid.width(), grid.height()));
+    clearance
+}
+
+/// Recalculates the clearance, the octile distance to the closest unpathable cell, inside the window.
+/// Cells outside the map count as unpathable.
+/// Changing the pathability of a rectangle only affects clearance up to `MAX_CLEARANCE_CELLS` around it,
+/// so the window needs to include that margin.
+/// Uses a two pass chamfer distance transform on the window and the obstacles that can reach it.
+pub fn update_clearance(clearance: &mut Grid<usize>,
+                        grid: &Grid<usize>,
+                        window_start: (usize, usize),
+                        window_end: (usize, usize)) {
+    let x0 = window_start.0.saturating_sub(MAX_CLEARANCE_CELLS);
+    let y0 = window_start.1.saturating_sub(MAX_CLEARANCE_CELLS);
+    let x1 = usize::min(window_end.0 + MAX_CLEARANCE_CELLS, grid.width());
+    let y1 = usize::min(window_end.1 + MAX_CLEARANCE_CELLS, grid.height());
+    let mut region = Grid::new(x1 - x0, y1 - y0, MAX_CLEARANCE);
+
+    for x in 0..region.width() {
+        for y in 0..region.height() {
+            if grid[(x0 + x, y0 + y)] == 0 {
+                region[(x, y)] = 0;
+            }
+        }
+    }
+
+    // Cells outside the map are unpathable, cells outside the region are too far to matter.
+    let (width, height) = (grid.width() as i64, grid.height() as i64);
+    let neighbour = |region: &Grid<usize>, x: i64, y: i64| {
+        let (map_x, map_y) = (x + x0 as i64, y + y0 as i64);
+
+        if map_x < 0 || map_y < 0 || map_x >= width || map_y >= height {
+            0
+        } else if x < 0 || y < 0 || !region.in_bounds(x as usize, y as usize) {
+            MAX_CLEARANCE
+        } else {
+            region[(x as usize, y as usize)]
+        }
+    };
+
+    for x in 0..region.width() as i64 {
+        for y in 0..region.height() as i64 {
+            let value = usize::min(region[(x as usize, y as usize)], neighbour(&region, x - 1, y - 1) + SQRT2);
+            let value = usize::min(value, neighbour(&region, x - 1, y) + MULT);
+            let value = usize::min(value, neighbour(&region, x - 1, y + 1) + SQRT2);
+            let value = usize::min(value, neighbour(&region, x, y - 1) + MULT);
+            region[(x as usize, y as usize)] = value;
+        }
+    }
+
+    for x in (0..region.width() as i64).rev() {
+        for y in (0..region.height() as i64).rev() {
+            let value = usize::min(region[(x as usize, y as usize)], neighbour(&region, x + 1, y + 1) + SQRT2);
+            let value = usize::min(value, neighbour(&region, x + 1, y) + MULT);
+            let value = usize::min(value, neighbour(&region, x + 1, y - 1) + SQRT2);
+            let value = usize::min(value, neighbour(&region, x, y + 1) + MULT);
+            region[(x as usize, y as usize)] = value;
+        }
+    }
+
+    for x in window_start.0..usize::min(window_end.0, grid.width()) {
+        for y in window_start.1..usize::min(window_end.1, grid.height()) {
+            clearance[(x, y)] = region[(x - x0, y - y0)];
+        }
+    }
+}
+
+/// Wraps another position api and only allows moves to cells with enough clearance for the unit radius.
+pub struct ClearancePosAPI<'a> {
+    pub api: &'a dyn PositionAPI,
+    pub clearance: &'a Grid<usize>,
+    pub required: usize,
+}
+
+impl PositionAPI for ClearancePosAPI<'_> {
+    #[inline]
+    fn manhattan_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.manhattan_distance(start, end) }
+
+    #[inline]
+    fn euclidean_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.euclidean_distance(start, end) }
+
+    #[inline]
+    fn octile_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.octile_distance(start, end) }
+
+    #[inline]
+    fn successors(&self, pos: &Pos, grid: &Grid<usize>) -> ArrayVec<(Pos, usize), 8> {
+        self.successors_within(pos, grid, ((0, 0), (grid.width(), grid.height())))
+    }
+
+    #[inline]
+    fn successors_within(&self,
+                         pos: &Pos,
+                         grid: &Grid<usize>,
+                         window: ((usize, usize), (usize, usize)))
+                         -> ArrayVec<(Pos, usize), 8> {
+        let mut arr = self.api.successors_within(pos, grid, window);
+        arr.retain(|(p, _)| self.clearance[(p.0, p.1)] >= self.required);
+        arr
+    }
+}
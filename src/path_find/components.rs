@@ -0,0 +1,72 @@
+use crate::helpers::grid::Grid;
+
+/// Label of unpathable cells.
+pub const NO_COMPONENT: usize = 0;
+
+/// Connected component labels of the pathable cells, two cells with the same label can reach each other.
+/// Diagonal moves need both orthogonal neighbours to be pathable, so 4-connectivity matches path finding.
+#[derive(Clone)]
+pub struct Components {
+    labels: Grid<usize>,
+    next_label: usize,
+}
+
+impl Components {
+    pub fn new(grid: &Grid<usize>) -> Self {
+        let mut components = Components { labels: Grid::new(grid.width(), grid.height(), NO_COMPONENT),
+                                          next_label: NO_COMPONENT + 1 };
+        components.update(grid, (0, 0), (grid.width(), grid.height()));
+        components
+    }
+
+    #[inline]
+    pub fn label(&self, position: (usize, usize)) -> usize { self.labels[position] }
+
+    /// Relabels components after the pathability inside the window has changed.
+    /// Only components touching the window or its border are filled again, so blocking a cell
+    /// can split a component and unblocking one can merge several.
+    pub fn update(&mut self, grid: &Grid<usize>, window_start: (usize, usize), window_end: (usize, usize)) {
+        let x0 = window_start.0.saturating_sub(1);
+        let y0 = window_start.1.saturating_sub(1);
+        let x1 = usize::min(window_end.0 + 1, grid.width());
+        let y1 = usize::min(window_end.1 + 1, grid.height());
+        let mut seeds = Vec::new();
+
+        for x in x0..x1 {
+            for y in y0..y1 {
+                if grid[(x, y)] == 0 {
+                    self.labels[(x, y)] = NO_COMPONENT;
+                } else {
+                    seeds.push((x, y));
+                }
+            }
+        }
+
+        // Labels from this update are new, older ones are left over from before the change
+        let first_label = self.next_label;
+
+        for seed in seeds {
+            if self.labels[seed] < first_label {
+                self.fill(grid, seed);
+            }
+        }
+    }
+
+    fn fill(&mut self, grid: &Grid<usize>, start: (usize, usize)) {
+        let label = self.next_label;
+        self.next_label += 1;
+        self.labels[start] = label;
+        let mut stack = vec![start];
+
+        while let Some((x, y)) = stack.pop() {
+            let neighbours = [(x.wrapping_sub(1), y), (x + 1, y), (x, y.wrapping_sub(1)), (x, y + 1)];
+
+            for (nx, ny) in neighbours {
+                if grid.in_bounds(nx, ny) && grid[(nx, ny)] > 0 && self.labels[(nx, ny)] != label {
+                    self.labels[(nx, ny)] = label;
+                    stack.push((nx, ny));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+use pathfinding::prelude::{astar, dijkstra_all};
+use pyo3::prelude::*;
+use pyo3::types::PyTuple;
+use std::collections::{HashMap, HashSet};
+
+use crate::error::{PathlibError, PathlibResult};
+use crate::helpers::round_point2;
+use crate::path_find::clearance;
+use crate::path_find::pos::{Pos, MULTF32};
+use crate::path_find::search::SearchBudget;
+use crate::path_find::PathFind;
+
+/// Time steps it takes from the fastest agent to move one cell, slower agents take more.
+const TICKS_PER_CELL: f32 = 4.0;
+/// Extra time agents may spend waiting for others on top of twice their own walking time.
+const WAIT_TICKS: usize = 32 * TICKS_PER_CELL as usize;
+/// Expanded positions in time for each agent when the budget doesn't limit them.
+const MAX_EXPANSIONS: usize = 200_000;
+
+/// Path where every cell has the time in seconds when the agent reaches it.
+/// Repeated cells mean that the agent waits for others to pass.
+pub type TimedPath = Vec<((usize, usize), f32)>;
+
+/// Unit for cooperative path finding.
+/// From python this is a tuple of (start, goal, size, speed) where size and speed are optional.
+#[derive(Clone, Debug)]
+pub struct Agent {
+    pub start: (usize, usize),
+    pub goal: (usize, usize),
+    /// Width of the unit in cells, agents larger than 1 use the path finding rules of large units
+    /// and larger than 2 also need clearance for their size.
+    pub size: usize,
+    /// Speed in cells per second.
+    pub speed: f32,
+}
+
+impl Agent {
+    pub fn new(start: (usize, usize), goal: (usize, usize)) -> Self {
+        Agent { start,
+                goal,
+                size: 1,
+                speed: 1.0 }
+    }
+
+    /// Radius for clearance checks, cells further than it from the center of the footprint need room.
+    /// Agents of size 1 and 2 fit in every pathable cell, the rules of large units handle size 2.
+    fn radius(&self) -> f32 { (self.size - 1) as f32 / 2.0 }
+}
+
+impl<'source> FromPyObject<'source> for Agent {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let tuple: &PyTuple = ob.downcast()?;
+        let start: (f32, f32) = tuple.get_item(0)?.extract()?;
+        let goal: (f32, f32) = tuple.get_item(1)?.extract()?;
+        let mut agent = Agent::new(round_point2(start), round_point2(goal));
+
+        if tuple.len() > 2 {
+            agent.size = tuple.get_item(2)?.extract()?;
+        }
+        if tuple.len() > 3 {
+            agent.speed = tuple.get_item(3)?.extract()?;
+        }
+
+        Ok(agent)
+    }
+}
+
+/// Cells reserved by agents whose paths are already planned.
+#[derive(Default)]
+struct Reservations {
+    /// (x, y, tick) of every reserved cell.
+    reserved: HashSet<(usize, usize, usize)>,
+    /// Last reserved tick of each cell, so that free cells can be skipped quickly.
+    last_tick: HashMap<(usize, usize), usize>,
+    /// Cells where agents stay after reaching their goal, from the tick they arrive.
+    parked: HashMap<(usize, usize), usize>,
+}
+
+impl Reservations {
+    fn is_free(&self, cells: &[(usize, usize)], from_tick: usize, to_tick: usize) -> bool {
+        cells.iter().all(|cell| {
+                        self.parked.get(cell).is_none_or(|tick| *tick > to_tick)
+                        && (self.last_tick.get(cell).is_none_or(|tick| *tick < from_tick)
+                            || (from_tick..=to_tick).all(|t| !self.reserved.contains(&(cell.0, cell.1, t))))
+                    })
+    }
+
+    /// Whether the agent can stay in the cells forever starting from the tick.
+    fn is_free_after(&self, cells: &[(usize, usize)], tick: usize) -> bool {
+        cells.iter().all(|cell| {
+                        !self.parked.contains_key(cell) && self.last_tick.get(cell).is_none_or(|last| *last < tick)
+                    })
+    }
+
+    fn reserve(&mut self, cells: &[(usize, usize)], from_tick: usize, to_tick: usize) {
+        for cell in cells {
+            for tick in from_tick..=to_tick {
+                self.reserved.insert((cell.0, cell.1, tick));
+            }
+
+            let last = self.last_tick.entry(*cell).or_insert(to_tick);
+            *last = usize::max(*last, to_tick);
+        }
+    }
+
+    fn park(&mut self, cells: &[(usize, usize)], tick: usize) {
+        for cell in cells {
+            let parked = self.parked.entry(*cell).or_insert(tick);
+            *parked = usize::min(*parked, tick);
+        }
+    }
+}
+
+impl PathFind {
+    /// Cells covered by an agent of the size standing in the position.
+    /// Even sizes extend one cell further to the right and up than to the left and down.
+    fn footprint(&self, position: (usize, usize), size: usize) -> Vec<(usize, usize)> {
+        let before = size.saturating_sub(1) / 2;
+        let (x_start, y_start) = (position.0.saturating_sub(before), position.1.saturating_sub(before));
+        let x_end = usize::min(x_start + size, self.width);
+        let y_end = usize::min(y_start + size, self.height);
+
+        (x_start..x_end).flat_map(|x| (y_start..y_end).map(move |y| (x, y))).collect()
+    }
+
+    /// Space time A* for a single agent against the reservations, returns the cells and their arrival ticks.
+    /// Gives up right away when the goal can't be reached or an earlier agent stays there,
+    /// and when the budget runs out.
+    fn find_reserved_path(&self,
+                          agent: &Agent,
+                          ticks_per_cell: f32,
+                          reservations: &Reservations,
+                          budget: &SearchBudget)
+                          -> Option<Vec<((usize, usize), usize)>> {
+        let large = agent.size > 1;
+        let start = self.get_closest_pathable(agent.start);
+        let goal = self.get_closest_pathable(agent.goal);
+        let goal_footprint = self.footprint(goal, agent.size);
+
+        if goal_footprint.iter().any(|cell| reservations.parked.contains_key(cell)) {
+            return None;
+        }
+
+        let ticks = |cost: usize| ((cost as f32 / MULTF32 * ticks_per_cell).ceil() as usize).max(1);
+        let start_pos = Pos(start.0, start.1);
+        let goal_pos = Pos(goal.0, goal.1);
+
+        self.with_api(large, false, Some(agent.radius()), None, |api| {
+                // Walking ticks to the goal without other agents, moves are symmetric so this searches from the goal
+                let mut remaining: HashMap<Pos, usize> =
+                    dijkstra_all(&goal_pos, |p| {
+                        api.successors(p, &self.map).into_iter().map(|(next, cost)| (next, ticks(cost)))
+                    }).into_iter()
+                      .map(|(pos, (_, cost))| (pos, cost))
+                      .collect();
+                remaining.insert(goal_pos.clone(), 0);
+
+                let max_tick = remaining.get(&start_pos)? * 2 + WAIT_TICKS;
+                let mut expansions = 0;
+
+                let result = astar(&(start_pos, 0),
+                                   |(pos, tick)| {
+                                       expansions += 1;
+                                       if budget.exhausted(expansions) {
+                                           return Vec::new();
+                                       }
+
+                                       let from_footprint = self.footprint((pos.0, pos.1), agent.size);
+                                       let mut moves = vec![((pos.clone(), tick + 1), 1)];
+
+                                       for (next, cost) in api.successors(pos, &self.map) {
+                                           moves.push(((next, tick + ticks(cost)), ticks(cost)));
+                                       }
+
+                                       moves.retain(|((next, arrival), _)| {
+                                                *arrival <= max_tick
+                                                && reservations.is_free(&from_footprint, *tick, *arrival)
+                                                && reservations.is_free(&self.footprint((next.0, next.1), agent.size),
+                                                                        *tick,
+                                                                        *arrival)
+                                            });
+                                       moves
+                                   },
+                                   |(pos, _)| remaining.get(pos).copied().unwrap_or(0),
+                                   |(pos, tick)| {
+                                       *pos == goal_pos && reservations.is_free_after(&goal_footprint, *tick)
+                                   });
+
+                result.map(|(nodes, _)| nodes.into_iter().map(|(pos, tick)| ((pos.0, pos.1), tick)).collect())
+            })
+    }
+}
+
+#[pymethods]
+impl PathFind {
+    /// Plans paths for the agents one by one so that they don't walk into each other.
+    /// Every path reserves its cells in space and time, later agents path around them or wait.
+    /// Agents earlier in the list have priority and stay in their goal after reaching it.
+    /// The budget limits the expanded positions of each agent and the time of the whole call,
+    /// without a limit on expansions each agent expands at most 200 000 positions.
+    /// Returns a timed path for each agent, empty when the agent has no path to its goal or the budget ran out.
+    /// Agents that don't have room for their size at the start are an error.
+    pub fn find_cooperative_paths(&self,
+                                  agents: Vec<Agent>,
+                                  possible_budget: Option<SearchBudget>)
+                                  -> PathlibResult<Vec<TimedPath>> {
+        for agent in &agents {
+            self.check_path_query(agent.start, agent.goal, None)?;
+
+            if !(agent.speed.is_finite() && agent.speed > 0.0) {
+                let reason = format!("agent speed must be positive and finite, got {}", agent.speed);
+                return Err(PathlibError::InvalidArgument(reason));
+            }
+
+            if agent.size == 0 {
+                return Err(PathlibError::InvalidArgument("agent size must be at least 1".to_string()));
+            }
+
+            let start = self.get_closest_pathable(agent.start);
+            if self.clearance[start] < clearance::required_clearance(agent.radius()) {
+                let reason = format!("agent of size {} doesn't fit at its start {:?}", agent.size, start);
+                return Err(PathlibError::InvalidArgument(reason));
+            }
+        }
+
+        let mut budget = possible_budget.unwrap_or_default();
+        budget.possible_max_expansions.get_or_insert(MAX_EXPANSIONS);
+        let max_speed = agents.iter().map(|a| a.speed).fold(0.0, f32::max);
+        let ticks_per_second = TICKS_PER_CELL * max_speed;
+        let mut reservations = Reservations::default();
+        let mut result = Vec::with_capacity(agents.len());
+
+        for agent in &agents {
+            let ticks_per_cell = ticks_per_second / agent.speed;
+
+            match self.find_reserved_path(agent, ticks_per_cell, &reservations, &budget) {
+                Some(path) => {
+                    for pair in path.windows(2) {
+                        let ((from, from_tick), (to, to_tick)) = (pair[0], pair[1]);
+                        reservations.reserve(&self.footprint(from, agent.size), from_tick, to_tick);
+                        reservations.reserve(&self.footprint(to, agent.size), from_tick, to_tick);
+                    }
+
+                    if let Some((goal, tick)) = path.last() {
+                        reservations.park(&self.footprint(*goal, agent.size), *tick);
+                    }
+
+                    result.push(path.into_iter()
+                                    .map(|(cell, tick)| (cell, tick as f32 / ticks_per_second))
+                                    .collect());
+                }
+                None => {
+                    // Agent stays where it is, others need to path around it
+                    let start = self.get_closest_pathable(agent.start);
+                    reservations.park(&self.footprint(start, agent.size), 0);
+                    result.push(Vec::new());
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,210 @@
+use pyo3::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::helpers::grid::Grid;
+use crate::helpers::round_point2;
+use crate::path_find::pos::{Pos, PositionAPI, MULTF32};
+
+const UNREACHABLE: usize = usize::MAX;
+const NO_NEXT: usize = usize::MAX;
+/// Distances from threats are multiplied with this in safety fields, more than 1 makes fleeing
+/// past a threat into open space preferable to hiding in a dead end close to it.
+const FLEE_COEFFICIENT: f32 = 1.2;
+
+/// Walking distances from every cell of the grid to the closest goal.
+/// Many units moving to the same goals can share a single field instead of doing a path search each.
+#[pyclass]
+#[derive(Clone)]
+pub struct DistanceField {
+    distances: Vec<usize>,
+    next: Vec<usize>,
+    width: usize,
+    height: usize,
+}
+
+#[pymethods]
+impl DistanceField {
+    #[getter(width)]
+    fn get_width(&self) -> usize { self.width }
+
+    #[getter(height)]
+    fn get_height(&self) -> usize { self.height }
+
+    /// Walking distance to the closest goal, None if the goals cannot be reached from the position.
+    pub fn distance(&self, position: (f32, f32)) -> Option<f32> {
+        let index = self.index(position)?;
+        let distance = self.distances[index];
+
+        if distance == UNREACHABLE {
+            None
+        } else {
+            Some(distance as f32 / MULTF32)
+        }
+    }
+
+    /// Next cell on the way to the closest goal.
+    /// Returns the same cell when the position is a goal, None when the goals cannot be reached.
+    pub fn next_step(&self, position: (f32, f32)) -> Option<(usize, usize)> {
+        let index = self.index(position)?;
+
+        if self.distances[index] == UNREACHABLE {
+            return None;
+        }
+
+        let next = self.next[index];
+
+        if next == NO_NEXT {
+            Some((index / self.height, index % self.height))
+        } else {
+            Some((next / self.height, next % self.height))
+        }
+    }
+
+    /// Unit vector pointing towards the next step, (0, 0) in goals and None when the goals cannot be reached.
+    pub fn direction(&self, position: (f32, f32)) -> Option<(f32, f32)> {
+        let next = self.next_step(position)?;
+        let dx = next.0 as f32 - position.0;
+        let dy = next.1 as f32 - position.1;
+        let length = (dx * dx + dy * dy).sqrt();
+
+        if length < f32::EPSILON {
+            Some((0.0, 0.0))
+        } else {
+            Some((dx / length, dy / length))
+        }
+    }
+
+    /// Follows the field from the position to the closest goal, at most `max_steps` cells.
+    pub fn path(&self, position: (f32, f32), max_steps: usize) -> Vec<(usize, usize)> {
+        let mut result = Vec::<(usize, usize)>::new();
+        let mut index = match self.index(position) {
+            Some(i) if self.distances[i] != UNREACHABLE => i,
+            _ => return result,
+        };
+
+        result.push((index / self.height, index % self.height));
+
+        while self.next[index] != NO_NEXT && result.len() <= max_steps {
+            index = self.next[index];
+            result.push((index / self.height, index % self.height));
+        }
+
+        result
+    }
+}
+
+impl DistanceField {
+    /// Runs a multi source Dijkstra from the goals over the grid with the movement rules of the api.
+    pub fn new(api: &dyn PositionAPI, grid: &Grid<usize>, goals: &[(usize, usize)]) -> Self {
+        let sources = goals.iter()
+                           .filter(|goal| goal.0 < grid.width() && goal.1 < grid.height() && grid[**goal] > 0)
+                           .map(|goal| (*goal, 0));
+
+        DistanceField::from_sources(api, grid, sources)
+    }
+
+    /// Brogue style safety field for fleeing from the threats.
+    /// Distances from the threats are multiplied with a negative coefficient and scanned again,
+    /// so following the field leads away from the threats and out of dead ends instead of into them.
+    pub fn safety(api: &dyn PositionAPI, grid: &Grid<usize>, threats: &[(usize, usize)]) -> Self {
+        let threat_field = DistanceField::new(api, grid, threats);
+        let max_distance = threat_field.distances
+                                       .iter()
+                                       .filter(|d| **d != UNREACHABLE)
+                                       .max()
+                                       .copied()
+                                       .unwrap_or(0);
+        // Offset keeps the negated distances positive, it doesn't change which way the field leads
+        let offset = (max_distance as f32 * FLEE_COEFFICIENT) as usize;
+        let height = grid.height();
+
+        let sources = threat_field.distances
+                                  .iter()
+                                  .enumerate()
+                                  .map(|(index, distance)| ((index / height, index % height), *distance))
+                                  .filter(|(position, _)| grid[*position] > 0)
+                                  .map(|(position, distance)| match distance {
+                                      // Cells that threats can't reach are as safe as it gets
+                                      UNREACHABLE => (position, 0),
+                                      _ => (position, offset - (distance as f32 * FLEE_COEFFICIENT) as usize),
+                                  });
+
+        DistanceField::from_sources(api, grid, sources)
+    }
+
+    /// Runs a multi source Dijkstra where every source starts with its own distance.
+    fn from_sources<I>(api: &dyn PositionAPI, grid: &Grid<usize>, sources: I) -> Self
+        where I: IntoIterator<Item = ((usize, usize), usize)>
+    {
+        let width = grid.width();
+        let height = grid.height();
+        let mut distances = vec![UNREACHABLE; width * height];
+        let mut next = vec![NO_NEXT; width * height];
+        let mut open = BinaryHeap::new();
+
+        for (source, distance) in sources {
+            let index = source.0 * height + source.1;
+
+            if distance < distances[index] {
+                distances[index] = distance;
+                open.push(Reverse((distance, index)));
+            }
+        }
+
+        while let Some(Reverse((distance, index))) = open.pop() {
+            if distance > distances[index] {
+                continue;
+            }
+
+            let pos = Pos(index / height, index % height);
+
+            for (neighbour, cost) in api.successors(&pos, grid) {
+                let neighbour_index = neighbour.0 * height + neighbour.1;
+                let new_distance = distance + cost;
+
+                if new_distance < distances[neighbour_index] {
+                    distances[neighbour_index] = new_distance;
+                    next[neighbour_index] = index;
+                    open.push(Reverse((new_distance, neighbour_index)));
+                }
+            }
+        }
+
+        DistanceField { distances,
+                        next,
+                        width,
+                        height }
+    }
+
+    /// Raw distance in `MULT` units, None when unreachable or outside of the grid.
+    pub fn distance_u(&self, position: (usize, usize)) -> Option<usize> {
+        if position.0 >= self.width || position.1 >= self.height {
+            return None;
+        }
+
+        let distance = self.distances[position.0 * self.height + position.1];
+
+        if distance == UNREACHABLE {
+            None
+        } else {
+            Some(distance)
+        }
+    }
+
+    /// Index of the cell of the position, None when the position is outside the field or not a number.
+    #[inline]
+    fn index(&self, position: (f32, f32)) -> Option<usize> {
+        if !(position.0 >= 0.0 && position.1 >= 0.0 && position.0.is_finite() && position.1.is_finite()) {
+            return None;
+        }
+
+        let (x, y) = round_point2(position);
+
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            Some(x * self.height + y)
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use arrayvec::ArrayVec;
+use pyo3::prelude::*;
+
+use crate::helpers::grid::Grid;
+use crate::path_find::pos::{Pos, PositionAPI};
+
+/// Limit for the influence of the cells that a path may enter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InfluenceCap {
+    pub max_influence: usize,
+    /// Cells above the limit cost this many times more instead of being unwalkable.
+    pub possible_multiplier: Option<f32>,
+}
+
+impl InfluenceCap {
+    pub fn new(max_influence: f32, possible_multiplier: Option<f32>) -> Self {
+        InfluenceCap { max_influence: max_influence.max(0.0) as usize,
+                       possible_multiplier }
+    }
+
+    /// Whether the cell can be entered at all.
+    #[inline]
+    pub fn allows(&self, value: usize) -> bool { value <= self.max_influence || self.possible_multiplier.is_some() }
+}
+
+/// From python this is either max influence for a hard cap or a tuple of (max influence, multiplier) for a soft cap.
+impl<'source> FromPyObject<'source> for InfluenceCap {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        if let Ok((max_influence, multiplier)) = ob.extract::<(f32, f32)>() {
+            return Ok(InfluenceCap::new(max_influence, Some(multiplier)));
+        }
+
+        Ok(InfluenceCap::new(ob.extract()?, None))
+    }
+}
+
+/// Wraps another position api and applies an influence cap to the cells it moves into.
+pub struct InfluenceCapPosAPI<'a> {
+    pub api: &'a dyn PositionAPI,
+    pub cap: InfluenceCap,
+}
+
+impl PositionAPI for InfluenceCapPosAPI<'_> {
+    #[inline]
+    fn manhattan_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.manhattan_distance(start, end) }
+
+    #[inline]
+    fn euclidean_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.euclidean_distance(start, end) }
+
+    #[inline]
+    fn octile_distance(&self, start: &Pos, end: &Pos) -> usize { self.api.octile_distance(start, end) }
+
+    #[inline]
+    fn successors(&self, pos: &Pos, grid: &Grid<usize>) -> ArrayVec<(Pos, usize), 8> {
+        self.successors_within(pos, grid, ((0, 0), (grid.width(), grid.height())))
+    }
+
+    #[inline]
+    fn successors_within(&self,
+                         pos: &Pos,
+                         grid: &Grid<usize>,
+                         window: ((usize, usize), (usize, usize)))
+                         -> ArrayVec<(Pos, usize), 8> {
+        let mut arr = self.api.successors_within(pos, grid, window);
+
+        match self.cap.possible_multiplier {
+            None => arr.retain(|(p, _)| grid[(p.0, p.1)] <= self.cap.max_influence),
+            Some(multiplier) => {
+                for (p, cost) in arr.iter_mut() {
+                    if grid[(p.0, p.1)] > self.cap.max_influence {
+                        *cost = (*cost as f32 * multiplier) as usize;
+                    }
+                }
+            }
+        }
+
+        arr
+    }
+}
@@ -0,0 +1,700 @@
+use arrayvec::ArrayVec;
+
+use crate::helpers::grid::Grid;
+use crate::path_find::pos::{Pos, DIAGONAL_MINUS_CARDINAL, MULT};
+use crate::path_find::search::{CellMarks, SearchEngine};
+
+/// Cardinal directions in the order used by `JumpTable`.
+const CARDINALS: [(i64, i64); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+const FITS_KNOWN: u8 = 1;
+const FITS: u8 = 2;
+const BLOCKED_KNOWN: u8 = 4;
+const BLOCKED: u8 = 8;
+const NEAR_KNOWN: u8 = 16;
+const NEAR: u8 = 32;
+
+/// Jump Point Search over a uniform cost grid.
+/// Diagonal moves are only allowed when both adjacent cardinal cells are walkable,
+/// which matches the corner cutting rules of `NormalPosAPI`.
+/// Large units follow the rules of `PosLargeAPI`, see `ScanMoves`.
+/// `walkable` must return false for any position outside of the searchable area.
+/// Returns the full path with intermediate cells and the cost in `MULT` units.
+#[allow(clippy::too_many_arguments)]
+pub fn jump_point_search<W, G>(engine: &mut SearchEngine,
+                               start: (usize, usize),
+                               goal: (usize, usize),
+                               width: usize,
+                               height: usize,
+                               large: bool,
+                               walkable: W,
+                               is_goal: G)
+                               -> Option<(Vec<Pos>, usize)>
+    where W: Fn(i64, i64) -> bool,
+          G: Fn(i64, i64) -> bool
+{
+    // Marks are taken out of the engine for the search, so that the successors can update them.
+    let mut marks = std::mem::take(&mut engine.marks);
+
+    if large {
+        marks.begin(width, height);
+    }
+
+    let mut moves = ScanMoves { walkable,
+                                width,
+                                height,
+                                large,
+                                marks: &mut marks };
+    let result = search(engine, &mut moves, start, goal, width, height, is_goal);
+
+    engine.marks = marks;
+    result
+}
+
+/// Same as `jump_point_search` on the whole grid that the table was made for, without any other limits.
+/// Straight jumps are read from the table instead of scanning the grid cell by cell.
+pub fn jump_point_search_table(engine: &mut SearchEngine,
+                               table: &JumpTable,
+                               start: (usize, usize),
+                               goal: (usize, usize))
+                               -> Option<(Vec<Pos>, usize)> {
+    let mut moves = TableMoves { table,
+                                 goal: (goal.0 as i64, goal.1 as i64) };
+    let (width, height) = (table.straight.width(), table.straight.height());
+
+    search(engine, &mut moves, start, goal, width, height, |x, y| x as usize == goal.0 && y as usize == goal.1)
+}
+
+fn search<M, G>(engine: &mut SearchEngine,
+                moves: &mut M,
+                start: (usize, usize),
+                goal: (usize, usize),
+                width: usize,
+                height: usize,
+                is_goal: G)
+                -> Option<(Vec<Pos>, usize)>
+    where M: Moves,
+          G: Fn(i64, i64) -> bool
+{
+    if !moves.fits(start.0 as i64, start.1 as i64) {
+        return None;
+    }
+
+    engine.astar_from_parent(width,
+                             height,
+                             start,
+                             |node, parent| successors(node, parent, moves, &is_goal),
+                             |p| octile((p.0, p.1), goal),
+                             |p| is_goal(p.0 as i64, p.1 as i64))
+          .map(|end| (fill_path(&engine.path(end)), engine.cost(end).unwrap_or_default()))
+}
+
+/// Checks whether a large unit fits in the cell, i.e. the cell is part of a fully walkable 2x2 block.
+#[inline]
+pub fn large_walkable<W>(x: i64, y: i64, walkable: &W) -> bool
+    where W: Fn(i64, i64) -> bool
+{
+    if !walkable(x, y) {
+        return false;
+    }
+
+    let left = walkable(x - 1, y);
+    let right = walkable(x + 1, y);
+    let down = walkable(x, y - 1);
+    let up = walkable(x, y + 1);
+
+    (left && down && walkable(x - 1, y - 1))
+    || (left && up && walkable(x - 1, y + 1))
+    || (right && down && walkable(x + 1, y - 1))
+    || (right && up && walkable(x + 1, y + 1))
+}
+
+/// Whether a 2x2 block on either side of the cardinal step is fully walkable.
+#[inline]
+fn side_block<W>(x: i64, y: i64, direction: (i64, i64), walkable: &W) -> bool
+    where W: Fn(i64, i64) -> bool
+{
+    let (dx, dy) = direction;
+
+    (walkable(x + dy, y + dx) && walkable(x + dx + dy, y + dy + dx))
+    || (walkable(x - dy, y - dx) && walkable(x + dx - dy, y + dy - dx))
+}
+
+/// Precalculated jump point search moves of one unit type on a grid.
+/// Stores whether the unit fits in each cell, which cells are next to a blocked step of a large unit,
+/// and where a straight jump in each cardinal direction ends.
+#[derive(Clone)]
+pub struct JumpTable {
+    large: bool,
+    /// `FITS`, `BLOCKED` and `NEAR` flags of each cell, with a border of empty cells around the grid
+    /// so that the neighbours of every cell can be read without bounds checks.
+    cells: Grid<u8>,
+    /// For each direction in `CARDINALS`, a positive value is the distance to the jump point
+    /// and any other value is minus the number of steps that can be taken before an obstacle.
+    straight: Grid<[i32; 4]>,
+}
+
+impl JumpTable {
+    pub fn new(grid: &Grid<usize>, large: bool) -> Self {
+        let (width, height) = (grid.width(), grid.height());
+        let mut table = JumpTable { large,
+                                    cells: Grid::new(width + 2, height + 2, 0),
+                                    straight: Grid::new(width, height, [0; 4]) };
+        table.update(grid, (0, 0), (width, height));
+        table
+    }
+
+    /// Recalculates the table after the pathability inside the window has changed.
+    /// Cell flags change up to 3 cells around the window,
+    /// straight jumps are recalculated for every row and column that crosses that area.
+    pub fn update(&mut self, grid: &Grid<usize>, window_start: (usize, usize), window_end: (usize, usize)) {
+        let (width, height) = (grid.width(), grid.height());
+        let walkable = |x: i64, y: i64| {
+            x >= 0 && y >= 0 && (x as usize) < width && (y as usize) < height && grid[(x as usize, y as usize)] > 0
+        };
+        let area = |margin: usize| {
+            (window_start.0.saturating_sub(margin)..usize::min(window_end.0 + margin, width),
+             window_start.1.saturating_sub(margin)..usize::min(window_end.1 + margin, height))
+        };
+
+        // Each pass only reads flags of the previous passes from the cells around it.
+        let (xs, ys) = area(1);
+        for x in xs {
+            for y in ys.clone() {
+                let (xi, yi) = (x as i64, y as i64);
+                let fits = if self.large { large_walkable(xi, yi, &walkable) } else { walkable(xi, yi) };
+                self.cells[(x + 1, y + 1)] = if fits { FITS } else { 0 };
+            }
+        }
+
+        if self.large {
+            let (xs, ys) = area(2);
+            for x in xs {
+                for y in ys.clone() {
+                    let (xi, yi) = (x as i64, y as i64);
+                    let blocked = self.fits(xi, yi)
+                                  && CARDINALS.iter().any(|&(dx, dy)| {
+                                                         self.fits(xi + dx, yi + dy)
+                                                         && !side_block(xi, yi, (dx, dy), &walkable)
+                                                     });
+                    let cell = &mut self.cells[(x + 1, y + 1)];
+                    *cell = (*cell & !BLOCKED) | if blocked { BLOCKED } else { 0 };
+                }
+            }
+
+            let (xs, ys) = area(3);
+            for x in xs {
+                for y in ys.clone() {
+                    let (xi, yi) = (x as i64, y as i64);
+                    let near =
+                        (xi - 1..=xi + 1).any(|nx| (yi - 1..=yi + 1).any(|ny| self.flags(nx, ny) & BLOCKED != 0));
+                    let cell = &mut self.cells[(x + 1, y + 1)];
+                    *cell = (*cell & !NEAR) | if near { NEAR } else { 0 };
+                }
+            }
+        }
+
+        // Grid is stored column by column, so rows are updated a column at a time.
+        let (xs, ys) = area(3);
+
+        for x in (0..width).rev() {
+            for y in ys.clone() {
+                self.straight[(x, y)][0] = self.straight_value(&walkable, (x, y), 0);
+            }
+        }
+
+        for x in 0..width {
+            for y in ys.clone() {
+                self.straight[(x, y)][1] = self.straight_value(&walkable, (x, y), 1);
+            }
+        }
+
+        for x in xs {
+            for y in (0..height).rev() {
+                self.straight[(x, y)][2] = self.straight_value(&walkable, (x, y), 2);
+            }
+            for y in 0..height {
+                self.straight[(x, y)][3] = self.straight_value(&walkable, (x, y), 3);
+            }
+        }
+    }
+
+    /// Straight jump from the cell, the value of the next cell in the direction must already be up to date.
+    #[inline(always)]
+    fn straight_value<W>(&self, walkable: &W, cell: (usize, usize), direction_index: usize) -> i32
+        where W: Fn(i64, i64) -> bool
+    {
+        let (x, y) = (cell.0 as i64, cell.1 as i64);
+        let (dx, dy) = CARDINALS[direction_index];
+        let (nx, ny) = (x + dx, y + dy);
+
+        if !self.fits(x, y) || !self.fits(nx, ny) || (self.large && !side_block(x, y, (dx, dy), walkable)) {
+            return 0;
+        }
+
+        let forced = if dx != 0 {
+            (self.fits(nx, ny - 1) && !self.fits(x, ny - 1)) || (self.fits(nx, ny + 1) && !self.fits(x, ny + 1))
+        } else {
+            (self.fits(nx - 1, ny) && !self.fits(nx - 1, y)) || (self.fits(nx + 1, ny) && !self.fits(nx + 1, y))
+        };
+
+        if forced || self.flags(nx, ny) & NEAR != 0 {
+            return 1;
+        }
+
+        let value = self.straight[(nx as usize, ny as usize)][direction_index];
+
+        if value > 0 {
+            value + 1
+        } else {
+            value - 1
+        }
+    }
+
+    #[inline]
+    fn fits(&self, x: i64, y: i64) -> bool { self.flags(x, y) & FITS != 0 }
+
+    #[inline]
+    fn flags(&self, x: i64, y: i64) -> u8 {
+        if x >= -1 && y >= -1 {
+            self.cells[((x + 1) as usize, (y + 1) as usize)]
+        } else {
+            0
+        }
+    }
+}
+
+/// Jump tables of small and large units.
+#[derive(Clone)]
+pub struct JumpTables {
+    pub normal: JumpTable,
+    pub large: JumpTable,
+}
+
+impl JumpTables {
+    pub fn new(grid: &Grid<usize>) -> Self {
+        JumpTables { normal: JumpTable::new(grid, false),
+                     large: JumpTable::new(grid, true) }
+    }
+
+    /// Recalculates both tables after the pathability inside the window has changed.
+    pub fn update(&mut self, grid: &Grid<usize>, window_start: (usize, usize), window_end: (usize, usize)) {
+        self.normal.update(grid, window_start, window_end);
+        self.large.update(grid, window_start, window_end);
+    }
+
+    pub fn copy_from(&mut self, other: &JumpTables) {
+        self.normal.cells.copy_from(&other.normal.cells);
+        self.normal.straight.copy_from(&other.normal.straight);
+        self.large.cells.copy_from(&other.large.cells);
+        self.large.straight.copy_from(&other.large.straight);
+    }
+
+    /// Table of the unit type.
+    #[inline]
+    pub fn get(&self, large: bool) -> &JumpTable {
+        if large {
+            &self.large
+        } else {
+            &self.normal
+        }
+    }
+}
+
+/// Steps the unit can take on the grid.
+/// Large units can only step between two cells when a fully walkable 2x2 block contains both of them.
+/// A* checks the same in `PosLargeAPI`, whose original version also stepped sideways into single open cells.
+/// For diagonal steps that is the normal corner cutting rule on cells where the unit fits,
+/// but a cardinal step between two such cells can still be blocked.
+/// Cells next to those steps are marked so that the search expands them in every direction.
+trait Moves {
+    /// Whether the unit fits in the cell.
+    fn fits(&mut self, x: i64, y: i64) -> bool;
+
+    /// Whether the cell is next to a blocked cardinal step of a large unit.
+    fn irregular(&mut self, x: i64, y: i64) -> bool;
+
+    /// Whether the unit can step from the cell in the direction.
+    fn can_step(&mut self, x: i64, y: i64, direction: (i64, i64)) -> bool;
+
+    /// Moves from the node in the cardinal direction until a jump point, the goal or an obstacle is found.
+    fn jump_straight<G>(&mut self, node: (i64, i64), direction: (i64, i64), is_goal: &G) -> Option<(i64, i64)>
+        where G: Fn(i64, i64) -> bool;
+}
+
+/// Moves that check the grid when the search first looks at a cell.
+/// For large units the checks are kept in the cell marks.
+struct ScanMoves<'a, W> {
+    walkable: W,
+    width: usize,
+    height: usize,
+    large: bool,
+    marks: &'a mut CellMarks,
+}
+
+impl<W> ScanMoves<'_, W> where W: Fn(i64, i64) -> bool
+{
+    #[inline]
+    fn index(&self, x: i64, y: i64) -> Option<usize> {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            Some(x as usize * self.height + y as usize)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the unit fits in the cell and can't step straight into a neighbouring cell where it also fits.
+    fn blocked_step(&mut self, x: i64, y: i64) -> bool {
+        let index = match self.index(x, y) {
+            Some(index) => index,
+            None => return false,
+        };
+        let marks = self.marks.get(index);
+
+        if marks & BLOCKED_KNOWN != 0 {
+            return marks & BLOCKED != 0;
+        }
+
+        let blocked = self.fits(x, y)
+                      && CARDINALS.iter().any(|&(dx, dy)| {
+                                             self.fits(x + dx, y + dy) && !side_block(x, y, (dx, dy), &self.walkable)
+                                         });
+
+        // Reading the marks again, fits may have just updated them.
+        let marks = self.marks.get(index) | BLOCKED_KNOWN | if blocked { BLOCKED } else { 0 };
+        self.marks.set(index, marks);
+        blocked
+    }
+
+    /// Whether a cell reached with a straight step in the direction has a forced neighbour.
+    #[inline]
+    fn forced(&mut self, cell: (i64, i64), direction: (i64, i64)) -> bool {
+        let (x, y) = cell;
+
+        if direction.0 != 0 {
+            let dx = direction.0;
+            (self.fits(x, y - 1) && !self.fits(x - dx, y - 1)) || (self.fits(x, y + 1) && !self.fits(x - dx, y + 1))
+        } else {
+            let dy = direction.1;
+            (self.fits(x - 1, y) && !self.fits(x - 1, y - dy)) || (self.fits(x + 1, y) && !self.fits(x + 1, y - dy))
+        }
+    }
+}
+
+impl<W> Moves for ScanMoves<'_, W> where W: Fn(i64, i64) -> bool
+{
+    #[inline]
+    fn fits(&mut self, x: i64, y: i64) -> bool {
+        if !self.large {
+            return (self.walkable)(x, y);
+        }
+
+        let index = match self.index(x, y) {
+            Some(index) => index,
+            None => return false,
+        };
+        let mut marks = self.marks.get(index);
+
+        if marks & FITS_KNOWN == 0 {
+            marks |= FITS_KNOWN;
+
+            if large_walkable(x, y, &self.walkable) {
+                marks |= FITS;
+            }
+
+            self.marks.set(index, marks);
+        }
+
+        marks & FITS != 0
+    }
+
+    #[inline]
+    fn irregular(&mut self, x: i64, y: i64) -> bool {
+        if !self.large {
+            return false;
+        }
+
+        let index = match self.index(x, y) {
+            Some(index) => index,
+            None => return false,
+        };
+        let marks = self.marks.get(index);
+
+        if marks & NEAR_KNOWN != 0 {
+            return marks & NEAR != 0;
+        }
+
+        let mut near = false;
+
+        for nx in x - 1..=x + 1 {
+            for ny in y - 1..=y + 1 {
+                near = near || self.blocked_step(nx, ny);
+            }
+        }
+
+        let marks = self.marks.get(index) | NEAR_KNOWN | if near { NEAR } else { 0 };
+        self.marks.set(index, marks);
+        near
+    }
+
+    #[inline]
+    fn can_step(&mut self, x: i64, y: i64, direction: (i64, i64)) -> bool {
+        let (dx, dy) = direction;
+
+        if dx != 0 && dy != 0 {
+            self.fits(x + dx, y + dy) && self.fits(x + dx, y) && self.fits(x, y + dy)
+        } else {
+            self.fits(x + dx, y + dy) && (!self.large || side_block(x, y, direction, &self.walkable))
+        }
+    }
+
+    fn jump_straight<G>(&mut self, node: (i64, i64), direction: (i64, i64), is_goal: &G) -> Option<(i64, i64)>
+        where G: Fn(i64, i64) -> bool
+    {
+        let (dx, dy) = direction;
+        let (mut x, mut y) = node;
+
+        loop {
+            if !self.can_step(x, y, direction) {
+                return None;
+            }
+
+            x += dx;
+            y += dy;
+
+            if is_goal(x, y) || self.irregular(x, y) || self.forced((x, y), direction) {
+                return Some((x, y));
+            }
+        }
+    }
+}
+
+/// Moves read from a `JumpTable`, the goal is a single cell.
+struct TableMoves<'a> {
+    table: &'a JumpTable,
+    goal: (i64, i64),
+}
+
+impl Moves for TableMoves<'_> {
+    #[inline]
+    fn fits(&mut self, x: i64, y: i64) -> bool { self.table.flags(x, y) & FITS != 0 }
+
+    #[inline]
+    fn irregular(&mut self, x: i64, y: i64) -> bool { self.table.flags(x, y) & NEAR != 0 }
+
+    #[inline]
+    fn can_step(&mut self, x: i64, y: i64, direction: (i64, i64)) -> bool {
+        let (dx, dy) = direction;
+
+        if dx != 0 && dy != 0 {
+            self.fits(x + dx, y + dy) && self.fits(x + dx, y) && self.fits(x, y + dy)
+        } else {
+            self.fits(x, y) && self.table.straight[(x as usize, y as usize)][cardinal_index(direction)] != 0
+        }
+    }
+
+    #[inline]
+    fn jump_straight<G>(&mut self, node: (i64, i64), direction: (i64, i64), _is_goal: &G) -> Option<(i64, i64)>
+        where G: Fn(i64, i64) -> bool
+    {
+        let (x, y) = node;
+        let (dx, dy) = direction;
+        let value = self.table.straight[(x as usize, y as usize)][cardinal_index(direction)];
+        let reach = value.unsigned_abs() as i64;
+
+        // Steps to the goal when it's on the same line
+        let to_goal = if dx != 0 && self.goal.1 == y {
+            (self.goal.0 - x) * dx
+        } else if dy != 0 && self.goal.0 == x {
+            (self.goal.1 - y) * dy
+        } else {
+            0
+        };
+
+        if to_goal > 0 && to_goal <= reach {
+            Some(self.goal)
+        } else if value > 0 {
+            Some((x + dx * reach, y + dy * reach))
+        } else {
+            None
+        }
+    }
+}
+
+/// Index of the cardinal direction in `CARDINALS`.
+#[inline]
+fn cardinal_index(direction: (i64, i64)) -> usize {
+    match direction {
+        (1, 0) => 0,
+        (-1, 0) => 1,
+        (0, 1) => 2,
+        _ => 3,
+    }
+}
+
+#[inline]
+fn octile(first: (usize, usize), other: (usize, usize)) -> usize {
+    let dx = first.0.abs_diff(other.0);
+    let dy = first.1.abs_diff(other.1);
+
+    if dx > dy {
+        MULT * dx + DIAGONAL_MINUS_CARDINAL * dy
+    } else {
+        MULT * dy + DIAGONAL_MINUS_CARDINAL * dx
+    }
+}
+
+/// Jump points reachable from the node with their costs.
+fn successors<M, G>(node: &Pos, parent: Option<Pos>, moves: &mut M, is_goal: &G) -> ArrayVec<(Pos, usize), 8>
+    where M: Moves,
+          G: Fn(i64, i64) -> bool
+{
+    let node_i = (node.0 as i64, node.1 as i64);
+    let parent_i = parent.map(|p| (p.0 as i64, p.1 as i64));
+    let mut result = ArrayVec::new();
+
+    for direction in pruned_directions(node_i, parent_i, moves) {
+        if let Some(jump_point) = jump(node_i, direction, moves, is_goal) {
+            let jump_u = (jump_point.0 as usize, jump_point.1 as usize);
+            result.push((Pos(jump_u.0, jump_u.1), octile((node.0, node.1), jump_u)));
+        }
+    }
+
+    result
+}
+
+/// Directions worth exploring from the node, based on the direction we arrived from.
+fn pruned_directions<M>(node: (i64, i64), parent: Option<(i64, i64)>, moves: &mut M) -> ArrayVec<(i64, i64), 8>
+    where M: Moves
+{
+    let (x, y) = node;
+    let mut directions = ArrayVec::<(i64, i64), 8>::new();
+
+    let (px, py) = match parent {
+        Some(p) if !moves.irregular(x, y) => p,
+        _ => {
+            // Start node or next to a blocked step, every neighbour is a candidate.
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    if (dx != 0 || dy != 0) && moves.can_step(x, y, (dx, dy)) {
+                        directions.push((dx, dy));
+                    }
+                }
+            }
+
+            return directions;
+        }
+    };
+
+    let dx = (x - px).signum();
+    let dy = (y - py).signum();
+
+    if dx != 0 && dy != 0 {
+        let vertical = moves.fits(x, y + dy);
+        let horizontal = moves.fits(x + dx, y);
+
+        if vertical {
+            directions.push((0, dy));
+        }
+        if horizontal {
+            directions.push((dx, 0));
+        }
+        if vertical && horizontal {
+            directions.push((dx, dy));
+        }
+    } else if dx != 0 {
+        let next = moves.fits(x + dx, y);
+        let up = moves.fits(x, y + 1);
+        let down = moves.fits(x, y - 1);
+
+        if next {
+            directions.push((dx, 0));
+            if up {
+                directions.push((dx, 1));
+            }
+            if down {
+                directions.push((dx, -1));
+            }
+        }
+        if up {
+            directions.push((0, 1));
+        }
+        if down {
+            directions.push((0, -1));
+        }
+    } else {
+        let next = moves.fits(x, y + dy);
+        let right = moves.fits(x + 1, y);
+        let left = moves.fits(x - 1, y);
+
+        if next {
+            directions.push((0, dy));
+            if right {
+                directions.push((1, dy));
+            }
+            if left {
+                directions.push((-1, dy));
+            }
+        }
+        if right {
+            directions.push((1, 0));
+        }
+        if left {
+            directions.push((-1, 0));
+        }
+    }
+
+    directions
+}
+
+/// Moves from the node along the direction until a jump point, the goal or an obstacle is found.
+fn jump<M, G>(node: (i64, i64), direction: (i64, i64), moves: &mut M, is_goal: &G) -> Option<(i64, i64)>
+    where M: Moves,
+          G: Fn(i64, i64) -> bool
+{
+    let (dx, dy) = direction;
+
+    if dx == 0 || dy == 0 {
+        return moves.jump_straight(node, direction, is_goal);
+    }
+
+    let (mut x, mut y) = node;
+
+    loop {
+        if !moves.can_step(x, y, direction) {
+            return None;
+        }
+
+        x += dx;
+        y += dy;
+
+        if is_goal(x, y)
+           || moves.irregular(x, y)
+           || moves.jump_straight((x, y), (dx, 0), is_goal).is_some()
+           || moves.jump_straight((x, y), (0, dy), is_goal).is_some()
+        {
+            return Some((x, y));
+        }
+    }
+}
+
+/// Fills in the cells between the jump points of the path.
+fn fill_path(jump_points: &[(usize, usize)]) -> Vec<Pos> {
+    let mut path: Vec<Pos> = jump_points.iter().take(1).map(|p| Pos(p.0, p.1)).collect();
+
+    for pair in jump_points.windows(2) {
+        let (x0, y0) = (pair[0].0 as i64, pair[0].1 as i64);
+        let (x1, y1) = (pair[1].0 as i64, pair[1].1 as i64);
+        let dx = (x1 - x0).signum();
+        let dy = (y1 - y0).signum();
+        let (mut x, mut y) = (x0, y0);
+
+        while (x, y) != (x1, y1) {
+            x += dx;
+            y += dy;
+            path.push(Pos(x as usize, y as usize));
+        }
+    }
+
+    path
+}
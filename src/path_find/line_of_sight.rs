@@ -0,0 +1,111 @@
+/// Whether the segment is clear, the first blocking cell and the traversed cells if they were requested.
+pub type RaycastResult = (bool, Option<(usize, usize)>, Vec<(usize, usize)>);
+
+/// Visits every cell that the segment between the two cell centers passes through (supercover line).
+/// When the segment passes exactly through a corner, both cells next to the corner are visited.
+/// Traversal stops when `visit` returns false; returns true if the whole segment was visited.
+pub fn traverse<F>(start: (i64, i64), end: (i64, i64), mut visit: F) -> bool
+    where F: FnMut(i64, i64) -> bool
+{
+    let dx = end.0 - start.0;
+    let dy = end.1 - start.1;
+    let nx = dx.abs();
+    let ny = dy.abs();
+    let sx = dx.signum();
+    let sy = dy.signum();
+
+    let (mut x, mut y) = start;
+    let (mut ix, mut iy) = (0i64, 0i64);
+
+    if !visit(x, y) {
+        return false;
+    }
+
+    while ix < nx || iy < ny {
+        let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+
+        if decision == 0 {
+            // Passing through a corner
+            if !visit(x + sx, y) || !visit(x, y + sy) {
+                return false;
+            }
+
+            x += sx;
+            y += sy;
+            ix += 1;
+            iy += 1;
+        } else if decision < 0 {
+            x += sx;
+            ix += 1;
+        } else {
+            y += sy;
+            iy += 1;
+        }
+
+        if !visit(x, y) {
+            return false;
+        }
+    }
+
+    true
+}
+
+/// Traverses the segment until a cell for which `blocked` returns true.
+/// Traversed cells include the blocking cell and are only collected when `include_cells` is set.
+pub fn raycast<F>(start: (usize, usize), end: (usize, usize), include_cells: bool, blocked: F) -> RaycastResult
+    where F: Fn(usize, usize) -> bool
+{
+    let mut possible_blocking = None;
+    let mut cells = Vec::new();
+
+    traverse((start.0 as i64, start.1 as i64), (end.0 as i64, end.1 as i64), |x, y| {
+        let cell = (x as usize, y as usize);
+
+        if include_cells {
+            cells.push(cell);
+        }
+
+        if blocked(cell.0, cell.1) {
+            possible_blocking = Some(cell);
+            return false;
+        }
+
+        true
+    });
+
+    (possible_blocking.is_none(), possible_blocking, cells)
+}
+
+/// Reduces a cell path to the minimal set of waypoints where every straight segment satisfies `clear`.
+/// `clear` receives the cell and the highest cost of the original path cells that the segment replaces.
+pub fn string_pull<C, F>(path: &[(usize, usize)], cost: C, clear: F) -> Vec<(usize, usize)>
+    where C: Fn((usize, usize)) -> usize,
+          F: Fn(i64, i64, usize) -> bool
+{
+    if path.len() < 3 {
+        return path.to_vec();
+    }
+
+    let mut waypoints = vec![path[0]];
+    let mut anchor = 0;
+    let mut max_cost = cost(path[0]);
+
+    for i in 1..path.len() {
+        let candidate_max = usize::max(max_cost, cost(path[i]));
+        let from = (path[anchor].0 as i64, path[anchor].1 as i64);
+        let to = (path[i].0 as i64, path[i].1 as i64);
+
+        // Neighbouring cells of the original path are always connected
+        if i == anchor + 1 || traverse(from, to, |x, y| clear(x, y, candidate_max)) {
+            max_cost = candidate_max;
+            continue;
+        }
+
+        anchor = i - 1;
+        waypoints.push(path[anchor]);
+        max_cost = usize::max(cost(path[anchor]), cost(path[i]));
+    }
+
+    waypoints.push(path[path.len() - 1]);
+    waypoints
+}
@@ -1,27 +1,78 @@
-use pathfinding::prelude::{astar, dijkstra_all, dijkstra_partial};
+use pathfinding::prelude::astar;
 use pyo3::prelude::*;
+use std::sync::{Mutex, TryLockError};
 
+use crate::error::{self, PathlibError, PathlibResult};
+use crate::helpers::grid::Grid;
 use crate::helpers::point2_f32;
 use crate::mapping::vision::VisionMap;
+use crate::path_find::batch::PathQuery;
+use crate::path_find::clearance::ClearancePosAPI;
+use crate::path_find::distance_field::DistanceField;
+use crate::path_find::influence_cap::{InfluenceCap, InfluenceCapPosAPI};
+use crate::path_find::line_of_sight::RaycastResult;
+use crate::path_find::planner::PathPlanner;
 use crate::path_find::pos::Pos;
-use crate::path_find::pos::{InfluencedPosAPI, InvertPosAPI, NormalPosAPI, PositionAPI};
+use crate::path_find::pos::{InfluencedPosAPI, NormalPosAPI, PositionAPI};
 use crate::path_find::pos_large::{InfluencedPosLargeAPI, PosLargeAPI};
+use crate::path_find::search::SearchBudget;
 
+pub mod alternatives;
 mod angles;
+pub mod batch;
+mod clearance;
+mod components;
+pub mod cooperative;
+pub mod distance_field;
+pub mod influence_cap;
+mod jps;
+pub mod obstacles;
+pub mod line_of_sight;
+pub mod nearest;
+pub mod path_stats;
+pub mod planner;
 pub mod pos;
 mod pos_large;
 pub mod rectangle;
+pub mod search;
 mod search_grid;
 
+/// Heuristic value in `find_path` that selects jump point search
+pub const JUMP_POINT_SEARCH: u8 = 3;
+
+/// Outcome of a budgeted path search, returned to python as an integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PathStatus {
+    /// Path reaches the end.
+    Complete = 0,
+    /// Budget ran out, path leads to the position closest to the end found so far.
+    Partial = 1,
+    /// End can't be reached, path leads to the closest reachable position.
+    Unreachable = 2,
+}
+
+/// Path, distance and `PathStatus` of a budgeted search.
+pub type PathAndStatus = (Vec<(usize, usize)>, f32, u8);
+
+/// Path, distance and the start and end that the search used after auto correction.
+pub type CorrectedPath = (Vec<(usize, usize)>, f32, ((usize, usize), (usize, usize)));
+
 #[pyclass]
 pub struct PathFind {
-    pub map: Vec<Vec<usize>>,
-    original_map: Vec<Vec<usize>>,
+    pub map: Grid<usize>,
+    original_map: Grid<usize>,
+    clearance: Grid<usize>,
+    original_clearance: Grid<usize>,
+    components: components::Components,
+    obstacles: obstacles::Obstacles,
+    jump_tables: jps::JumpTables,
+    original_jump_tables: jps::JumpTables,
     pub width: usize,
     pub height: usize,
     normal_influence: usize,
     auto_correct: bool,
     free_finder: search_grid::FreeFinder,
+    search: Mutex<search::SearchEngine>,
 }
 
 #[inline]
@@ -53,78 +104,541 @@ pub fn euclidean_distance(first: (f32, f32), other: (f32, f32)) -> f32 {
 impl PathFind {
     pub fn test_normalize_influence(&mut self, value: usize) -> usize {
         self.normalize_influence(value);
-        let mut sum: usize = 0;
-        for x in &self.map {
-            sum += x.iter().sum::<usize>();
-        }
-        sum
+        self.map.iter().sum::<usize>()
     }
 }
 
 impl PathFind {
-    pub fn new_internal(map: Vec<Vec<usize>>) -> Self {
-        let width = map.len();
+    pub fn new_internal<G: Into<Grid<usize>>>(map: G) -> Self {
+        let map = map.into();
+        let width = map.width();
         let original_map = map.clone();
-        let height = map[0].len();
+        let height = map.height();
         let normal_influence: usize = 1;
         let auto_correct: bool = true;
         let free_finder = search_grid::FreeFinder::new();
+        let search = Mutex::new(search::SearchEngine::new(width, height));
+        let clearance = clearance::create_clearance(&map);
+        let original_clearance = clearance.clone();
+        let components = components::Components::new(&map);
+        let obstacles = obstacles::Obstacles::new(width, height);
+        let jump_tables = jps::JumpTables::new(&map);
+        let original_jump_tables = jump_tables.clone();
 
         PathFind { map,
                    original_map,
+                   clearance,
+                   original_clearance,
+                   components,
+                   obstacles,
+                   jump_tables,
+                   original_jump_tables,
                    width,
                    height,
                    normal_influence,
                    auto_correct,
-                   free_finder }
+                   free_finder,
+                   search }
     }
     // Removes multiple blocks on the grid and makes it pathable
     // center = center of block
     pub fn remove_blocks_rust(&mut self, centers: &[(f32, f32)], size: (usize, usize)) {
         for center in centers {
-            let rect = rectangle::Rectangle::init_from_center(*center, size, self.width, self.height);
+            self.remove_block(*center, size);
+        }
+    }
 
-            for x in rect.x..rect.x_end {
-                for y in rect.y..rect.y_end {
-                    self.map[x][y] = self.normal_influence;
+    // Creates a block on the grid that is not pathable
+    // center = center of building
+    pub fn create_blocks_rust(&mut self, centers: &[(f32, f32)], size: (usize, usize)) -> Vec<usize> {
+        centers.iter().map(|center| self.create_block(*center, size)).collect()
+    }
+
+    /// Id that the next obstacle placed with create_block gets.
+    #[inline]
+    pub fn next_obstacle_id(&self) -> usize { self.obstacles.next_id() }
+
+    /// Places an obstacle with the given id, replacing an earlier obstacle with the same id.
+    pub fn add_obstacle(&mut self, id: usize, obstacle: obstacles::Obstacle) {
+        self.remove_obstacle(id);
+        let rect = obstacle.bounds(self.width, self.height);
+        self.obstacles.add(id, obstacle, &mut self.map);
+        self.update_pathability(&rect);
+    }
+
+    /// Removes the obstacle with the id and returns it, None if there is no such obstacle.
+    pub fn remove_obstacle(&mut self, id: usize) -> Option<obstacles::Obstacle> {
+        let obstacle = self.obstacles.remove(id, &mut self.map)?;
+        self.update_pathability(&obstacle.bounds(self.width, self.height));
+        Some(obstacle)
+    }
+
+    /// Removes unnecessary turns from the path with line of sight checks, following the rules of the query.
+    /// Large units require the 2x2 clearance used by large path finding on every crossed cell,
+    /// with a radius every crossed cell needs clearance for it and a hard influence cap is never crossed.
+    /// With influence or a soft cap, shortcuts may not cross cells with higher influence
+    /// than the part of the path they replace.
+    pub fn smooth_path(&self, path: &[(usize, usize)], query: &PathQuery) -> (Vec<(f32, f32)>, f32) {
+        let grid = &self.map;
+        let (width, height) = (self.width as i64, self.height as i64);
+        let (possible_radius, possible_cap) = (query.possible_radius, query.possible_influence_cap);
+        let walkable =
+            |x: i64, y: i64| x >= 0 && y >= 0 && x < width && y < height && grid[(x as usize, y as usize)] > 0;
+        let cost = |p: (usize, usize)| {
+            let capped = possible_cap.is_some_and(|cap| grid[p] > cap.max_influence);
+            if query.influence || capped {
+                grid[p]
+            } else {
+                0
+            }
+        };
+        let clear = |x: i64, y: i64, max_cost: usize| {
+            let fits = if query.large { jps::large_walkable(x, y, &walkable) } else { walkable(x, y) };
+            let position = (x as usize, y as usize);
+            fits && self.is_allowed(position, possible_radius, possible_cap) && cost(position) <= max_cost
+        };
+
+        let waypoints = line_of_sight::string_pull(path, cost, clear);
+        let mut distance = 0f32;
+
+        for pair in waypoints.windows(2) {
+            distance += euclidean_distance(point2_f32(pair[0]), point2_f32(pair[1]));
+        }
+
+        (waypoints.into_iter().map(point2_f32).collect(), distance)
+    }
+
+    /// Runs a partial Dijkstra with the reusable search buffers and collects every found position.
+    /// Searches running at the same time, e.g. in batches, get their own temporary buffers.
+    fn dijkstra_destinations<A, S>(&self, api: &A, start: (usize, usize), stop: S) -> Vec<((usize, usize), f32)>
+        where A: PositionAPI + ?Sized,
+              S: FnMut(&Pos) -> bool
+    {
+        self.with_search(|engine| {
+                let grid = &self.map;
+                engine.dijkstra_partial(grid.width(), grid.height(), start, |p| api.successors(p, grid), stop);
+                engine.reached()
+                      .map(|(pos, cost)| (pos, cost as f32 / pos::MULTF32))
+                      .collect()
+            })
+    }
+
+    /// Calls the function with the shared search engine, or with a new one when it's in use by another thread.
+    fn with_search<R, F>(&self, run: F) -> R
+        where F: FnOnce(&mut search::SearchEngine) -> R
+    {
+        match self.search.try_lock() {
+            Ok(mut engine) => run(&mut engine),
+            Err(TryLockError::Poisoned(poisoned)) => run(&mut poisoned.into_inner()),
+            Err(TryLockError::WouldBlock) => run(&mut search::SearchEngine::new(self.width, self.height)),
+        }
+    }
+
+    pub fn update_planner_rust(&self, planner: &mut PathPlanner, changed_cells: &[(usize, usize)]) {
+        self.with_api(planner.large, planner.influence, None, None, |api| {
+                planner.update(api, &self.map, changed_cells)
+            })
+    }
+
+    /// Calls the function with the position api matching the unit type.
+    /// With a radius only cells with enough clearance for the unit are pathable,
+    /// with an influence cap the cells above it are unpathable or more expensive.
+    fn with_api<R, F>(&self,
+                      large: bool,
+                      influence: bool,
+                      possible_radius: Option<f32>,
+                      possible_cap: Option<InfluenceCap>,
+                      f: F)
+                      -> R
+        where F: FnOnce(&dyn PositionAPI) -> R
+    {
+        let normal_api = NormalPosAPI();
+        let large_api = PosLargeAPI();
+        let influence_api = InfluencedPosAPI { normal_influence: self.normal_influence };
+        let influence_large_api = InfluencedPosLargeAPI { normal_influence: self.normal_influence };
+
+        let api: &dyn PositionAPI = match (large, influence) {
+            (false, false) => &normal_api,
+            (true, false) => &large_api,
+            (false, true) => &influence_api,
+            (true, true) => &influence_large_api,
+        };
+
+        let clearance_api;
+        let api: &dyn PositionAPI = match possible_radius {
+            None => api,
+            Some(radius) => {
+                clearance_api = ClearancePosAPI { api,
+                                                  clearance: &self.clearance,
+                                                  required: clearance::required_clearance(radius) };
+                &clearance_api
+            }
+        };
+
+        match possible_cap {
+            None => f(api),
+            Some(cap) => f(&InfluenceCapPosAPI { api, cap }),
+        }
+    }
+
+    pub fn check_position(&self, position: (usize, usize)) -> PathlibResult<()> {
+        error::check_position(position, self.width, self.height)
+    }
+
+    pub fn check_positions(&self, positions: &[(usize, usize)]) -> PathlibResult<()> {
+        positions.iter().try_for_each(|p| self.check_position(*p))
+    }
+
+    /// Validates the positions and the window of a path query.
+    pub fn check_path_query(&self,
+                            start: (usize, usize),
+                            end: (usize, usize),
+                            possible_window: Option<((usize, usize), (usize, usize))>)
+                            -> PathlibResult<()> {
+        self.check_position(start)?;
+        self.check_position(end)?;
+
+        match possible_window {
+            Some(window) => error::check_window(window, self.width, self.height),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates that the planner was created for a grid of the same size.
+    pub fn check_planner(&self, planner: &PathPlanner) -> PathlibResult<()> {
+        let (width, height) = planner.size();
+
+        if (width, height) != (self.width, self.height) {
+            let reason = format!("planner was created for a {}x{} grid, but the grid is {}x{}",
+                                 width, height, self.width, self.height);
+            return Err(PathlibError::InvalidArgument(reason));
+        }
+
+        Ok(())
+    }
+
+    /// Recalculates clearance, connected components and jump tables around the rectangle
+    /// after its pathability has changed.
+    fn update_pathability(&mut self, rect: &rectangle::Rectangle) {
+        if rect.x >= rect.x_end || rect.y >= rect.y_end {
+            return;
+        }
+
+        self.components.update(&self.map, (rect.x, rect.y), (rect.x_end, rect.y_end));
+        self.jump_tables.update(&self.map, (rect.x, rect.y), (rect.x_end, rect.y_end));
+
+        let margin = clearance::MAX_CLEARANCE_CELLS;
+        clearance::update_clearance(&mut self.clearance,
+                                    &self.map,
+                                    (rect.x.saturating_sub(margin), rect.y.saturating_sub(margin)),
+                                    (rect.x_end + margin, rect.y_end + margin));
+    }
+
+    /// Whether a pathable cell has enough clearance for the radius and is below the hard influence cap.
+    #[inline]
+    fn is_allowed(&self,
+                  position: (usize, usize),
+                  possible_radius: Option<f32>,
+                  possible_influence_cap: Option<InfluenceCap>)
+                  -> bool {
+        possible_radius.is_none_or(|radius| self.clearance[position] >= clearance::required_clearance(radius))
+        && possible_influence_cap.is_none_or(|cap| cap.allows(self.map[position]))
+    }
+
+    #[inline]
+    fn jump_point_path(&self,
+                       start: (usize, usize),
+                       goal: (usize, usize),
+                       large: bool,
+                       possible_window: Option<((usize, usize), (usize, usize))>,
+                       possible_u_distance: Option<usize>,
+                       allowed: impl Fn((usize, usize)) -> bool)
+                       -> Option<(Vec<Pos>, usize)> {
+        let grid = &self.map;
+        let ((x0, y0), (x1, y1)) = possible_window.unwrap_or(((0, 0), (self.width, self.height)));
+        let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+        let walkable = |x: i64, y: i64| {
+            x >= x0
+            && y >= y0
+            && x < x1
+            && y < y1
+            && grid[(x as usize, y as usize)] > 0
+            && allowed((x as usize, y as usize))
+        };
+        let is_goal = |x: i64, y: i64| match possible_u_distance {
+            None => x as usize == goal.0 && y as usize == goal.1,
+            Some(u_distance) => octile_distance((x as usize, y as usize), goal) < u_distance,
+        };
+
+        self.with_search(|engine| {
+                jps::jump_point_search(engine, start, goal, self.width, self.height, large, walkable, is_goal)
+            })
+    }
+
+    /// Jump point search on the whole grid using the jump tables.
+    #[inline]
+    fn jump_point_table_path(&self,
+                             start: (usize, usize),
+                             goal: (usize, usize),
+                             large: bool)
+                             -> Option<(Vec<Pos>, usize)> {
+        self.with_search(|engine| jps::jump_point_search_table(engine, self.jump_tables.get(large), start, goal))
+    }
+
+    pub fn find_destinations_in_with(&self,
+                                     start: (usize, usize),
+                                     distance: f32,
+                                     possible_radius: Option<f32>,
+                                     possible_cap: Option<InfluenceCap>)
+                                     -> Vec<((usize, usize), f32)> {
+        let start_pos: Pos = Pos(start.0, start.1);
+        let u_distance = (distance * pos::MULTF32) as usize;
+
+        self.with_api(false, false, possible_radius, possible_cap, |api| {
+                self.dijkstra_destinations(api, start, |p| api.octile_distance(p, &start_pos) > u_distance)
+            })
+    }
+
+    /// Same as find_path from python, with the parameters in a `PathQuery`.
+    pub fn find_path(&self, query: PathQuery) -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        self.check_path_query(query.start, query.end, query.possible_window)?;
+        Ok(self.find_path_inline(&query))
+    }
+
+    /// Same as find_path_corrected from python, with the parameters in a `PathQuery`.
+    pub fn find_path_corrected(&self, query: PathQuery) -> PathlibResult<CorrectedPath> {
+        self.check_path_query(query.start, query.end, query.possible_window)?;
+        Ok(self.find_path_corrected_inline(&query))
+    }
+
+    /// Same as find_path_smooth from python, with the parameters in a `PathQuery`.
+    pub fn find_path_smooth(&self, query: PathQuery) -> PathlibResult<(Vec<(f32, f32)>, f32)> {
+        self.check_path_query(query.start, query.end, query.possible_window)?;
+        let (path, _) = self.find_path_inline(&query);
+        Ok(self.smooth_path(&path, &query))
+    }
+
+    #[inline]
+    pub(crate) fn find_path_inline(&self, query: &PathQuery) -> (Vec<(usize, usize)>, f32) {
+        let (path, distance, _) = self.find_path_corrected_inline(query);
+        (path, distance)
+    }
+
+    /// Start and end of the query moved to the closest cells that the search can use, when auto correct is on.
+    /// Those are cells inside the window with room for a large unit or the radius and below a hard influence cap.
+    fn corrected_endpoints(&self, query: &PathQuery) -> ((usize, usize), (usize, usize)) {
+        let ((x0, y0), (x1, y1)) = query.possible_window.unwrap_or(((0, 0), (self.width, self.height)));
+        let (x0, y0, x1, y1) = (x0 as i64, y0 as i64, x1 as i64, y1 as i64);
+        let walkable =
+            |x: i64, y: i64| x >= x0 && y >= y0 && x < x1 && y < y1 && self.map[(x as usize, y as usize)] > 0;
+        let usable = |p: (usize, usize)| {
+            let (x, y) = (p.0 as i64, p.1 as i64);
+            let fits = if query.large { jps::large_walkable(x, y, &walkable) } else { walkable(x, y) };
+            fits && self.is_allowed(p, query.possible_radius, query.possible_influence_cap)
+        };
+        let correct = |p: (usize, usize)| {
+            if !self.auto_correct || usable(p) {
+                p
+            } else {
+                self.free_finder.find_free_where(p, &self.map, usable)
+            }
+        };
+
+        (correct(query.start), correct(query.end))
+    }
+
+    /// Same as find_path_inline, but also returns the start and end after auto correction.
+    fn find_path_corrected_inline(&self, query: &PathQuery) -> CorrectedPath {
+        let (corrected_start, corrected_end) = self.corrected_endpoints(query);
+        let grid: &Grid<usize> = &self.map;
+        let start: Pos = Pos(corrected_start.0, corrected_start.1);
+        let goal: Pos = Pos(corrected_end.0, corrected_end.1);
+        let (large, influence) = (query.large, query.influence);
+        let (possible_radius, possible_influence_cap) = (query.possible_radius, query.possible_influence_cap);
+
+        let result: Option<(Vec<Pos>, usize)>;
+        let possible_u_distance: Option<usize>;
+
+        if influence {
+            possible_u_distance = query.possible_distance_from_target
+                                       .map(|d| (d * pos::MULTF32 * (self.normal_influence as f32)) as usize);
+        } else {
+            possible_u_distance = query.possible_distance_from_target.map(|d| (d * pos::MULTF32) as usize);
+        }
+
+        let heuristic = query.possible_heuristic.unwrap_or(0);
+
+        // Soft cap changes the move costs, so it can't use jump point search
+        let soft_cap = possible_influence_cap.is_some_and(|cap| cap.possible_multiplier.is_some());
+
+        // Jump tables only know the pathability of the whole grid
+        let unlimited = query.possible_window.is_none()
+                        && possible_radius.is_none()
+                        && possible_influence_cap.is_none()
+                        && possible_u_distance.is_none();
+
+        if heuristic == JUMP_POINT_SEARCH && unlimited && !influence {
+            result = self.jump_point_table_path(corrected_start, corrected_end, large);
+        } else if heuristic == JUMP_POINT_SEARCH && !influence && !soft_cap {
+            result = self.jump_point_path(corrected_start,
+                                          corrected_end,
+                                          large,
+                                          query.possible_window,
+                                          possible_u_distance,
+                                          |p| self.is_allowed(p, possible_radius, possible_influence_cap));
+        } else {
+            let window = query.possible_window.unwrap_or(((0, 0), (grid.width(), grid.height())));
+
+            result = self.with_api(large, influence, possible_radius, possible_influence_cap, |api| {
+                             let estimate = |p: &Pos| match heuristic {
+                                 0 => api.manhattan_distance(p, &goal),
+                                 1 => api.octile_distance(p, &goal),
+                                 _ => api.euclidean_distance(p, &goal),
+                             };
+
+                             astar(&start,
+                                   |p| api.successors_within(p, grid, window),
+                                   estimate,
+                                   |p| possible_u_distance.map_or(*p == goal, |u_distance| estimate(p) < u_distance))
+                         });
+        }
+
+        let mut path: Vec<(usize, usize)>;
+        let distance: f32;
+
+        match result {
+            None => {
+                path = Vec::<(usize, usize)>::new();
+                distance = 0.0
+            }
+            Some(t) => {
+                distance = (t.1 as f32) / pos::MULTF32;
+                path = Vec::<(usize, usize)>::with_capacity(t.0.len());
+                for pos in t.0 {
+                    path.push((pos.0, pos.1))
                 }
             }
         }
+
+        (path, distance, (corrected_start, corrected_end))
     }
 
-    // Creates a block on the grid that is not pathable
-    // center = center of building
-    pub fn create_blocks_rust(&mut self, centers: &[(f32, f32)], size: (usize, usize)) {
-        for center in centers {
-            let rect = rectangle::Rectangle::init_from_center(*center, size, self.width, self.height);
+    #[inline]
+    fn lowest_influence_walk_inline(&self, center: (usize, usize), distance: f32) -> ((usize, usize), f32) {
+        let destinations = self.find_destinations_in_inline(center, distance);
 
-            for x in rect.x..rect.x_end {
-                for y in rect.y..rect.y_end {
-                    self.map[x][y] = 0;
+        let mut min_value = std::usize::MAX;
+        let mut min_distance = std::f32::MAX;
+        let mut min_position = center;
+
+        for destination in destinations {
+            let pos = destination.0;
+            let new_val = self.map[pos];
+            if new_val == 0 {
+                continue;
+            }
+
+            let distance = destination.1;
+
+            if new_val < min_value || (new_val == min_value && distance < min_distance) {
+                min_value = new_val;
+                min_distance = distance;
+                min_position = pos;
+            }
+        }
+
+        (min_position, min_distance)
+    }
+
+    #[inline]
+    pub fn djiktra_inline(&self, start: (usize, usize), distance: f32) -> Vec<((usize, usize), f32)> {
+        let start_pos: Pos = Pos(start.0, start.1);
+        let u_distance = (distance * pos::MULTF32) as usize;
+        let api = NormalPosAPI();
+
+        self.dijkstra_destinations(&api, start, |p| api.octile_distance(p, &start_pos) > u_distance)
+    }
+
+    #[inline]
+    pub fn find_destinations_in_inline(&self, start: (usize, usize), distance: f32) -> Vec<((usize, usize), f32)> {
+        self.find_destinations_in_with(start, distance, None, None)
+    }
+
+    #[inline]
+    fn find_destinations_in_inline_influence(&self,
+                                             start: (usize, usize),
+                                             distance: f32)
+                                             -> Vec<((usize, usize), f32)> {
+        let start_pos: Pos = Pos(start.0, start.1);
+        let u_distance = (distance * (self.normal_influence as f32) * pos::MULTF32) as usize;
+        let api = InfluencedPosAPI { normal_influence: self.normal_influence };
+
+        self.dijkstra_destinations(&api, start, |p| api.octile_distance(p, &start_pos) > u_distance)
+    }
+
+    #[inline]
+    fn get_closest_pathable(&self, start: (usize, usize)) -> (usize, usize) {
+        if !self.auto_correct || self.map[start] > 0 {
+            start
+        } else {
+            self.free_finder.find_free(start, &self.map)
+        }
+    }
+
+    /// Closest position with enough clearance for a unit with the radius.
+    #[inline]
+    fn get_closest_clear(&self, start: (usize, usize), possible_radius: Option<f32>) -> (usize, usize) {
+        match possible_radius {
+            None => self.get_closest_pathable(start),
+            Some(radius) => {
+                let required = clearance::required_clearance(radius);
+
+                if !self.auto_correct || self.clearance[start] >= required {
+                    start
+                } else {
+                    self.free_finder
+                        .find_free_where(start, &self.clearance, |p| self.clearance[p] >= required)
                 }
             }
         }
     }
+
+    #[inline]
+    pub fn inline_lowest_value(&self, center: (f32, f32), distance: usize) -> ((usize, usize), f32) {
+        let rect = rectangle::Rectangle::init_from_center(center, (distance, distance), self.width, self.height);
+
+        let mut min_value = std::usize::MAX;
+        let mut min_distance = std::usize::MAX;
+        let mut min_position = (center.0 as usize, center.1 as usize);
+        let target_pos = (center.0 as usize, center.1 as usize);
+
+        for x in rect.x..rect.x_end {
+            for y in rect.y..rect.y_end {
+                let new_val = self.map[(x, y)];
+                if new_val == 0 {
+                    continue;
+                }
+
+                let distance = octile_distance((x, y), target_pos);
+
+                if new_val < min_value || (new_val == min_value && distance < min_distance) {
+                    min_value = new_val;
+                    min_distance = distance;
+                    min_position = (x, y);
+                }
+            }
+        }
+
+        (min_position, min_distance as f32 / pos::MULTF32)
+    }
 }
 
 #[pymethods]
 impl PathFind {
     #[new]
-    fn new(map: Vec<Vec<usize>>) -> Self {
-        let width = map.len();
-        let original_map = map.clone();
-        let height = map[0].len();
-        let normal_influence: usize = 1;
-        let auto_correct: bool = true;
-        let free_finder = search_grid::FreeFinder::new();
-
-        PathFind { map,
-                   original_map,
-                   width,
-                   height,
-                   normal_influence,
-                   auto_correct,
-                   free_finder }
+    fn new(map: Vec<Vec<usize>>) -> PyResult<Self> {
+        error::check_grid(&map)?;
+        Ok(Self::new_internal(map))
     }
 
     // object.width
@@ -141,12 +655,23 @@ impl PathFind {
 
     // object.map
     #[getter(map)]
-    fn get_map(&self) -> PyResult<Vec<Vec<usize>>> { Ok(self.map.clone()) }
+    fn get_map(&self) -> PyResult<Vec<Vec<usize>>> { Ok(self.map.to_vec()) }
 
     // object.map(2dArray)
     #[setter(map)]
     fn set_map(&mut self, value: Vec<Vec<usize>>) -> PyResult<()> {
-        self.map = value;
+        error::check_grid(&value)?;
+
+        if value.len() != self.width || value[0].len() != self.height {
+            let reason = format!("map size must stay {}x{}", self.width, self.height);
+            return Err(PathlibError::InvalidGrid(reason).into());
+        }
+
+        self.map = Grid::from(value);
+        self.clearance = clearance::create_clearance(&self.map);
+        self.components = components::Components::new(&self.map);
+        self.jump_tables = jps::JumpTables::new(&self.map);
+        self.obstacles.clear();
         Ok(())
     }
 
@@ -161,66 +686,143 @@ impl PathFind {
         Ok(())
     }
 
+    // object.auto_correct_distance
+    #[getter(auto_correct_distance)]
+    fn get_auto_correct_distance(&self) -> PyResult<f32> { Ok(self.free_finder.max_distance) }
+
+    // object.auto_correct_distance(float)
+    #[setter(auto_correct_distance)]
+    pub fn set_auto_correct_distance(&mut self, value: f32) -> PathlibResult<()> {
+        error::check_distance("auto correct distance", value)?;
+        self.free_finder.max_distance = value;
+        Ok(())
+    }
+
+    /// Closest pathable cell at most max_distance away, the position itself when it is pathable.
+    /// None when there is no pathable cell close enough. Defaults to the auto correct distance.
+    pub fn closest_pathable(&self,
+                            position: (usize, usize),
+                            possible_max_distance: Option<f32>)
+                            -> PathlibResult<Option<(usize, usize)>> {
+        self.check_position(position)?;
+        let max_distance = possible_max_distance.unwrap_or(self.free_finder.max_distance);
+        error::check_distance("max distance", max_distance)?;
+
+        if self.map[position] > 0 {
+            return Ok(Some(position));
+        }
+
+        Ok(self.free_finder
+               .find_free_within(position, &self.map, max_distance, |p| self.map[p] > 0))
+    }
+
     pub fn reset(&mut self) -> PyResult<()> {
-        self.map = self.original_map.clone();
+        self.reset_void();
         Ok(())
     }
 
-    pub fn reset_void(&mut self) { self.map = self.original_map.clone(); }
+    pub fn reset_void(&mut self) {
+        self.map.copy_from(&self.original_map);
+        self.clearance.copy_from(&self.original_clearance);
+        self.components = components::Components::new(&self.map);
+        self.jump_tables.copy_from(&self.original_jump_tables);
+        self.obstacles.clear();
+    }
 
-    // Creates a block on the grid that is not pathable
-    // center = center of building
-    pub fn create_block(&mut self, center: (f32, f32), size: (usize, usize)) {
-        let rect = rectangle::Rectangle::init_from_center(center, size, self.width, self.height);
+    /// Id of the connected component of the cell, None if the cell is not pathable.
+    /// Cells with the same id can reach each other, ids change when blocks are created or removed.
+    pub fn component_id(&self, position: (usize, usize)) -> PathlibResult<Option<usize>> {
+        self.check_position(position)?;
+        let label = self.components.label(position);
 
-        for x in rect.x..rect.x_end {
-            for y in rect.y..rect.y_end {
-                self.map[x][y] = 0;
-            }
+        if label == components::NO_COMPONENT {
+            Ok(None)
+        } else {
+            Ok(Some(label))
         }
     }
 
+    /// Whether a unit at start can walk to end at all, without doing a path search.
+    pub fn same_component(&self, start: (usize, usize), end: (usize, usize)) -> PathlibResult<bool> {
+        let start_id = self.component_id(start)?;
+        let end_id = self.component_id(end)?;
+        Ok(start_id.is_some() && start_id == end_id)
+    }
+
+    /// Distance from the center of the cell to the closest unpathable cell, capped at 10.
+    /// A unit with radius r fits in the cell when the clearance is at least r + 0.5.
+    pub fn clearance(&self, position: (usize, usize)) -> PathlibResult<f32> {
+        self.check_position(position)?;
+        Ok(self.clearance[position] as f32 / pos::MULTF32)
+    }
+
     // Creates a block on the grid that is not pathable
     // center = center of building
-    pub fn create_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) {
-        for center in centers {
-            let rect = rectangle::Rectangle::init_from_center(center, size, self.width, self.height);
+    // Returns the id of the block for remove_block_by_id
+    pub fn create_block(&mut self, center: (f32, f32), size: (usize, usize)) -> usize {
+        let id = self.obstacles.next_id();
+        self.add_obstacle(id, obstacles::Obstacle::rectangle(center, size, self.width, self.height));
+        id
+    }
 
-            for x in rect.x..rect.x_end {
-                for y in rect.y..rect.y_end {
-                    self.map[x][y] = 0;
-                }
-            }
-        }
+    // Creates a block on the grid that is not pathable
+    // center = center of building
+    pub fn create_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) -> Vec<usize> {
+        self.create_blocks_rust(&centers, size)
     }
 
     // Removes a block on the grid and makes it pathable
     // center = center of block
+    // A block created with the same center and size is removed like with remove_block_by_id,
+    // otherwise the cells that were pathable in the original map are made pathable.
     pub fn remove_block(&mut self, center: (f32, f32), size: (usize, usize)) {
+        if let Some(id) = self.obstacles.find(center, size) {
+            self.remove_obstacle(id);
+            return;
+        }
+
         let rect = rectangle::Rectangle::init_from_center(center, size, self.width, self.height);
 
         for x in rect.x..rect.x_end {
             for y in rect.y..rect.y_end {
-                self.map[x][y] = self.normal_influence;
+                if self.original_map[(x, y)] == 0 {
+                    continue;
+                }
+
+                let value = self.obstacles.uncover((x, y)).unwrap_or(self.map[(x, y)]);
+                self.map[(x, y)] = if value > 0 { value } else { self.normal_influence };
             }
         }
+
+        self.update_pathability(&rect);
     }
 
+    // Removes multiple blocks on the grid and makes it pathable
+    // center = center of block
+    pub fn remove_blocks(&mut self, centers: Vec<(f32, f32)>, size: (usize, usize)) {
+        self.remove_blocks_rust(&centers, size);
+    }
+
+    /// Removes the block created with the id, cells under it get back the values they had before.
+    /// Cells that other blocks cover stay unpathable. Returns false if there is no block with the id.
+    pub fn remove_block_by_id(&mut self, id: usize) -> bool { self.remove_obstacle(id).is_some() }
+
     pub fn normalize_influence(&mut self, value: usize) {
         self.normal_influence = value;
+        self.obstacles.normalize(value);
 
-        for y in &mut self.map {
-            for x in y {
-                if *x > 0 {
-                    *x = value;
-                }
+        for cell in self.map.iter_mut() {
+            if *cell > 0 {
+                *cell = value;
             }
         }
     }
 
     /// Adds influence to a single position
-    pub fn add_influence_spot(&mut self, position: (usize, usize), influence: usize) {
-        self.map[position.0][position.1] += influence;
+    pub fn add_influence_spot(&mut self, position: (usize, usize), influence: usize) -> PathlibResult<()> {
+        self.check_position(position)?;
+        self.map[position] += influence;
+        Ok(())
     }
 
     /// Adds influence based on euclidean distance
@@ -235,8 +837,8 @@ impl PathFind {
             for x in rect.x..rect.x_end {
                 for y in rect.y..rect.y_end {
                     let value = max * (1.0 - (octile_distance(position, (x, y)) as f32) * mult);
-                    if value > 0.0 && self.map[x][y] > 0 {
-                        self.map[x][y] += value as usize;
+                    if value > 0.0 && self.map[(x, y)] > 0 {
+                        self.map[(x, y)] += value as usize;
                     }
                 }
             }
@@ -257,7 +859,7 @@ impl PathFind {
             for x in rect.x..rect.x_end {
                 for y in rect.y..rect.y_end {
                     if (octile_distance(position, (x, y)) as f32) < mult_distance {
-                        self.map[x][y] += value;
+                        self.map[(x, y)] += value;
                     }
                 }
             }
@@ -265,17 +867,18 @@ impl PathFind {
     }
 
     /// Adds influence based on walk distance
-    pub fn add_walk_influence(&mut self, positions: Vec<(usize, usize)>, max: f32, distance: f32) {
+    pub fn add_walk_influence(&mut self, positions: Vec<(usize, usize)>, max: f32, distance: f32) -> PathlibResult<()> {
+        self.check_positions(&positions)?;
         let mult = 1.0 / distance;
         let max_int = max as usize;
 
         for position in &positions {
-            if self.map[position.0][position.1] == 0 {
+            if self.map[*position] == 0 {
                 continue;
             }
 
             let destinations = self.find_destinations_in_inline(*position, distance);
-            self.map[position.0][position.1] += max_int;
+            self.map[*position] += max_int;
 
             for destination in destinations {
                 let end_point = destination.0;
@@ -283,101 +886,59 @@ impl PathFind {
                 let value = max * (1.0 - current_distance * mult);
 
                 if current_distance < distance {
-                    self.map[end_point.0][end_point.1] += value as usize
+                    self.map[end_point] += value as usize
                 }
             }
         }
+
+        Ok(())
     }
 
     /// Adds influence based on walk distance
-    pub fn add_walk_influence_flat(&mut self, positions: Vec<(usize, usize)>, max: f32, distance: f32) {
+    pub fn add_walk_influence_flat(&mut self,
+                                   positions: Vec<(usize, usize)>,
+                                   max: f32,
+                                   distance: f32)
+                                   -> PathlibResult<()> {
+        self.check_positions(&positions)?;
         let max_int = max as usize;
 
         for position in &positions {
             let corrected_position = self.get_closest_pathable(*position);
 
-            if self.map[corrected_position.0][corrected_position.1] == 0 {
+            if self.map[corrected_position] == 0 {
                 continue;
             }
 
             let destinations = self.find_destinations_in_inline(corrected_position, distance);
-            self.map[position.0][position.1] += max_int;
+            self.map[*position] += max_int;
 
             for destination in destinations {
                 let end_point = destination.0;
-                self.map[end_point.0][end_point.1] += max_int
+                self.map[end_point] += max_int
             }
         }
+
+        Ok(())
     }
 
-    pub fn current_influence(&self, position: (usize, usize)) -> usize { self.map[position.0][position.1] }
+    pub fn current_influence(&self, position: (usize, usize)) -> PathlibResult<usize> {
+        self.check_position(position)?;
+        Ok(self.map[position])
+    }
 
     /// Finds the first reachable position within specified walking distance from the center point with lowest value
-    pub fn lowest_influence_walk(&self, center: (usize, usize), distance: f32) -> ((usize, usize), f32) {
+    pub fn lowest_influence_walk(&self, center: (usize, usize), distance: f32) -> PathlibResult<((usize, usize), f32)> {
+        self.check_position(center)?;
         let corrected_center = self.get_closest_pathable(center);
 
-        self.lowest_influence_walk_inline(corrected_center, distance)
-    }
-
-    #[inline]
-    fn lowest_influence_walk_inline(&self, center: (usize, usize), distance: f32) -> ((usize, usize), f32) {
-        let destinations = self.find_destinations_in_inline(center, distance);
-
-        let mut min_value = std::usize::MAX;
-        let mut min_distance = std::f32::MAX;
-        let mut min_position = center;
-
-        for destination in destinations {
-            let pos = destination.0;
-            let new_val = self.map[pos.0][pos.1];
-            if new_val == 0 {
-                continue;
-            }
-
-            let distance = destination.1;
-
-            if new_val < min_value || (new_val == min_value && distance < min_distance) {
-                min_value = new_val;
-                min_distance = distance;
-                min_position = pos;
-            }
-        }
-
-        (min_position, min_distance)
+        Ok(self.lowest_influence_walk_inline(corrected_center, distance))
     }
 
     /// Finds the first reachable position within specified distance from the center point with lowest value
-    pub fn lowest_influence(&self, center: (f32, f32), distance: usize) -> ((usize, usize), f32) {
-        self.inline_lowest_value(center, distance)
-    }
-
-    #[inline]
-    pub fn inline_lowest_value(&self, center: (f32, f32), distance: usize) -> ((usize, usize), f32) {
-        let rect = rectangle::Rectangle::init_from_center(center, (distance, distance), self.width, self.height);
-
-        let mut min_value = std::usize::MAX;
-        let mut min_distance = std::usize::MAX;
-        let mut min_position = (center.0 as usize, center.1 as usize);
-        let target_pos = (center.0 as usize, center.1 as usize);
-
-        for x in rect.x..rect.x_end {
-            for y in rect.y..rect.y_end {
-                let new_val = self.map[x][y];
-                if new_val == 0 {
-                    continue;
-                }
-
-                let distance = octile_distance((x, y), target_pos);
-
-                if new_val < min_value || (new_val == min_value && distance < min_distance) {
-                    min_value = new_val;
-                    min_distance = distance;
-                    min_position = (x, y);
-                }
-            }
-        }
-
-        (min_position, min_distance as f32 / pos::MULTF32)
+    pub fn lowest_influence(&self, center: (f32, f32), distance: usize) -> PathlibResult<((usize, usize), f32)> {
+        self.check_position((center.0 as usize, center.1 as usize))?;
+        Ok(self.inline_lowest_value(center, distance))
     }
 
     /// Find the shortest path and returns the path and distance.
@@ -391,27 +952,67 @@ impl PathFind {
     ///         0 - manhattan distance (default)
     ///         1 - octile distance
     ///         2 - euclidean distance
+    ///         3 - jump point search with octile distance, only used when influence is false
     ///     possible_window:
     ///         if set, restrict path to the given window of the map
     ///     possible_distance_from_target:
     ///         if set, short circuit when finding a position closer than the given distance from
     ///         the target
-    pub fn find_path(&self,
-                     start: (usize, usize),
-                     end: (usize, usize),
-                     large: bool,
-                     influence: bool,
-                     possible_heuristic: Option<u8>,
-                     possible_window: Option<((usize, usize), (usize, usize))>,
-                     possible_distance_from_target: Option<f32>)
-                     -> (Vec<(usize, usize)>, f32) {
-        self.find_path_inline(start,
-                              end,
-                              large,
-                              influence,
-                              possible_heuristic,
-                              possible_window,
-                              possible_distance_from_target)
+    ///     possible_radius:
+    ///         if set, only path through cells with enough clearance for a unit with the given radius
+    ///     possible_influence_cap:
+    ///         if set to max influence, cells with influence above it are not pathable
+    ///         if set to (max influence, multiplier), moving into cells above it costs multiplier times more
+    /// Start, end, large and influence are required,
+    /// everything after them can be left out or given as keyword arguments.
+    #[pyo3(name = "find_path")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_args(&self,
+                          start: (usize, usize),
+                          end: (usize, usize),
+                          large: bool,
+                          influence: bool,
+                          possible_heuristic: Option<u8>,
+                          possible_window: Option<((usize, usize), (usize, usize))>,
+                          possible_distance_from_target: Option<f32>,
+                          possible_radius: Option<f32>,
+                          possible_influence_cap: Option<InfluenceCap>)
+                          -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        self.find_path(PathQuery { start,
+                                   end,
+                                   large,
+                                   influence,
+                                   possible_heuristic,
+                                   possible_window,
+                                   possible_distance_from_target,
+                                   possible_radius,
+                                   possible_influence_cap })
+    }
+
+    /// Same as find_path, but also returns the start and end that the search used.
+    /// If either differs from the given one, it was moved to the closest cell the unit can use.
+    #[pyo3(name = "find_path_corrected")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_corrected_args(&self,
+                                    start: (usize, usize),
+                                    end: (usize, usize),
+                                    large: bool,
+                                    influence: bool,
+                                    possible_heuristic: Option<u8>,
+                                    possible_window: Option<((usize, usize), (usize, usize))>,
+                                    possible_distance_from_target: Option<f32>,
+                                    possible_radius: Option<f32>,
+                                    possible_influence_cap: Option<InfluenceCap>)
+                                    -> PathlibResult<CorrectedPath> {
+        self.find_path_corrected(PathQuery { start,
+                                             end,
+                                             large,
+                                             influence,
+                                             possible_heuristic,
+                                             possible_window,
+                                             possible_distance_from_target,
+                                             possible_radius,
+                                             possible_influence_cap })
     }
 
     /// Basic version of find_path with all parameters except heuristic set to false or None.
@@ -419,224 +1020,212 @@ impl PathFind {
                            start: (usize, usize),
                            end: (usize, usize),
                            possible_heuristic: Option<u8>)
-                           -> (Vec<(usize, usize)>, f32) {
-        self.find_path_inline(start, end, false, false, possible_heuristic, None, None)
+                           -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        self.check_path_query(start, end, None)?;
+        let query = PathQuery { possible_heuristic,
+                                ..PathQuery::new(start, end) };
+        Ok(self.find_path_inline(&query))
     }
 
-    #[inline]
-    fn find_path_inline(&self,
-                        given_start: (usize, usize),
-                        given_end: (usize, usize),
-                        large: bool,
-                        influence: bool,
-                        possible_heuristic: Option<u8>,
-                        possible_window: Option<((usize, usize), (usize, usize))>,
-                        possible_distance_from_target: Option<f32>)
-                        -> (Vec<(usize, usize)>, f32) {
-        let corrected_start = self.get_closest_pathable(given_start);
-        let corrected_end = self.get_closest_pathable(given_end);
-        let grid: &Vec<Vec<usize>> = &self.map;
-        let api: &dyn PositionAPI;
-        let normal_api = NormalPosAPI();
-        let large_api = PosLargeAPI();
-        let influence_api = InfluencedPosAPI { normal_influence: self.normal_influence };
-        let influence_large_api = InfluencedPosLargeAPI { normal_influence: self.normal_influence };
-        let start: Pos = Pos(corrected_start.0, corrected_start.1);
-        let goal: Pos = Pos(corrected_end.0, corrected_end.1);
-
-        match (large, influence) {
-            (false, false) => api = &normal_api,
-            (true, false) => api = &large_api,
-            (false, true) => api = &influence_api,
-            (true, true) => api = &influence_large_api,
-        }
-
-        let result: Option<(Vec<Pos>, usize)>;
-        let possible_u_distance: Option<usize>;
-
-        if influence {
-            possible_u_distance = possible_distance_from_target.map(|d| (d * pos::MULTF32 * (self.normal_influence as f32)) as usize);
-        } else {
-            possible_u_distance = possible_distance_from_target.map(|d| (d * pos::MULTF32) as usize);
-        }
-
-        match (possible_window, possible_u_distance, possible_heuristic.unwrap_or(0)) {
-            (None, None, 0) => {
-                result =
-                    astar(&start, |p| api.successors(p, grid), |p| api.manhattan_distance(p, &goal), |p| *p == goal)
-            }
-            (None, None, 1) => {
-                result = astar(&start, |p| api.successors(p, grid), |p| api.octile_distance(p, &goal), |p| *p == goal)
-            }
-            (None, None, _) => {
-                result =
-                    astar(&start, |p| api.successors(p, grid), |p| api.euclidean_distance(p, &goal), |p| *p == goal)
-            }
-            (None, Some(u_distance), 0) => {
-                result = astar(&start,
-                               |p| api.successors(p, grid),
-                               |p| api.manhattan_distance(p, &goal),
-                               |p| api.manhattan_distance(p, &goal) < u_distance)
-            }
-            (None, Some(u_distance), 1) => {
-                result = astar(&start,
-                               |p| api.successors(p, grid),
-                               |p| api.octile_distance(p, &goal),
-                               |p| api.octile_distance(p, &goal) < u_distance)
-            }
-            (None, Some(u_distance), _) => {
-                result = astar(&start,
-                               |p| api.successors(p, grid),
-                               |p| api.euclidean_distance(p, &goal),
-                               |p| api.euclidean_distance(p, &goal) < u_distance)
-            }
-            (Some(window), None, 0) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.manhattan_distance(p, &goal),
-                               |p| *p == goal)
-            }
-            (Some(window), None, 1) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.octile_distance(p, &goal),
-                               |p| *p == goal)
-            }
-            (Some(window), None, _) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.euclidean_distance(p, &goal),
-                               |p| *p == goal)
-            }
-            (Some(window), Some(u_distance), 0) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.manhattan_distance(p, &goal),
-                               |p| api.manhattan_distance(p, &goal) < u_distance)
-            }
-            (Some(window), Some(u_distance), 1) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.octile_distance(p, &goal),
-                               |p| api.octile_distance(p, &goal) < u_distance)
-            }
-            (Some(window), Some(u_distance), _) => {
-                result = astar(&start,
-                               |p| api.successors_within(p, grid, window),
-                               |p| api.euclidean_distance(p, &goal),
-                               |p| api.euclidean_distance(p, &goal) < u_distance)
-            }
-        }
-
-        let mut path: Vec<(usize, usize)>;
-        let distance: f32;
-
-        match result {
-            None => {
-                path = Vec::<(usize, usize)>::new();
-                distance = 0.0
-            }
-            Some(t) => {
-                distance = (t.1 as f32) / pos::MULTF32;
-                path = Vec::<(usize, usize)>::with_capacity(t.0.len());
-                for pos in t.0 {
-                    path.push((pos.0, pos.1))
-                }
-            }
-        }
-
-        (path, distance)
+    /// Same as find_path with octile heuristic, but gives up when the budget runs out.
+    /// Returns the path, distance and status, see `PathStatus`. When the end was not reached
+    /// the path leads to the position closest to the end that was found.
+    /// Parameters:
+    ///     budget:
+    ///         maximum number of expanded positions and maximum time in milliseconds, either can be None
+    pub fn find_path_budget(&self,
+                            start: (usize, usize),
+                            end: (usize, usize),
+                            large: bool,
+                            influence: bool,
+                            budget: SearchBudget)
+                            -> PathlibResult<PathAndStatus> {
+        self.check_path_query(start, end, None)?;
+        let corrected_start = self.get_closest_pathable(start);
+        let corrected_end = self.get_closest_pathable(end);
+        let goal = Pos(corrected_end.0, corrected_end.1);
+
+        self.with_api(large, influence, None, None, |api| {
+                self.with_search(|engine| {
+                        let grid = &self.map;
+                        let (status, reached) = engine.astar_budget(grid.width(),
+                                                                    grid.height(),
+                                                                    corrected_start,
+                                                                    |p| api.successors(p, grid),
+                                                                    |p| api.octile_distance(p, &goal),
+                                                                    budget);
+                        let distance = engine.cost(reached).unwrap_or(0) as f32 / pos::MULTF32;
+
+                        Ok((engine.path(reached), distance, status as u8))
+                    })
+            })
     }
 
-    /// Finds all reachable destinations from selected start point. Ignores influence.
-    pub fn find_all_destinations(&self, start: (usize, usize)) -> PyResult<Vec<((usize, usize), f32)>> {
-        let start: pos::Pos = pos::Pos(start.0, start.1);
-        let grid: &Vec<Vec<usize>> = &self.map;
-        let api = NormalPosAPI();
-        let result = dijkstra_all(&start, |p| api.successors(p, &grid));
-
-        let mut destination_collection: Vec<((usize, usize), f32)> =
-            Vec::<((usize, usize), f32)>::with_capacity(result.len());
-
-        for found_path in result {
-            let x = ((found_path.1).0).0;
-            let y = ((found_path.1).0).1;
-            let d = ((found_path.1).1 as f32) / pos::MULTF32;
-            destination_collection.push(((x, y), d));
-        }
-
-        Ok(destination_collection)
+    /// Finds the shortest path and smooths it into straight segments that do not cross unpathable cells.
+    /// Returns the waypoints and the euclidean length of the smoothed path.
+    /// Parameters are the same as in find_path, radius and influence cap also apply to the straight segments.
+    #[pyo3(name = "find_path_smooth")]
+    #[allow(clippy::too_many_arguments)]
+    pub fn find_path_smooth_args(&self,
+                                 start: (usize, usize),
+                                 end: (usize, usize),
+                                 large: bool,
+                                 influence: bool,
+                                 possible_heuristic: Option<u8>,
+                                 possible_window: Option<((usize, usize), (usize, usize))>,
+                                 possible_distance_from_target: Option<f32>,
+                                 possible_radius: Option<f32>,
+                                 possible_influence_cap: Option<InfluenceCap>)
+                                 -> PathlibResult<(Vec<(f32, f32)>, f32)> {
+        self.find_path_smooth(PathQuery { start,
+                                          end,
+                                          large,
+                                          influence,
+                                          possible_heuristic,
+                                          possible_window,
+                                          possible_distance_from_target,
+                                          possible_radius,
+                                          possible_influence_cap })
     }
 
-    /// Finds all reachable destinations from selected start point. Ignores influence.
-    pub fn find_destinations_in(&self, start: (usize, usize), distance: f32) -> PyResult<Vec<((usize, usize), f32)>> {
-        Ok(self.find_destinations_in_inline(start, distance))
+    /// Checks whether units can walk straight from start to end, using every cell the segment touches.
+    /// Returns whether the segment is clear, the first unpathable cell and all traversed cells
+    /// up to the blocking cell when include_cells is set.
+    pub fn raycast(&self,
+                   start: (usize, usize),
+                   end: (usize, usize),
+                   include_cells: bool)
+                   -> PathlibResult<RaycastResult> {
+        self.check_position(start)?;
+        self.check_position(end)?;
+
+        Ok(line_of_sight::raycast(start, end, include_cells, |x, y| self.map[(x, y)] == 0))
     }
 
-    #[inline]
-    pub fn find_destinations_in_inline(&self, start: (usize, usize), distance: f32) -> Vec<((usize, usize), f32)> {
-        let start: pos::Pos = pos::Pos(start.0, start.1);
-        let grid = &self.map;
-        let u_distance = (distance * pos::MULTF32) as usize;
-        let api = NormalPosAPI();
-
-        let result =
-            dijkstra_partial(&start, |p| api.successors(p, &grid), |p| api.octile_distance(p, &start) > u_distance);
+    /// Calculates walking distances from every cell to the closest goal.
+    /// Units can then query next_step or direction from the field instead of finding their own path.
+    /// Parameters:
+    ///     large:
+    ///         use path finding rules of a large unit
+    ///     influence:
+    ///         take influence into account
+    ///     possible_radius:
+    ///         if set, only use cells with enough clearance for a unit with the given radius
+    ///     possible_influence_cap:
+    ///         max influence or (max influence, multiplier), same as in find_path
+    pub fn distance_field(&self,
+                          goals: Vec<(usize, usize)>,
+                          large: bool,
+                          influence: bool,
+                          possible_radius: Option<f32>,
+                          possible_influence_cap: Option<InfluenceCap>)
+                          -> PathlibResult<DistanceField> {
+        self.check_positions(&goals)?;
+        let corrected_goals: Vec<(usize, usize)> =
+            goals.iter().map(|g| self.get_closest_clear(*g, possible_radius)).collect();
+
+        Ok(self.with_api(large, influence, possible_radius, possible_influence_cap, |api| {
+                   DistanceField::new(api, &self.map, &corrected_goals)
+               }))
+    }
 
-        let hash_map = result.0;
-        let mut destination_collection: Vec<((usize, usize), f32)> =
-            Vec::<((usize, usize), f32)>::with_capacity(hash_map.len());
+    /// Finds a path away from the threats by following a safety map, see `DistanceField::safety`.
+    /// Terrain and current influence are taken into account, so the path runs past threats into open
+    /// space rather than into dead ends. Stops after walking the distance or when no cell nearby is safer.
+    /// Returns the path and its walking length.
+    pub fn flee_path(&self,
+                     start: (usize, usize),
+                     threats: Vec<(usize, usize)>,
+                     distance: f32)
+                     -> PathlibResult<(Vec<(usize, usize)>, f32)> {
+        self.check_position(start)?;
+        self.check_positions(&threats)?;
+        let corrected_start = self.get_closest_pathable(start);
+        let corrected_threats: Vec<(usize, usize)> = threats.iter().map(|t| self.get_closest_pathable(*t)).collect();
+
+        let field = self.with_api(false, true, None, None, |api| {
+                            DistanceField::safety(api, &self.map, &corrected_threats)
+                        });
+
+        let mut path = vec![corrected_start];
+        let mut walked = 0.0;
+
+        for step in field.path(point2_f32(corrected_start), usize::MAX).into_iter().skip(1) {
+            if walked >= distance {
+                break;
+            }
 
-        for found_path in hash_map {
-            let x = (found_path.0).0;
-            let y = (found_path.0).1;
-            let d = ((found_path.1).1 as f32) / pos::MULTF32;
-            destination_collection.push(((x, y), d));
+            walked += octile_distance_f32(*path.last().unwrap(), step);
+            path.push(step);
         }
 
-        destination_collection
+        Ok((path, walked))
     }
 
-    #[inline]
-    fn find_destinations_in_inline_influence(&self,
-                                             start: (usize, usize),
-                                             distance: f32)
-                                             -> Vec<((usize, usize), f32)> {
-        let start: Pos = Pos(start.0, start.1);
-        let grid = &self.map;
-        let u_distance = (distance * (self.normal_influence as f32) * pos::MULTF32) as usize;
-        let api = InfluencedPosAPI { normal_influence: self.normal_influence };
-
-        let result =
-            dijkstra_partial(&start, |p| api.successors(p, &grid), |p| api.octile_distance(p, &start) > u_distance);
-
-        let hash_map = result.0;
-        let mut destination_collection: Vec<((usize, usize), f32)> =
-            Vec::<((usize, usize), f32)>::with_capacity(hash_map.len());
+    /// Creates a path planner that keeps the path from start to goal up to date with
+    /// incremental repairs, see update_planner.
+    /// Parameters:
+    ///     large:
+    ///         use path finding rules of a large unit
+    ///     influence:
+    ///         take influence into account
+    pub fn create_planner(&self,
+                          start: (usize, usize),
+                          goal: (usize, usize),
+                          large: bool,
+                          influence: bool)
+                          -> PathlibResult<PathPlanner> {
+        self.check_position(start)?;
+        self.check_position(goal)?;
+        let corrected_start = self.get_closest_pathable(start);
+        let corrected_goal = self.get_closest_pathable(goal);
+
+        Ok(self.with_api(large, influence, None, None, |api| {
+                   PathPlanner::new(api, &self.map, corrected_start, corrected_goal, large, influence)
+               }))
+    }
 
-        for found_path in hash_map {
-            let x = (found_path.0).0;
-            let y = (found_path.0).1;
-            let d = ((found_path.1).1 as f32) / pos::MULTF32;
-            destination_collection.push(((x, y), d));
-        }
+    /// Repairs the path of the planner after the changed cells have been modified,
+    /// e.g. with create_block or remove_block.
+    pub fn update_planner(&self,
+                          mut planner: PyRefMut<PathPlanner>,
+                          changed_cells: Vec<(usize, usize)>)
+                          -> PathlibResult<()> {
+        self.check_planner(&planner)?;
+        self.check_positions(&changed_cells)?;
+        self.update_planner_rust(&mut planner, &changed_cells);
+        Ok(())
+    }
 
-        destination_collection
+    /// Finds all reachable destinations from selected start point. Ignores influence.
+    pub fn find_all_destinations(&self, start: (usize, usize)) -> PathlibResult<Vec<((usize, usize), f32)>> {
+        self.check_position(start)?;
+        Ok(self.dijkstra_destinations(&NormalPosAPI(), start, |_| false))
     }
 
-    #[inline]
-    fn get_closest_pathable(&self, start: (usize, usize)) -> (usize, usize) {
-        if !self.auto_correct || self.map[start.0][start.1] > 0 {
-            start
-        } else {
-            self.free_finder.find_free(start, &self.map, self.width, self.height)
-        }
+    /// Finds all reachable destinations from selected start point. Ignores influence.
+    /// With a radius only cells with enough clearance for the unit are reachable,
+    /// with an influence cap cells above it are unreachable or more expensive.
+    pub fn find_destinations_in(&self,
+                                start: (usize, usize),
+                                distance: f32,
+                                possible_radius: Option<f32>,
+                                possible_influence_cap: Option<InfluenceCap>)
+                                -> PathlibResult<Vec<((usize, usize), f32)>> {
+        self.check_position(start)?;
+        Ok(self.find_destinations_in_with(start, distance, possible_radius, possible_influence_cap))
     }
 
     /// Finds a compromise where low influence matches with close position to the start position.
-    pub fn find_low_inside_walk(&self, start: (f32, f32), target: (f32, f32), distance: f32) -> ((f32, f32), f32) {
+    pub fn find_low_inside_walk(&self,
+                                start: (f32, f32),
+                                target: (f32, f32),
+                                distance: f32)
+                                -> PathlibResult<((f32, f32), f32)> {
         let start_int = (start.0 as usize, start.1 as usize);
         let target_int = (target.0 as usize, target.1 as usize);
+        self.check_position(start_int)?;
+        self.check_position(target_int)?;
 
         let current_distance = euclidean_distance(start, target);
 
@@ -647,12 +1236,15 @@ impl PathFind {
 
         if current_distance > distance + 2.0 {
             // Just do normal influence pathfinding to near the target.
-            let path =
-                self.find_path_inline(corrected_start, corrected_target, false, true, Some(1u8), None, Some(distance));
+            let query = PathQuery { influence: true,
+                                    possible_heuristic: Some(1u8),
+                                    possible_distance_from_target: Some(distance),
+                                    ..PathQuery::new(corrected_start, corrected_target) };
+            let path = self.find_path_inline(&query);
             if path.1 >= 0f32 {
-                return (point2_f32(*path.0.last().unwrap_or(&corrected_start)), path.1);
+                return Ok((point2_f32(*path.0.last().unwrap_or(&corrected_start)), path.1));
             }
-            return ((0.0, 0.0), -1.0); // Failed
+            return Ok(((0.0, 0.0), -1.0)); // Failed
         }
 
         let destinations = self.find_destinations_in_inline(corrected_start, distance + 1.0);
@@ -660,7 +1252,7 @@ impl PathFind {
         let mut best_target: ((f32, f32), f32) = (point2_f32(corrected_start), 0.0);
         let mut best_influence = f32::MAX;
         if current_distance < distance {
-            best_influence = self.map[corrected_start.0][corrected_start.1] as f32;
+            best_influence = self.map[corrected_start] as f32;
         }
 
         for destination in destinations {
@@ -673,7 +1265,7 @@ impl PathFind {
             // Use magic distance constant here to not move without reason.
             // Let's take the distance into account so that same influence value is better when it's closer.
             let distance_value = distance_from_start;
-            let influence = self.map[(destination.0).0][(destination.0).1] as f32 + distance_value;
+            let influence = self.map[((destination.0).0, (destination.0).1)] as f32 + distance_value;
 
             if influence < best_influence {
                 best_target = (point2_f32(destination.0), distance);
@@ -681,70 +1273,32 @@ impl PathFind {
             }
         }
 
-        best_target
-    }
-
-    pub fn invert_djiktra(&self, start: (f32, f32), distance: f32) -> Vec<((usize, usize), f32)> {
-        let start_int = (start.0 as usize, start.1 as usize);
-        let start: Pos = Pos(start_int.0, start_int.1);
-        let grid = &self.map;
-        let u_distance = (distance * pos::MULTF32) as usize;
-        let api = InvertPosAPI();
-
-        let result =
-            dijkstra_partial(&start, |p| api.successors(p, &grid), |p| api.octile_distance(p, &start) > u_distance);
-
-        let hash_map = result.0;
-        let mut destination_collection: Vec<((usize, usize), f32)> =
-            Vec::<((usize, usize), f32)>::with_capacity(hash_map.len());
-
-        for found_path in hash_map {
-            let x = (found_path.0).0;
-            let y = (found_path.0).1;
-            let d = ((found_path.1).1 as f32) / pos::MULTF32;
-            destination_collection.push(((x, y), d));
-        }
-
-        destination_collection
+        Ok(best_target)
     }
 
-    pub fn djiktra(&self, start: (f32, f32), distance: f32) -> Vec<((usize, usize), f32)> {
+    pub fn djiktra(&self, start: (f32, f32), distance: f32) -> PathlibResult<Vec<((usize, usize), f32)>> {
         let start_int = (start.0 as usize, start.1 as usize);
-        let start: pos::Pos = pos::Pos(start_int.0, start_int.1);
-        let grid = &self.map;
-        let u_distance = (distance * pos::MULTF32) as usize;
-        let api = NormalPosAPI();
-        let result =
-            dijkstra_partial(&start, |p| api.successors(p, &grid), |p| api.octile_distance(p, &start) > u_distance);
-
-        let hash_map = result.0;
-        let mut destination_collection: Vec<((usize, usize), f32)> =
-            Vec::<((usize, usize), f32)>::with_capacity(hash_map.len());
-
-        for found_path in hash_map {
-            let x = (found_path.0).0;
-            let y = (found_path.0).1;
-            let d = ((found_path.1).1 as f32) / pos::MULTF32;
-            destination_collection.push(((x, y), d));
-        }
-
-        destination_collection
+        self.check_position(start_int)?;
+        Ok(self.djiktra_inline(start_int, distance))
     }
 
     pub fn add_influence_to_map_by_vision(&mut self,
                                           vision_map: &VisionMap,
                                           seen_value: usize,
-                                          detection_value: usize) {
+                                          detection_value: usize)
+                                          -> PathlibResult<()> {
         for x in 0..self.width {
             for y in 0..self.height {
-                let status = vision_map.vision_status((x as f32, y as f32));
+                let status = vision_map.vision_status((x as f32, y as f32))?;
                 if status == 1 {
-                    self.add_influence_spot((x, y), seen_value);
+                    self.map[(x, y)] += seen_value;
                 }
                 if status == 2 {
-                    self.add_influence_spot((x, y), detection_value);
+                    self.map[(x, y)] += detection_value;
                 }
             }
         }
+
+        Ok(())
     }
 }
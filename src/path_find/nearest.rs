@@ -0,0 +1,74 @@
+use pyo3::prelude::*;
+use std::collections::HashMap;
+
+use crate::error::{PathlibError, PathlibResult};
+use crate::path_find::{pos, PathFind};
+
+/// Index of the goal in the given list, path to it and walking distance without the weight.
+pub type NearestGoal = (usize, Vec<(usize, usize)>, f32);
+
+#[pymethods]
+impl PathFind {
+    /// Finds the goal that can be reached first by walking with a single search.
+    /// Parameters:
+    ///     possible_weights:
+    ///         if set, extra distance for each goal, the goal with the lowest distance plus weight is selected.
+    ///         Negative weights count as zero.
+    ///     large:
+    ///         use path finding rules of a large unit
+    ///     influence:
+    ///         take influence into account
+    /// Returns the index of the goal, path and distance to it, or None if none of the goals can be reached.
+    pub fn find_nearest_goal(&self,
+                             start: (usize, usize),
+                             goals: Vec<(usize, usize)>,
+                             possible_weights: Option<Vec<f32>>,
+                             large: bool,
+                             influence: bool)
+                             -> PathlibResult<Option<NearestGoal>> {
+        self.check_position(start)?;
+        self.check_positions(&goals)?;
+
+        if let Some(weights) = &possible_weights {
+            if weights.len() != goals.len() {
+                let reason = format!("{} weights given for {} goals", weights.len(), goals.len());
+                return Err(PathlibError::InvalidArgument(reason));
+            }
+        }
+
+        let unit_cost = if influence {
+            pos::MULTF32 * self.normal_influence as f32
+        } else {
+            pos::MULTF32
+        };
+
+        // Same cell can be given multiple times, only the lowest weight matters
+        let mut goal_weights: HashMap<(usize, usize), (usize, usize)> = HashMap::with_capacity(goals.len());
+
+        for (index, goal) in goals.iter().enumerate() {
+            let weight = possible_weights.as_ref().map_or(0.0, |weights| weights[index].max(0.0));
+            let u_weight = (weight * unit_cost) as usize;
+            let corrected_goal = self.get_closest_pathable(*goal);
+
+            let entry = goal_weights.entry(corrected_goal).or_insert((u_weight, index));
+            if u_weight < entry.0 {
+                *entry = (u_weight, index);
+            }
+        }
+
+        let corrected_start = self.get_closest_pathable(start);
+
+        Ok(self.with_api(large, influence, None, None, |api| {
+                   self.with_search(|engine| {
+                           let grid = &self.map;
+                           let (goal, cost) = engine.dijkstra_nearest(grid.width(),
+                                                                      grid.height(),
+                                                                      corrected_start,
+                                                                      |p| api.successors(p, grid),
+                                                                      |p| goal_weights.get(&(p.0, p.1)).map(|w| w.0))?;
+
+                           Some((goal_weights[&goal].1, engine.path(goal), cost as f32 / pos::MULTF32))
+                       })
+               }))
+    }
+}
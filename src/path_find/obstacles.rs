@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+
+use crate::helpers::grid::Grid;
+use crate::path_find::rectangle::Rectangle;
+
+/// Block placed on the map with create_block.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Obstacle {
+    pub center: (f32, f32),
+    pub size: (usize, usize),
+    /// Cells that the obstacle makes unpathable, all of them are inside the size around the center.
+    pub cells: Vec<(usize, usize)>,
+}
+
+impl Obstacle {
+    /// Obstacle that covers the whole rectangle.
+    pub fn rectangle(center: (f32, f32), size: (usize, usize), width: usize, height: usize) -> Self {
+        let rect = Rectangle::init_from_center(center, size, width, height);
+        let cells = (rect.x..rect.x_end).flat_map(|x| (rect.y..rect.y_end).map(move |y| (x, y)))
+                                        .collect();
+
+        Obstacle { center, size, cells }
+    }
+
+    /// Rectangle that contains all cells of the obstacle.
+    #[inline]
+    pub fn bounds(&self, width: usize, height: usize) -> Rectangle {
+        Rectangle::init_from_center(self.center, self.size, width, height)
+    }
+}
+
+/// Obstacle layer on top of the map.
+/// Keeps what was under the obstacles, so removing one restores the terrain and influence it covered.
+#[derive(Clone)]
+pub struct Obstacles {
+    obstacles: HashMap<usize, Obstacle>,
+    /// Number of obstacles covering each cell.
+    counts: Grid<usize>,
+    /// Map values of covered cells from before the first obstacle was placed on them.
+    covered: Grid<usize>,
+    next_id: usize,
+}
+
+impl Obstacles {
+    pub fn new(width: usize, height: usize) -> Self {
+        Obstacles { obstacles: HashMap::new(),
+                    counts: Grid::new(width, height, 0),
+                    covered: Grid::new(width, height, 0),
+                    next_id: 1 }
+    }
+
+    /// Id that the next obstacle should use.
+    #[inline]
+    pub fn next_id(&self) -> usize { self.next_id }
+
+    /// Id of an obstacle with exactly the same center and size.
+    pub fn find(&self, center: (f32, f32), size: (usize, usize)) -> Option<usize> {
+        self.obstacles
+            .iter()
+            .filter(|(_, other)| other.center == center && other.size == size)
+            .map(|(id, _)| *id)
+            .max()
+    }
+
+    /// Adds the obstacle and makes its cells unpathable.
+    pub fn add(&mut self, id: usize, obstacle: Obstacle, map: &mut Grid<usize>) {
+        for cell in &obstacle.cells {
+            if self.counts[*cell] == 0 {
+                self.covered[*cell] = map[*cell];
+            }
+
+            self.counts[*cell] += 1;
+            map[*cell] = 0;
+        }
+
+        self.obstacles.insert(id, obstacle);
+        self.next_id = usize::max(self.next_id, id + 1);
+    }
+
+    /// Removes the obstacle, cells that no other obstacle covers get their old values back.
+    /// Returns the removed obstacle, None if there is no obstacle with the id.
+    pub fn remove(&mut self, id: usize, map: &mut Grid<usize>) -> Option<Obstacle> {
+        let obstacle = self.obstacles.remove(&id)?;
+
+        for cell in &obstacle.cells {
+            // Cells may have been uncovered already
+            if self.counts[*cell] == 0 {
+                continue;
+            }
+
+            self.counts[*cell] -= 1;
+
+            if self.counts[*cell] == 0 {
+                map[*cell] = self.covered[*cell];
+            }
+        }
+
+        Some(obstacle)
+    }
+
+    /// Removes the cell from all obstacles covering it, returns the value it had before them.
+    pub fn uncover(&mut self, position: (usize, usize)) -> Option<usize> {
+        if self.counts[position] == 0 {
+            return None;
+        }
+
+        self.counts[position] = 0;
+        Some(self.covered[position])
+    }
+
+    /// Sets the influence of pathable cells under the obstacles to the value, see `PathFind::normalize_influence`.
+    pub fn normalize(&mut self, value: usize) {
+        for x in 0..self.counts.width() {
+            for y in 0..self.counts.height() {
+                if self.counts[(x, y)] > 0 && self.covered[(x, y)] > 0 {
+                    self.covered[(x, y)] = value;
+                }
+            }
+        }
+    }
+
+    /// Forgets all obstacles without touching the map, used when the map is replaced.
+    pub fn clear(&mut self) {
+        self.obstacles.clear();
+        self.counts = Grid::new(self.counts.width(), self.counts.height(), 0);
+    }
+}
@@ -0,0 +1,78 @@
+use pyo3::prelude::*;
+
+use crate::error::PathlibResult;
+use crate::mapping::vision::{VisionMap, VisionStatus};
+use crate::path_find::PathFind;
+
+/// Danger along a path, influence values and the threshold are counted above the normal influence of the map.
+#[pyclass]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PathStats {
+    /// Sum of influence above normal over the path.
+    #[pyo3(get)]
+    pub total_influence: usize,
+    /// Highest influence above normal on the path.
+    #[pyo3(get)]
+    pub max_influence: usize,
+    /// Index of the most dangerous cell, None if no cell has influence above normal.
+    #[pyo3(get)]
+    pub max_influence_index: Option<usize>,
+    /// Number of cells whose influence above normal is higher than the threshold.
+    #[pyo3(get)]
+    pub cells_above_threshold: usize,
+    /// Index of the first cell whose influence above normal is higher than the threshold.
+    #[pyo3(get)]
+    pub first_danger_index: Option<usize>,
+    /// Number of cells that are seen or detected by the enemy, None without a vision map.
+    #[pyo3(get)]
+    pub seen_cells: Option<usize>,
+}
+
+impl PathFind {
+    /// Calculates the danger along the path, seen cells are counted only with a vision map.
+    pub fn evaluate_path_rust(&self,
+                              path: &[(usize, usize)],
+                              threshold: f32,
+                              possible_vision: Option<&VisionMap>)
+                              -> PathlibResult<PathStats> {
+        self.check_positions(path)?;
+        let mut stats = PathStats::default();
+
+        for (index, position) in path.iter().enumerate() {
+            let value = self.map[*position];
+            let excess = value.saturating_sub(self.normal_influence);
+
+            stats.total_influence += excess;
+
+            if excess > stats.max_influence {
+                stats.max_influence = excess;
+                stats.max_influence_index = Some(index);
+            }
+
+            if excess as f32 > threshold {
+                stats.cells_above_threshold += 1;
+                stats.first_danger_index.get_or_insert(index);
+            }
+        }
+
+        stats.seen_cells = possible_vision.map(|vision| {
+                                              path.iter()
+                                                  .filter(|p| {
+                                                      matches!(vision.points[**p],
+                                                               VisionStatus::Seen | VisionStatus::Detected)
+                                                  })
+                                                  .count()
+                                          });
+
+        Ok(stats)
+    }
+}
+
+#[pymethods]
+impl PathFind {
+    /// Calculates the danger along the path: total and max influence above normal influence,
+    /// number of cells with influence more than the threshold above normal and the index of the first one.
+    pub fn evaluate_path(&self, path: Vec<(usize, usize)>, threshold: f32) -> PathlibResult<PathStats> {
+        self.evaluate_path_rust(&path, threshold, None)
+    }
+}
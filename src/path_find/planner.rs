@@ -0,0 +1,245 @@
+use arrayvec::ArrayVec;
+use pyo3::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+use crate::helpers::grid::Grid;
+use crate::path_find::pos::{Pos, PositionAPI, MULTF32};
+
+const INFINITE: usize = usize::MAX;
+
+type Key = (usize, usize);
+
+/// Persistent path between two positions that is repaired incrementally with D* Lite
+/// when the grid changes, instead of searching the whole path again.
+/// The search runs backwards from the goal, so costs to the goal stay valid for every
+/// cell that the map changes did not affect.
+#[pyclass]
+pub struct PathPlanner {
+    start: (usize, usize),
+    goal: (usize, usize),
+    pub large: bool,
+    pub influence: bool,
+    width: usize,
+    height: usize,
+    g: Vec<usize>,
+    rhs: Vec<usize>,
+    queued: Vec<Option<Key>>,
+    open: BinaryHeap<Reverse<(Key, usize)>>,
+    path: Vec<(usize, usize)>,
+    distance: usize,
+}
+
+#[pymethods]
+impl PathPlanner {
+    #[getter(start)]
+    fn get_start(&self) -> (usize, usize) { self.start }
+
+    #[getter(goal)]
+    fn get_goal(&self) -> (usize, usize) { self.goal }
+
+    /// Path and distance after the latest update, an empty path when the goal cannot be reached.
+    pub fn current_path(&self) -> (Vec<(usize, usize)>, f32) {
+        if self.path.is_empty() {
+            return (Vec::new(), 0.0);
+        }
+
+        (self.path.clone(), self.distance as f32 / MULTF32)
+    }
+}
+
+impl PathPlanner {
+    pub fn new(api: &dyn PositionAPI,
+               grid: &Grid<usize>,
+               start: (usize, usize),
+               goal: (usize, usize),
+               large: bool,
+               influence: bool)
+               -> Self {
+        let size = grid.width() * grid.height();
+        let mut planner = PathPlanner { start,
+                                        goal,
+                                        large,
+                                        influence,
+                                        width: grid.width(),
+                                        height: grid.height(),
+                                        g: vec![INFINITE; size],
+                                        rhs: vec![INFINITE; size],
+                                        queued: vec![None; size],
+                                        open: BinaryHeap::new(),
+                                        path: Vec::new(),
+                                        distance: 0 };
+
+        let goal_index = planner.index(goal);
+        planner.rhs[goal_index] = 0;
+        let key = planner.calculate_key(api, goal_index);
+        planner.insert(goal_index, key);
+        planner.compute_shortest_path(api, grid);
+        planner.extract_path(api, grid);
+        planner
+    }
+
+    /// Repairs the path after the values of the changed cells have been modified in the grid.
+    pub fn update(&mut self, api: &dyn PositionAPI, grid: &Grid<usize>, changed_cells: &[(usize, usize)]) {
+        // Successors of a cell depend on its 3x3 neighbourhood, large units also need room around it
+        let radius = if self.large { 2 } else { 1 };
+
+        for cell in changed_cells {
+            for index in self.neighbourhood(grid, *cell, radius, true) {
+                self.update_vertex(api, grid, index);
+            }
+        }
+
+        self.compute_shortest_path(api, grid);
+        self.extract_path(api, grid);
+    }
+
+    /// Width and height of the grid that the planner was created for.
+    pub fn size(&self) -> (usize, usize) { (self.width, self.height) }
+
+    #[inline]
+    fn index(&self, position: (usize, usize)) -> usize { position.0 * self.height + position.1 }
+
+    #[inline]
+    fn position(&self, index: usize) -> (usize, usize) { (index / self.height, index % self.height) }
+
+    fn calculate_key(&self, api: &dyn PositionAPI, index: usize) -> Key {
+        let value = usize::min(self.g[index], self.rhs[index]);
+        let (x, y) = self.position(index);
+        let heuristic = api.octile_distance(&Pos(self.start.0, self.start.1), &Pos(x, y));
+        (value.saturating_add(heuristic), value)
+    }
+
+    fn insert(&mut self, index: usize, key: Key) {
+        self.queued[index] = Some(key);
+        self.open.push(Reverse((key, index)));
+    }
+
+    /// Lowest key in the open list, outdated entries are dropped on the way.
+    fn top(&mut self) -> Option<(Key, usize)> {
+        while let Some(&Reverse((key, index))) = self.open.peek() {
+            if self.queued[index] == Some(key) {
+                return Some((key, index));
+            }
+
+            self.open.pop();
+        }
+
+        None
+    }
+
+    fn update_vertex(&mut self, api: &dyn PositionAPI, grid: &Grid<usize>, index: usize) {
+        let position = self.position(index);
+
+        if position != self.goal {
+            let mut rhs = INFINITE;
+
+            if grid[position] > 0 {
+                for (successor, cost) in api.successors(&Pos(position.0, position.1), grid) {
+                    let g = self.g[self.index((successor.0, successor.1))];
+                    rhs = usize::min(rhs, g.saturating_add(cost));
+                }
+            }
+
+            self.rhs[index] = rhs;
+        }
+
+        self.queued[index] = None;
+
+        if self.g[index] != self.rhs[index] {
+            let key = self.calculate_key(api, index);
+            self.insert(index, key);
+        }
+    }
+
+    fn compute_shortest_path(&mut self, api: &dyn PositionAPI, grid: &Grid<usize>) {
+        let start_index = self.index(self.start);
+
+        loop {
+            let start_key = self.calculate_key(api, start_index);
+            let top = self.top();
+            let start_consistent = self.g[start_index] == self.rhs[start_index];
+
+            let (old_key, index) = match top {
+                Some((key, index)) if key < start_key || !start_consistent => (key, index),
+                _ => break,
+            };
+
+            self.open.pop();
+            self.queued[index] = None;
+            let new_key = self.calculate_key(api, index);
+
+            if old_key < new_key {
+                self.insert(index, new_key);
+            } else if self.g[index] > self.rhs[index] {
+                self.g[index] = self.rhs[index];
+
+                for neighbour in self.neighbourhood(grid, self.position(index), 1, false) {
+                    self.update_vertex(api, grid, neighbour);
+                }
+            } else {
+                self.g[index] = INFINITE;
+
+                for neighbour in self.neighbourhood(grid, self.position(index), 1, true) {
+                    self.update_vertex(api, grid, neighbour);
+                }
+            }
+        }
+    }
+
+    fn extract_path(&mut self, api: &dyn PositionAPI, grid: &Grid<usize>) {
+        self.path.clear();
+        self.distance = self.g[self.index(self.start)];
+
+        if self.distance == INFINITE {
+            return;
+        }
+
+        let mut current = self.start;
+        self.path.push(current);
+
+        while current != self.goal && self.path.len() <= self.g.len() {
+            let mut best: Option<((usize, usize), usize)> = None;
+
+            for (successor, cost) in api.successors(&Pos(current.0, current.1), grid) {
+                let value = self.g[self.index((successor.0, successor.1))].saturating_add(cost);
+
+                if value != INFINITE && best.is_none_or(|b| value < b.1) {
+                    best = Some(((successor.0, successor.1), value));
+                }
+            }
+
+            match best {
+                Some((next, _)) => {
+                    current = next;
+                    self.path.push(current);
+                }
+                None => {
+                    self.path.clear();
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Indices of the cells up to radius away from the position that are inside the grid, radius can be 1 or 2.
+    fn neighbourhood(&self,
+                     grid: &Grid<usize>,
+                     position: (usize, usize),
+                     radius: usize,
+                     include_center: bool)
+                     -> ArrayVec<usize, 25> {
+        let mut result = ArrayVec::<usize, 25>::new();
+        let x_range = position.0.saturating_sub(radius)..usize::min(position.0 + radius + 1, grid.width());
+
+        for x in x_range {
+            for y in position.1.saturating_sub(radius)..usize::min(position.1 + radius + 1, grid.height()) {
+                if include_center || (x, y) != position {
+                    result.push(self.index((x, y)));
+                }
+            }
+        }
+
+        result
+    }
+}
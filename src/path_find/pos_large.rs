@@ -1,3 +1,4 @@
+use crate::helpers::grid::Grid;
 use crate::path_find::pos::Pos;
 use crate::path_find::pos::PositionAPI;
 use arrayvec::ArrayVec;
@@ -42,14 +43,14 @@ impl PositionAPI for PosLargeAPI {
     }
 
     #[inline]
-    fn successors(&self, pos: &Pos, grid: &[Vec<usize>]) -> ArrayVec<(Pos, usize), 8> {
-        self.successors_within(pos, grid, ((0, 0), (grid.len(), grid[0].len())))
+    fn successors(&self, pos: &Pos, grid: &Grid<usize>) -> ArrayVec<(Pos, usize), 8> {
+        self.successors_within(pos, grid, ((0, 0), (grid.width(), grid.height())))
     }
 
     #[inline]
     fn successors_within(&self,
                          pos: &Pos,
-                         grid: &[Vec<usize>],
+                         grid: &Grid<usize>,
                          window: ((usize, usize), (usize, usize)))
                          -> ArrayVec<(Pos, usize), 8> {
         let &Pos(x, y) = pos;
@@ -69,42 +70,38 @@ impl PositionAPI for PosLargeAPI {
         let ((x0, y0), (x1, y1)) = window;
 
         if x > x0 {
-            val_left = grid[x - 1][y] > 0;
+            val_left = grid[(x - 1, y)] > 0;
         }
 
         if y > y0 {
-            val_down = grid[x][y - 1] > 0;
+            val_down = grid[(x, y - 1)] > 0;
         }
 
         if x + 1 < x1 {
-            val_right = grid[x + 1][y] > 0;
+            val_right = grid[(x + 1, y)] > 0;
         }
 
         if y + 1 < y1 {
-            val_up = grid[x][y + 1] > 0;
+            val_up = grid[(x, y + 1)] > 0;
         }
 
         if val_left {
-            arr.push((Pos(x - 1, y), MULT));
-
             if val_down {
-                val_left_down = grid[x - 1][y - 1] > 0;
+                val_left_down = grid[(x - 1, y - 1)] > 0;
             }
 
             if val_up {
-                val_left_up = grid[x - 1][y + 1] > 0;
+                val_left_up = grid[(x - 1, y + 1)] > 0;
             }
         }
 
         if val_right {
-            arr.push((Pos(x + 1, y), MULT));
-
             if val_down {
-                val_right_down = grid[x + 1][y - 1] > 0;
+                val_right_down = grid[(x + 1, y - 1)] > 0;
             }
 
             if val_up {
-                val_right_up = grid[x + 1][y + 1] > 0;
+                val_right_up = grid[(x + 1, y + 1)] > 0;
             }
         }
 
@@ -176,14 +173,14 @@ impl PositionAPI for InfluencedPosLargeAPI {
     }
 
     #[inline]
-    fn successors(&self, pos: &Pos, grid: &[Vec<usize>]) -> ArrayVec<(Pos, usize), 8> {
-        self.successors_within(pos, grid, ((0, 0), (grid.len(), grid[0].len())))
+    fn successors(&self, pos: &Pos, grid: &Grid<usize>) -> ArrayVec<(Pos, usize), 8> {
+        self.successors_within(pos, grid, ((0, 0), (grid.width(), grid.height())))
     }
 
     #[inline]
     fn successors_within(&self,
                          pos: &Pos,
-                         grid: &[Vec<usize>],
+                         grid: &Grid<usize>,
                          window: ((usize, usize), (usize, usize)))
                          -> ArrayVec<(Pos, usize), 8> {
         let &Pos(x, y) = pos;
@@ -205,38 +202,38 @@ impl PositionAPI for InfluencedPosLargeAPI {
         let y1 = window.1 .1;
 
         if x > x0 {
-            val_left = grid[x - 1][y];
+            val_left = grid[(x - 1, y)];
         }
 
         if y > y0 {
-            val_down = grid[x][y - 1];
+            val_down = grid[(x, y - 1)];
         }
 
         if x + 1 < x1 {
-            val_right = grid[x + 1][y];
+            val_right = grid[(x + 1, y)];
         }
 
         if y + 1 < y1 {
-            val_up = grid[x][y + 1];
+            val_up = grid[(x, y + 1)];
         }
 
         if val_left > 0 {
             if val_down > 0 {
-                val_left_down = grid[x - 1][y - 1];
+                val_left_down = grid[(x - 1, y - 1)];
             }
 
             if val_up > 0 {
-                val_left_up = grid[x - 1][y + 1];
+                val_left_up = grid[(x - 1, y + 1)];
             }
         }
 
         if val_right > 0 {
             if val_down > 0 {
-                val_right_down = grid[x + 1][y - 1];
+                val_right_down = grid[(x + 1, y - 1)];
             }
 
             if val_up > 0 {
-                val_right_up = grid[x + 1][y + 1];
+                val_right_up = grid[(x + 1, y + 1)];
             }
         }
 
@@ -0,0 +1,471 @@
+use arrayvec::ArrayVec;
+use pyo3::prelude::*;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::time::{Duration, Instant};
+
+use crate::path_find::pos::Pos;
+use crate::path_find::PathStatus;
+
+const NO_PARENT: usize = usize::MAX;
+/// How many nodes are expanded between checks of the deadline.
+const DEADLINE_INTERVAL: usize = 64;
+
+/// Open list entry ordered by cost only, so that equal cost entries pop in the same order
+/// as they do in `pathfinding::dijkstra_partial`.
+struct OpenNode {
+    cost: usize,
+    index: usize,
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool { self.cost == other.cost }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> { Some(self.cmp(other)) }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering { other.cost.cmp(&self.cost) }
+}
+
+/// Limits for a budgeted search, no limits means the search runs until it finishes.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SearchBudget {
+    pub possible_max_expansions: Option<usize>,
+    pub possible_deadline: Option<Instant>,
+}
+
+impl SearchBudget {
+    /// Budget with the maximum number of expanded nodes and the maximum time in milliseconds.
+    pub fn new(possible_max_expansions: Option<usize>, possible_max_time: Option<f32>) -> Self {
+        let possible_deadline =
+            possible_max_time.map(|ms| Instant::now() + Duration::from_secs_f32(ms.max(0.0) / 1000.0));
+
+        SearchBudget { possible_max_expansions,
+                       possible_deadline }
+    }
+
+    /// Whether a search that has expanded this many positions should stop.
+    #[inline]
+    pub(crate) fn exhausted(&self, expansions: usize) -> bool {
+        if let Some(max_expansions) = self.possible_max_expansions {
+            if expansions >= max_expansions {
+                return true;
+            }
+        }
+
+        match self.possible_deadline {
+            Some(deadline) => expansions.is_multiple_of(DEADLINE_INTERVAL) && Instant::now() >= deadline,
+            None => false,
+        }
+    }
+}
+
+/// From python this is a tuple of (max_expansions, max_time) where either can be None,
+/// max time is in milliseconds and starts from the call.
+impl<'source> FromPyObject<'source> for SearchBudget {
+    fn extract(ob: &'source PyAny) -> PyResult<Self> {
+        let (possible_max_expansions, possible_max_time) = ob.extract()?;
+        Ok(SearchBudget::new(possible_max_expansions, possible_max_time))
+    }
+}
+
+/// Flags that a search stores for the cells it looks at, e.g. results of expensive walkability checks.
+/// Cleared between searches with a generation counter like the search buffers.
+#[derive(Default)]
+pub struct CellMarks {
+    marks: Vec<u8>,
+    generations: Vec<u32>,
+    generation: u32,
+}
+
+impl CellMarks {
+    /// Clears the marks of the previous search.
+    pub fn begin(&mut self, width: usize, height: usize) {
+        let size = width * height;
+
+        if self.generations.len() != size {
+            self.marks = vec![0; size];
+            self.generations = vec![0; size];
+            self.generation = 0;
+        }
+
+        if self.generation == u32::MAX {
+            for generation in &mut self.generations {
+                *generation = 0;
+            }
+            self.generation = 0;
+        }
+
+        self.generation += 1;
+    }
+
+    /// Marks of the cell, 0 if nothing was stored during this search.
+    #[inline]
+    pub fn get(&self, index: usize) -> u8 {
+        if self.generations[index] == self.generation {
+            self.marks[index]
+        } else {
+            0
+        }
+    }
+
+    #[inline]
+    pub fn set(&mut self, index: usize, marks: u8) {
+        self.generations[index] = self.generation;
+        self.marks[index] = marks;
+    }
+}
+
+/// Reusable buffers for grid searches.
+/// Cells are marked as seen with a generation counter, so nothing needs to be cleared between searches.
+pub struct SearchEngine {
+    costs: Vec<usize>,
+    parents: Vec<usize>,
+    generations: Vec<u32>,
+    generation: u32,
+    reached: Vec<usize>,
+    open: BinaryHeap<OpenNode>,
+    height: usize,
+    /// Cell marks for searches that need them, allocated on first use.
+    pub marks: CellMarks,
+}
+
+impl SearchEngine {
+    pub fn new(width: usize, height: usize) -> Self {
+        SearchEngine { costs: vec![0; width * height],
+                       parents: vec![NO_PARENT; width * height],
+                       generations: vec![0; width * height],
+                       generation: 0,
+                       reached: Vec::new(),
+                       open: BinaryHeap::new(),
+                       height,
+                       marks: CellMarks::default() }
+    }
+
+    /// Dijkstra search from start that stops when `stop` returns true for a popped node.
+    /// Finds the same nodes with the same costs as `pathfinding::dijkstra_partial`.
+    /// Returns the node that stopped the search, results can be read with `reached` and `cost`.
+    pub fn dijkstra_partial<FN, FS>(&mut self,
+                                    width: usize,
+                                    height: usize,
+                                    start: (usize, usize),
+                                    mut successors: FN,
+                                    mut stop: FS)
+                                    -> Option<(usize, usize)>
+        where FN: FnMut(&Pos) -> ArrayVec<(Pos, usize), 8>,
+              FS: FnMut(&Pos) -> bool
+    {
+        self.begin(width, height);
+
+        let start_index = start.0 * height + start.1;
+        self.discover(start_index, NO_PARENT, 0);
+        self.open.push(OpenNode { cost: 0, index: start_index });
+
+        while let Some(OpenNode { cost, index }) = self.open.pop() {
+            let pos = Pos(index / height, index % height);
+
+            if stop(&pos) {
+                return Some((pos.0, pos.1));
+            }
+
+            // Nodes are pushed again when a cheaper route is found, skip the outdated entries.
+            if cost > self.costs[index] {
+                continue;
+            }
+
+            for (successor, move_cost) in successors(&pos) {
+                let new_cost = cost + move_cost;
+                let successor_index = successor.0 * height + successor.1;
+
+                if self.generations[successor_index] == self.generation {
+                    if self.costs[successor_index] <= new_cost {
+                        continue;
+                    }
+
+                    self.costs[successor_index] = new_cost;
+                    self.parents[successor_index] = index;
+                } else {
+                    self.discover(successor_index, index, new_cost);
+                }
+
+                self.open.push(OpenNode { cost: new_cost,
+                                          index: successor_index });
+            }
+        }
+
+        None
+    }
+
+    /// Dijkstra search from start for the goal with the lowest cost plus goal weight.
+    /// `goal_weight` returns the weight of goal nodes and None for other nodes.
+    /// Returns the goal and its cost without the weight, the path can be read with `path`.
+    pub fn dijkstra_nearest<FN, FG>(&mut self,
+                                    width: usize,
+                                    height: usize,
+                                    start: (usize, usize),
+                                    mut successors: FN,
+                                    mut goal_weight: FG)
+                                    -> Option<((usize, usize), usize)>
+        where FN: FnMut(&Pos) -> ArrayVec<(Pos, usize), 8>,
+              FG: FnMut(&Pos) -> Option<usize>
+    {
+        self.begin(width, height);
+
+        let start_index = start.0 * height + start.1;
+        self.discover(start_index, NO_PARENT, 0);
+        self.open.push(OpenNode { cost: 0, index: start_index });
+
+        let mut best: Option<((usize, usize), usize, usize)> = None;
+
+        while let Some(OpenNode { cost, index }) = self.open.pop() {
+            if cost > self.costs[index] {
+                continue;
+            }
+
+            // Goals found later can't be cheaper as weights are never negative
+            if let Some((_, _, best_total)) = best {
+                if cost >= best_total {
+                    break;
+                }
+            }
+
+            let pos = Pos(index / height, index % height);
+
+            if let Some(weight) = goal_weight(&pos) {
+                if best.is_none_or(|(_, _, best_total)| cost + weight < best_total) {
+                    best = Some(((pos.0, pos.1), cost, cost + weight));
+                }
+            }
+
+            for (successor, move_cost) in successors(&pos) {
+                let new_cost = cost + move_cost;
+                let successor_index = successor.0 * height + successor.1;
+
+                if self.generations[successor_index] == self.generation {
+                    if self.costs[successor_index] <= new_cost {
+                        continue;
+                    }
+
+                    self.costs[successor_index] = new_cost;
+                    self.parents[successor_index] = index;
+                } else {
+                    self.discover(successor_index, index, new_cost);
+                }
+
+                self.open.push(OpenNode { cost: new_cost,
+                                          index: successor_index });
+            }
+        }
+
+        best.map(|(goal, cost, _)| (goal, cost))
+    }
+
+    /// A* search from start towards the node where heuristic is zero, stopping when the budget runs out.
+    /// Returns the status and the goal, or the expanded node with the lowest heuristic when the goal
+    /// was not reached. Path and cost to the returned node can be read with `path` and `cost`.
+    pub fn astar_budget<FN, FH>(&mut self,
+                                width: usize,
+                                height: usize,
+                                start: (usize, usize),
+                                mut successors: FN,
+                                mut heuristic: FH,
+                                budget: SearchBudget)
+                                -> (PathStatus, (usize, usize))
+        where FN: FnMut(&Pos) -> ArrayVec<(Pos, usize), 8>,
+              FH: FnMut(&Pos) -> usize
+    {
+        self.begin(width, height);
+
+        let start_index = start.0 * height + start.1;
+        let start_heuristic = heuristic(&Pos(start.0, start.1));
+        self.discover(start_index, NO_PARENT, 0);
+        self.open.push(OpenNode { cost: start_heuristic,
+                                  index: start_index });
+
+        let mut best = (start_heuristic, 0, start);
+        let mut expansions = 0;
+
+        while let Some(OpenNode { cost: estimate, index }) = self.open.pop() {
+            let pos = Pos(index / height, index % height);
+            let cost = self.costs[index];
+            let remaining = heuristic(&pos);
+
+            // Nodes are pushed again when a cheaper route is found, skip the outdated entries.
+            if estimate > cost + remaining {
+                continue;
+            }
+
+            if remaining == 0 {
+                return (PathStatus::Complete, (pos.0, pos.1));
+            }
+
+            if (remaining, cost) < (best.0, best.1) {
+                best = (remaining, cost, (pos.0, pos.1));
+            }
+
+            if budget.exhausted(expansions) {
+                return (PathStatus::Partial, best.2);
+            }
+
+            expansions += 1;
+
+            for (successor, move_cost) in successors(&pos) {
+                let new_cost = cost + move_cost;
+                let successor_index = successor.0 * height + successor.1;
+
+                if self.generations[successor_index] == self.generation {
+                    if self.costs[successor_index] <= new_cost {
+                        continue;
+                    }
+
+                    self.costs[successor_index] = new_cost;
+                    self.parents[successor_index] = index;
+                } else {
+                    self.discover(successor_index, index, new_cost);
+                }
+
+                self.open.push(OpenNode { cost: new_cost + heuristic(&successor),
+                                          index: successor_index });
+            }
+        }
+
+        (PathStatus::Unreachable, best.2)
+    }
+
+    /// A* search from start to the first popped node where `is_goal` is true.
+    /// Successors also get the parent of the node, None for the start, which jump point search uses for pruning.
+    /// Returns the goal, path and cost to it can be read with `path` and `cost`.
+    pub fn astar_from_parent<FN, FH, FG>(&mut self,
+                                         width: usize,
+                                         height: usize,
+                                         start: (usize, usize),
+                                         mut successors: FN,
+                                         mut heuristic: FH,
+                                         mut is_goal: FG)
+                                         -> Option<(usize, usize)>
+        where FN: FnMut(&Pos, Option<Pos>) -> ArrayVec<(Pos, usize), 8>,
+              FH: FnMut(&Pos) -> usize,
+              FG: FnMut(&Pos) -> bool
+    {
+        self.begin(width, height);
+
+        let start_index = start.0 * height + start.1;
+        self.discover(start_index, NO_PARENT, 0);
+        self.open.push(OpenNode { cost: heuristic(&Pos(start.0, start.1)),
+                                  index: start_index });
+
+        while let Some(OpenNode { cost: estimate, index }) = self.open.pop() {
+            let pos = Pos(index / height, index % height);
+            let cost = self.costs[index];
+
+            // Nodes are pushed again when a cheaper route is found, skip the outdated entries.
+            if estimate > cost + heuristic(&pos) {
+                continue;
+            }
+
+            if is_goal(&pos) {
+                return Some((pos.0, pos.1));
+            }
+
+            let parent = self.parents[index];
+            let possible_parent = if parent == NO_PARENT { None } else { Some(Pos(parent / height, parent % height)) };
+
+            for (successor, move_cost) in successors(&pos, possible_parent) {
+                let new_cost = cost + move_cost;
+                let successor_index = successor.0 * height + successor.1;
+
+                if self.generations[successor_index] == self.generation {
+                    if self.costs[successor_index] <= new_cost {
+                        continue;
+                    }
+
+                    self.costs[successor_index] = new_cost;
+                    self.parents[successor_index] = index;
+                } else {
+                    self.discover(successor_index, index, new_cost);
+                }
+
+                self.open.push(OpenNode { cost: new_cost + heuristic(&successor),
+                                          index: successor_index });
+            }
+        }
+
+        None
+    }
+
+    /// Nodes found by the last search except the start, in the order they were found, with their costs.
+    pub fn reached(&self) -> impl Iterator<Item = ((usize, usize), usize)> + '_ {
+        let height = self.height;
+
+        self.reached
+            .iter()
+            .skip(1)
+            .map(move |&index| ((index / height, index % height), self.costs[index]))
+    }
+
+    /// Cost of the node in the last search, None if the node was not found.
+    pub fn cost(&self, position: (usize, usize)) -> Option<usize> {
+        let index = position.0 * self.height + position.1;
+
+        if index < self.generations.len() && self.generations[index] == self.generation {
+            Some(self.costs[index])
+        } else {
+            None
+        }
+    }
+
+    /// Path from the start of the last search to the node, empty if the node was not found.
+    pub fn path(&self, position: (usize, usize)) -> Vec<(usize, usize)> {
+        let mut path = Vec::new();
+
+        if self.cost(position).is_none() {
+            return path;
+        }
+
+        let mut index = position.0 * self.height + position.1;
+
+        while index != NO_PARENT {
+            path.push((index / self.height, index % self.height));
+            index = self.parents[index];
+        }
+
+        path.reverse();
+        path
+    }
+
+    fn begin(&mut self, width: usize, height: usize) {
+        let size = width * height;
+
+        if self.generations.len() != size || self.height != height {
+            self.costs = vec![0; size];
+            self.parents = vec![NO_PARENT; size];
+            self.generations = vec![0; size];
+            self.generation = 0;
+            self.height = height;
+        }
+
+        if self.generation == u32::MAX {
+            for generation in &mut self.generations {
+                *generation = 0;
+            }
+            self.generation = 0;
+        }
+
+        self.generation += 1;
+        self.reached.clear();
+        self.open.clear();
+    }
+
+    #[inline]
+    fn discover(&mut self, index: usize, parent: usize, cost: usize) {
+        self.generations[index] = self.generation;
+        self.costs[index] = cost;
+        self.parents[index] = parent;
+        self.reached.push(index);
+    }
+}
@@ -1,35 +1,104 @@
+use crate::helpers::grid::Grid;
+
+/// Default for how far positions are moved to the closest free cell, as far as the search grid reaches.
+pub const DEFAULT_MAX_DISTANCE: f32 = 4.0;
+/// The search grid has every cell of the rings closer than this, the rest are searched ring by ring.
+const FIRST_UNCHECKED_RING: i64 = 3;
+
 #[derive(Clone, Debug)]
 pub struct FreeFinder {
     closest_grid: Vec<(i64, i64)>,
+    /// How far from the lookup position free cells are searched for.
+    pub max_distance: f32,
 }
 
 impl FreeFinder {
     pub fn new() -> FreeFinder {
         let closest_grid = create_search_grid();
-        FreeFinder { closest_grid }
+        FreeFinder { closest_grid,
+                     max_distance: DEFAULT_MAX_DISTANCE }
     }
 
-    pub fn find_free(&self, lookup: (usize, usize), map: &[Vec<usize>], width: usize, height: usize) -> (usize, usize) {
-        let mut result = (lookup.0, lookup.1);
+    pub fn find_free(&self, lookup: (usize, usize), map: &Grid<usize>) -> (usize, usize) {
+        self.find_free_where(lookup, map, |p| map[p] > 0)
+    }
+
+    /// Finds the closest position up to `max_distance` away that is inside the map and accepted by `free`.
+    /// Returns the lookup position if none is found.
+    pub fn find_free_where<F>(&self, lookup: (usize, usize), map: &Grid<usize>, free: F) -> (usize, usize)
+        where F: Fn((usize, usize)) -> bool
+    {
+        self.find_free_within(lookup, map, self.max_distance, free).unwrap_or(lookup)
+    }
 
-        for offset in &self.closest_grid {
+    /// Finds the closest position to the lookup that is inside the map and accepted by `free`,
+    /// the lookup position itself isn't checked.
+    /// Close cells are checked in the order of the search grid, further ones ring by ring.
+    pub fn find_free_within<F>(&self,
+                               lookup: (usize, usize),
+                               map: &Grid<usize>,
+                               max_distance: f32,
+                               free: F)
+                               -> Option<(usize, usize)>
+        where F: Fn((usize, usize)) -> bool
+    {
+        let max_distance2 = max_distance * max_distance;
+        let distance2 = |offset: (i64, i64)| (offset.0 * offset.0 + offset.1 * offset.1) as f32;
+        let accepted = |offset: (i64, i64)| {
             let adjusted = ((lookup.0 as i64 + offset.0), (lookup.1 as i64 + offset.1));
 
             if adjusted.0 >= 0 && adjusted.1 >= 0 {
                 let adjusted_usize = (adjusted.0 as usize, adjusted.1 as usize);
 
-                if adjusted_usize.0 < width && adjusted_usize.1 < height && map[adjusted_usize.0][adjusted_usize.1] > 0
-                {
-                    result = adjusted_usize;
-                    break;
+                if map.in_bounds(adjusted_usize.0, adjusted_usize.1) && free(adjusted_usize) {
+                    return Some(adjusted_usize);
+                }
+            }
+
+            None
+        };
+
+        let found = self.closest_grid
+                        .iter()
+                        .filter(|offset| distance2(**offset) <= max_distance2)
+                        .find_map(|offset| accepted(*offset));
+
+        if found.is_some() {
+            return found;
+        }
+
+        let mut best: Option<((usize, usize), f32)> = None;
+        // Rings further than the size of the map only have cells outside of it
+        let last_ring = f32::min(max_distance, usize::max(map.width(), map.height()) as f32) as i64;
+
+        for ring in FIRST_UNCHECKED_RING..=last_ring {
+            // Every cell in this ring or further is at least ring away
+            if best.is_some_and(|(_, best_distance2)| (ring * ring) as f32 > best_distance2) {
+                break;
+            }
+
+            for offset in ring_offsets(ring) {
+                let offset_distance2 = distance2(offset);
+
+                if offset_distance2 > max_distance2 || best.is_some_and(|(_, d2)| offset_distance2 >= d2) {
+                    continue;
+                }
+
+                if let Some(position) = accepted(offset) {
+                    best = Some((position, offset_distance2));
                 }
             }
         }
 
-        result
+        best.map(|(position, _)| position)
     }
 }
 
+/// Offsets on the border of a square with the given radius, corners are returned twice.
+fn ring_offsets(ring: i64) -> impl Iterator<Item = (i64, i64)> {
+    (-ring..=ring).flat_map(move |d| [(d, -ring), (d, ring), (-ring, d), (ring, d)])
+}
+
 /// ## Search order as follows:
 /// ___A9A___
 /// __87678__
@@ -1,7 +1,7 @@
 use sc2pathlib::mapping::map::Map;
 use sc2pathlib::path_find;
 use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
 
 fn rot90(vec: Vec<Vec<usize>>) -> Vec<Vec<usize>> {
     let new_height = vec.len();
@@ -35,16 +35,50 @@ pub fn read_vec_from_file(file_path: &str) -> Vec<Vec<usize>> {
     rot90(arr)
 }
 
+/// Reads a 2 dimensional uint8 numpy array saved with np.save, as the python wrapper gets the grids from the game.
+/// Rows of the array are y, so the result is transposed to the x first layout.
+pub fn read_vec_from_npy(file_path: &str) -> Vec<Vec<usize>> {
+    let mut bytes = Vec::new();
+    File::open(file_path).unwrap().read_to_end(&mut bytes).unwrap();
+
+    let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+    let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+    let shape = &header[header.find("'shape': (").unwrap() + 10..];
+    let shape: Vec<usize> = shape[..shape.find(')').unwrap()].split(',')
+                                                              .map(|value| value.trim().parse().unwrap())
+                                                              .collect();
+    let data = &bytes[10 + header_length..];
+    let (rows, columns) = (shape[0], shape[1]);
+
+    (0..columns).map(|x| (0..rows).map(|y| data[y * columns + x] as usize).collect())
+                .collect()
+}
+
+pub fn get_automaton_map() -> Map {
+    let pathing = read_vec_from_npy("tests/AutomatonLE_pathing.npy");
+    let placement = read_vec_from_npy("tests/AutomatonLE_placement.npy");
+    let height = read_vec_from_npy("tests/AutomatonLE_height.npy");
+
+    Map::new(pathing, placement, height, 18, 16, 166, 164).unwrap()
+}
+
 pub fn get_pathfind(file: &str) -> path_find::PathFind {
     let map = read_vec_from_file(file);
     path_find::PathFind::new_internal(map)
 }
 
+/// 10x10 map with a wall through the middle at x = 5 that splits it in two.
+pub fn get_split_pathfind() -> path_find::PathFind {
+    let mut map = vec![vec![1; 10]; 10];
+    map[5] = vec![0; 10];
+    path_find::PathFind::new_internal(map)
+}
+
 pub fn get_choke_map() -> Map {
     let grid = read_vec_from_file("tests/choke.txt");
     let grid2 = read_vec_from_file("tests/choke.txt");
     let grid_height = read_vec_from_file("tests/choke_height.txt");
 
-    let map = Map::new(grid, grid2, grid_height, 2, 2, 38, 38);
+    let map = Map::new(grid, grid2, grid_height, 2, 2, 38, 38).unwrap();
     map
 }
@@ -1,5 +1,8 @@
-use common::{get_choke_map, read_vec_from_file};
-use sc2pathlib::mapping::{map::Map, vision::VisionUnit};
+use common::{get_automaton_map, get_choke_map, read_vec_from_file};
+use sc2pathlib::error::PathlibError;
+use sc2pathlib::mapping::{footprint::Footprint, map::Map, ramps::Ramp, vision::VisionUnit};
+use sc2pathlib::path_find::batch::PathQuery;
+use std::time::{Duration, Instant};
 mod common;
 
 #[test]
@@ -7,8 +10,8 @@ fn test_find_path_map() {
     let grid = read_vec_from_file("tests/maze4x4.txt");
     let grid2 = read_vec_from_file("tests/maze4x4.txt");
     let grid3 = read_vec_from_file("tests/maze4x4.txt");
-    let map = Map::new(grid, grid2, grid3, 1, 1, 3, 3);
-    let r = map.find_path_basic(0, (0f32, 0f32), (3f32, 3f32), Some(0));
+    let map = Map::new(grid, grid2, grid3, 1, 1, 3, 3).unwrap();
+    let r = map.find_path_basic(0, (0f32, 0f32), (3f32, 3f32), Some(0)).unwrap();
     let (_, distance) = r;
     assert_eq!(distance, 6.0);
 }
@@ -31,23 +34,364 @@ fn test_find_map_chokes() {
 fn test_ray_vision() {
     let mut map = get_choke_map();
     let vision_unit = VisionUnit::new(false, false, (18f32, 8f32), 10f32);
-    map.add_vision_unit(vision_unit);
+    map.add_vision_unit(vision_unit).unwrap();
     map.calculate_vision_map();
 
-    assert_eq!(map.vision_status((12f32, 8f32)), 1);
-    assert_eq!(map.vision_status((19f32, 8f32)), 1);
-    assert_eq!(map.vision_status((25f32, 8f32)), 0);
-    assert_eq!(map.vision_status((27f32, 8f32)), 0);
+    assert_eq!(map.vision_status((12f32, 8f32)).unwrap(), 1);
+    assert_eq!(map.vision_status((19f32, 8f32)).unwrap(), 1);
+    assert_eq!(map.vision_status((25f32, 8f32)).unwrap(), 0);
+    assert_eq!(map.vision_status((27f32, 8f32)).unwrap(), 0);
 }
 
 #[test]
 fn test_flying_vision() {
     let mut map = get_choke_map();
     let vision_unit = VisionUnit::new(false, true, (19f32, 8f32), 10f32);
-    map.add_vision_unit(vision_unit);
+    map.add_vision_unit(vision_unit).unwrap();
     map.calculate_vision_map();
 
-    assert_eq!(map.vision_status((21f32, 8f32)), 1);
-    assert_eq!(map.vision_status((27f32, 8f32)), 1);
-    assert_eq!(map.vision_status((31f32, 8f32)), 0);
+    assert_eq!(map.vision_status((21f32, 8f32)).unwrap(), 1);
+    assert_eq!(map.vision_status((27f32, 8f32)).unwrap(), 1);
+    assert_eq!(map.vision_status((31f32, 8f32)).unwrap(), 0);
+}
+
+#[test]
+fn test_evaluate_path_counts_seen_cells() {
+    let mut map = get_choke_map();
+    let vision_unit = VisionUnit::new(false, false, (18f32, 8f32), 10f32);
+    map.add_vision_unit(vision_unit).unwrap();
+    map.calculate_vision_map();
+
+    let path = vec![(12, 8), (19, 8), (25, 8), (27, 8)];
+    let stats = map.evaluate_path(0, path, 1.0).unwrap();
+    assert_eq!(stats.seen_cells, Some(2));
+    assert_eq!(stats.total_influence, 0);
+    assert_eq!(stats.first_danger_index, None);
+}
+
+#[test]
+fn test_vision_raycast_matches_ground_vision() {
+    let map = get_choke_map();
+
+    let (clear, blocking, _) = map.vision_raycast((18f32, 8f32), (12f32, 8f32), false).unwrap();
+    assert!(clear);
+    assert_eq!(blocking, None);
+
+    let (clear, blocking, cells) = map.vision_raycast((18f32, 8f32), (25f32, 8f32), true).unwrap();
+    assert!(!clear);
+    let blocking = blocking.unwrap();
+    assert_eq!(cells.last(), Some(&blocking));
+    assert!(blocking.0 > 18 && blocking.0 <= 25);
+}
+
+#[test]
+fn test_find_path_abstract() {
+    let map = get_choke_map();
+    let (route, distance) = map.find_path_abstract((10f32, 10f32), (30f32, 30f32)).unwrap();
+    assert_eq!(route, vec![0]);
+    assert!(distance > 0f32);
+
+    let (route, _) = map.find_path_abstract((10f32, 10f32), (12f32, 30f32)).unwrap();
+    assert!(route.is_empty());
+}
+
+#[test]
+fn test_find_path_hierarchical() {
+    let map = get_choke_map();
+    let (path, distance) = map.find_path_hierarchical((10f32, 10f32), (30f32, 30f32), false, false, Some(1)).unwrap();
+    let (_, full_distance) =
+        map.find_path(0, PathQuery { possible_heuristic: Some(1), ..PathQuery::new((10, 10), (30, 30)) }).unwrap();
+
+    assert_eq!(path.first(), Some(&(10, 10)));
+    assert_eq!(path.last(), Some(&(30, 30)));
+    assert!(distance >= full_distance);
+    assert!(distance < full_distance + 3f32);
+}
+
+#[test]
+fn test_abstract_graph_block_choke() {
+    let mut map = get_choke_map();
+    map.create_block((21.5f32, 21f32), (6, 6));
+    let (route, distance) = map.find_path_abstract((10f32, 10f32), (30f32, 30f32)).unwrap();
+    assert!(route.is_empty());
+    assert_eq!(distance, -1f32);
+
+    map.remove_block((21.5f32, 21f32), (6, 6));
+    let (route, _) = map.find_path_abstract((10f32, 10f32), (30f32, 30f32)).unwrap();
+    assert_eq!(route, vec![0]);
+}
+
+#[test]
+fn test_overlapping_blocks_keep_choke_closed() {
+    let mut map = get_choke_map();
+    let first = map.create_block((21.5f32, 21f32), (6, 6));
+    let second = map.create_block((21.5f32, 21f32), (6, 6));
+
+    assert!(map.remove_block_by_id(first));
+    assert!(!map.same_component(0, (10f32, 10f32), (30f32, 30f32)).unwrap());
+
+    assert!(map.remove_block_by_id(second));
+    assert!(map.same_component(0, (10f32, 10f32), (30f32, 30f32)).unwrap());
+    let (route, _) = map.find_path_abstract((10f32, 10f32), (30f32, 30f32)).unwrap();
+    assert_eq!(route, vec![0]);
+}
+
+#[test]
+fn test_create_footprint_masks() {
+    let mut map = get_choke_map();
+    let town_hall = map.create_footprint((10.5f32, 10.5f32), Footprint::TownHall as u8).unwrap();
+
+    // Cut corners of a town hall stay pathable
+    for pathing in [&map.ground_pathing, &map.colossus_pathing, &map.reaper_pathing] {
+        assert!(pathing.map[(8, 8)] > 0);
+        assert!(pathing.map[(12, 12)] > 0);
+        assert_eq!(pathing.map[(8, 9)], 0);
+        assert_eq!(pathing.map[(10, 10)], 0);
+        assert!(pathing.map[(13, 10)] > 0);
+    }
+
+    map.create_footprint((6f32, 6.5f32), Footprint::MineralField as u8).unwrap();
+    assert_eq!(map.ground_pathing.map[(5, 6)], 0);
+    assert_eq!(map.ground_pathing.map[(6, 6)], 0);
+    assert!(map.ground_pathing.map[(5, 7)] > 0);
+    assert!(map.ground_pathing.map[(7, 6)] > 0);
+
+    let depot = map.create_footprint((15f32, 15f32), Footprint::SupplyDepotLowered as u8).unwrap();
+    assert!(map.ground_pathing.map[(14, 14)] > 0);
+    assert!(map.remove_block_by_id(depot));
+
+    assert!(map.remove_block_by_id(town_hall));
+    assert!(map.ground_pathing.map[(10, 10)] > 0);
+    assert!(matches!(map.create_footprint((10f32, 10f32), 99), Err(PathlibError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_same_component_block_choke() {
+    let mut map = get_choke_map();
+    assert!(map.same_component(0, (10f32, 10f32), (30f32, 30f32)).unwrap());
+
+    map.create_block((21.5f32, 21f32), (6, 6));
+    assert!(!map.same_component(0, (10f32, 10f32), (30f32, 30f32)).unwrap());
+    assert!(map.same_component(3, (10f32, 10f32), (30f32, 30f32)).unwrap());
+
+    map.remove_block((21.5f32, 21f32), (6, 6));
+    assert!(map.same_component(0, (10f32, 10f32), (30f32, 30f32)).unwrap());
+}
+
+#[test]
+fn test_find_path_smooth_around_wall() {
+    let map = get_choke_map();
+    let query = PathQuery { possible_heuristic: Some(1),
+                            ..PathQuery::new((10, 10), (30, 10)) };
+    let (waypoints, distance) = map.find_path_smooth(0, query).unwrap();
+
+    // The wall between the rooms forces at least one turn at the choke
+    assert!(waypoints.len() >= 3);
+    assert!(distance > 20f32);
+}
+
+#[test]
+fn test_invalid_map_input_returns_errors() {
+    let mut map = get_choke_map();
+    assert_eq!(map.find_path_basic(7, (10f32, 10f32), (30f32, 30f32), Some(0)).unwrap_err(),
+               PathlibError::UnknownMapType(7));
+    assert!(matches!(map.get_zone((200f32, 10f32)), Err(PathlibError::OutOfBounds { .. })));
+    assert!(matches!(map.lowest_influence(0, (200f32, 10f32), 3), Err(PathlibError::OutOfBounds { .. })));
+    assert!(matches!(map.find_path_basic(0, (10f32, 10f32), (10f32, 99f32), Some(0)),
+                     Err(PathlibError::OutOfBounds { .. })));
+    let vision_unit = VisionUnit::new(true, false, (10f32, 99f32), 10f32);
+    assert!(matches!(map.add_vision_unit(vision_unit), Err(PathlibError::OutOfBounds { .. })));
+    map.calculate_vision_map();
+
+    let grid = read_vec_from_file("tests/maze4x4.txt");
+    let mut ragged = grid.clone();
+    ragged[0].pop();
+    let result = Map::new(ragged, grid.clone(), grid.clone(), 1, 1, 3, 3);
+    assert!(matches!(result, Err(PathlibError::InvalidGrid(_))));
+
+    let result = Map::new(grid.clone(), grid.clone(), grid.clone(), 0, 1, 3, 3);
+    assert!(matches!(result, Err(PathlibError::InvalidWindow { .. })));
+    let result = Map::new(grid.clone(), grid.clone(), grid, 1, 1, 4, 3);
+    assert!(matches!(result, Err(PathlibError::InvalidWindow { .. })));
+}
+
+#[test]
+fn test_map_walkable_near_the_edge() {
+    // Climb checks look two cells past walkable cells next to the edge, which used to read outside the grid
+    let mut grid = vec![vec![1; 64]; 64];
+    for column in grid.iter_mut() {
+        column[0] = 0;
+        column[63] = 0;
+    }
+    grid[0] = vec![0; 64];
+    grid[63] = vec![0; 64];
+    let map = Map::new(grid.clone(), grid.clone(), grid, 1, 1, 62, 62).unwrap();
+    assert!(map.same_component(0, (2f32, 2f32), (61f32, 61f32)).unwrap());
+}
+
+#[test]
+fn test_plan_wall_closes_choke() {
+    let mut map = get_choke_map();
+    let depots = vec![Footprint::Building2x2 as u8; 3];
+    let wall = map.plan_wall_rust((21.5f32, 22f32), (10f32, 22f32), &depots, 0).unwrap().unwrap();
+    assert!(!wall.is_empty() && wall.len() <= 3);
+
+    let ids: Vec<usize> = wall.iter()
+                              .map(|(footprint, center)| map.create_footprint(*center, *footprint).unwrap())
+                              .collect();
+    assert!(!map.same_component(0, (10f32, 22f32), (30f32, 22f32)).unwrap());
+
+    for id in ids {
+        map.remove_block_by_id(id);
+    }
+
+    let wall = map.plan_wall_rust((21.5f32, 22f32), (10f32, 22f32), &depots, 1).unwrap().unwrap();
+
+    for (footprint, center) in wall {
+        map.create_footprint(center, footprint).unwrap();
+    }
+    assert!(map.same_component(0, (10f32, 22f32), (30f32, 22f32)).unwrap());
+
+    // The gap is a single cell, blocking one more cell of the passage disconnects the sides
+    let closes_gap = (15..29).flat_map(|x| (15..30).map(move |y| (x as f32 + 0.5, y as f32 + 0.5)))
+                             .any(|cell| {
+                                 let id = map.create_block(cell, (1, 1));
+                                 let connected = map.same_component(0, (10f32, 22f32), (30f32, 22f32)).unwrap();
+                                 map.remove_block_by_id(id);
+                                 !connected
+                             });
+    assert!(closes_gap);
+
+    let result = map.plan_wall_rust((21.5f32, 22f32), (21.5f32, 22f32), &[1], 0);
+    assert!(matches!(result, Err(PathlibError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_plan_wall_gives_up_quickly() {
+    let map = get_choke_map();
+    let (depot, sensor) = (Footprint::Building2x2 as u8, Footprint::Building1x1 as u8);
+
+    // A single depot is too narrow for the passage
+    assert_eq!(map.plan_wall_rust((21.5f32, 22f32), (10f32, 22f32), &[depot], 0).unwrap(), None);
+
+    // Wide enough in total, but no chain of them closes the passage
+    let started = Instant::now();
+    let buildings = [Footprint::Building3x3 as u8; 4];
+    assert_eq!(map.plan_wall_rust((21.5f32, 22f32), (10f32, 22f32), &buildings, 1).unwrap(), None);
+    let buildings = [depot, sensor, sensor, sensor, sensor, sensor];
+    assert_eq!(map.plan_wall_rust((21.5f32, 22f32), (10f32, 22f32), &buildings, 2).unwrap(), None);
+    assert!(started.elapsed() < Duration::from_secs(2), "took {:?}", started.elapsed());
+}
+
+#[test]
+fn test_find_placement() {
+    let mut map = get_choke_map();
+    assert_eq!(map.find_placement((10.5f32, 10.5f32), Footprint::Building3x3 as u8, 5f32, None, None).unwrap(),
+               Some((10.5f32, 10.5f32)));
+
+    let town_hall = map.create_footprint((10.5f32, 10.5f32), Footprint::TownHall as u8).unwrap();
+    assert!(!map.placement.is_buildable((8, 8)));
+    let depot = map.find_placement((10f32, 10f32), Footprint::Building2x2 as u8, 5f32, None, None).unwrap().unwrap();
+    assert!((depot.0 - 10.0).abs() >= 3.0 || (depot.1 - 10.0).abs() >= 3.0);
+
+    map.remove_block_by_id(town_hall);
+    assert!(map.placement.is_buildable((8, 8)));
+
+    // Mineral line around the mineral field is kept free
+    map.create_footprint((10f32, 14.5f32), Footprint::MineralField as u8).unwrap();
+    let sensor = map.find_placement((10.5f32, 12.5f32), Footprint::Building1x1 as u8, 5f32, None, None)
+                    .unwrap()
+                    .unwrap();
+    assert!(sensor.1 < 11.0 || sensor.1 > 18.0 || sensor.0 < 6.0 || sensor.0 > 14.0);
+
+    let pylons = vec![(30f32, 30f32)];
+    let powered = map.find_placement((10f32, 30f32), Footprint::Building3x3 as u8, 30f32, Some(pylons), None).unwrap();
+    let (x, y) = powered.unwrap();
+    assert!(((x - 30.0).powi(2) + (y - 30.0).powi(2)).sqrt() <= 6.5);
+
+    let mut creep = vec![vec![0; 41]; 43];
+    creep[30][10] = 1;
+    let zerg = map.find_placement((10f32, 10f32), Footprint::Building1x1 as u8, 30f32, None, Some(creep)).unwrap();
+    assert_eq!(zerg, Some((30.5f32, 10.5f32)));
+
+    map.reset();
+    assert!(map.placement.is_buildable((10, 14)));
+}
+
+#[test]
+fn test_find_placement_avoids_ramps() {
+    let pathing = read_vec_from_file("tests/choke.txt");
+    let mut placement = read_vec_from_file("tests/choke.txt");
+    let height = read_vec_from_file("tests/choke_height.txt");
+
+    for column in placement.iter_mut().take(24).skip(20) {
+        for cell in column.iter_mut().take(24).skip(21) {
+            *cell = 0;
+        }
+    }
+
+    let map = Map::new(pathing, placement, height, 2, 2, 38, 38).unwrap();
+    let result = map.find_placement((19.5f32, 22.5f32), Footprint::Building1x1 as u8, 3f32, None, None).unwrap();
+    assert_eq!(result, Some((18.5f32, 22.5f32)));
+}
+
+#[test]
+fn test_ramps_automaton() {
+    let mut map = get_automaton_map();
+    assert!(map.ramps.len() > 10);
+
+    let mains: Vec<&Ramp> = map.ramps.iter().filter(|ramp| ramp.is_main).collect();
+    assert_eq!(mains.len(), 2);
+
+    for ramp in &mains {
+        assert!(ramp.upper.iter().all(|cell| ramp.cells.contains(cell)));
+        assert!(ramp.width >= 2.0 && ramp.width <= 6.0);
+        let heights =
+            |cells: &[(usize, usize)]| cells.iter().map(|p| map.points[*p].height).sum::<usize>() / cells.len();
+        assert!(heights(&ramp.upper) > heights(&ramp.lower));
+    }
+
+    // Main ramp of the start location at (32.5, 51.5) leads up towards the west
+    let ramp = mains.iter().find(|ramp| ramp.top_center.0 < 92.0).unwrap();
+    assert!((ramp.top_center.0 - 33.5).abs() < 2.0 && (ramp.top_center.1 - 51.0).abs() < 2.0);
+    let (dx, dy) = ramp.direction;
+    assert!((dx * dx + dy * dy - 1.0).abs() < 1e-3);
+    assert!(dx < 0.0);
+
+    let top = ramp.top_center;
+    map.calculate_zones(vec![(29.5f32, 65.5f32), (154.5f32, 114.5f32)]).unwrap();
+    let ramp = map.ramps.iter().find(|ramp| ramp.top_center == top).unwrap();
+    assert_eq!(ramp.zones.0, 1);
+}
+
+#[test]
+fn test_plan_wall_main_ramp() {
+    let mut map = get_automaton_map();
+    let ramp = map.ramps.iter().find(|ramp| ramp.is_main).unwrap().clone();
+    let (top, direction) = (ramp.top_center, ramp.direction);
+    let inside = (top.0 + direction.0 * 6.0, top.1 + direction.1 * 6.0);
+    let outside = (ramp.bottom_center.0 - direction.0 * 4.0, ramp.bottom_center.1 - direction.1 * 4.0);
+    let buildings = vec![Footprint::Building2x2 as u8, Footprint::Building3x3 as u8, Footprint::Building2x2 as u8];
+
+    assert!(map.same_component(0, inside, outside).unwrap());
+    let wall = map.plan_wall_rust(top, inside, &buildings, 0).unwrap().unwrap();
+    assert!(!wall.is_empty());
+
+    for (footprint, center) in wall {
+        map.create_footprint(center, footprint).unwrap();
+    }
+    assert!(!map.same_component(0, inside, outside).unwrap());
+}
+
+#[test]
+fn test_find_path_large_automaton() {
+    let map = get_automaton_map();
+    let (path, distance) = map.find_path(0, PathQuery::new((40, 60), (140, 110))).unwrap();
+    let (large_path, large_distance) =
+        map.find_path(0, PathQuery { large: true, ..PathQuery::new((40, 60), (140, 110)) }).unwrap();
+
+    assert!(!path.is_empty() && !large_path.is_empty());
+    assert_eq!(large_path.first(), Some(&(40, 60)));
+    assert_eq!(large_path.last(), Some(&(140, 110)));
+    assert!(large_distance >= distance);
 }
@@ -1,15 +1,27 @@
-use common::get_pathfind;
+use common::{get_pathfind, get_split_pathfind, read_vec_from_file};
+use pathfinding::prelude::dijkstra_partial;
+use sc2pathlib::error::PathlibError;
+use sc2pathlib::helpers::grid::Grid;
 use sc2pathlib::helpers::point2_f32;
 use sc2pathlib::helpers::round_point2;
+use sc2pathlib::path_find::batch::PathQuery;
+use sc2pathlib::path_find::cooperative::Agent;
+use sc2pathlib::path_find::influence_cap::InfluenceCap;
+use sc2pathlib::path_find::line_of_sight;
+use sc2pathlib::path_find::euclidean_distance;
 use sc2pathlib::path_find::octile_distance;
+use sc2pathlib::path_find::search::SearchBudget;
+use sc2pathlib::path_find::PathStatus;
 use sc2pathlib::path_find::octile_distance_f32;
+use sc2pathlib::path_find::pos::{NormalPosAPI, Pos, PositionAPI, MULTF32};
+use std::time::{Duration, Instant};
 
 mod common;
 
 #[test]
 fn test_find_path_automaton_le() {
     let path_find = get_pathfind("tests/AutomatonLE.txt");
-    let r = path_find.find_path_basic((32, 51), (150, 118), Some(0));
+    let r = path_find.find_path_basic((32, 51), (150, 118), Some(0)).unwrap();
     let (_, distance) = r;
     assert_eq!(distance, 175.3076);
 }
@@ -17,7 +29,7 @@ fn test_find_path_automaton_le() {
 #[test]
 fn test_find_path_4x4() {
     let path_find = get_pathfind("tests/maze4x4.txt");
-    let r = path_find.find_path_basic((0, 0), (3, 3), Some(0));
+    let r = path_find.find_path_basic((0, 0), (3, 3), Some(0)).unwrap();
     let (_, distance) = r;
     assert_eq!(distance, 6.0);
 }
@@ -25,7 +37,7 @@ fn test_find_path_4x4() {
 #[test]
 fn test_find_path_10x10() {
     let path_find = get_pathfind("tests/empty10x10.txt");
-    let r = path_find.find_path_basic((0, 0), (8, 9), Some(0));
+    let r = path_find.find_path_basic((0, 0), (8, 9), Some(0)).unwrap();
     let (_, distance) = r;
     assert_eq!(distance, 12.3136);
 }
@@ -38,12 +50,12 @@ fn test_find_low_inside1() {
     let enemy_pos = (4usize, 0usize);
     let start_pos = (5f32, 0f32);
     let all_pos: Vec<(usize, usize)> = vec![enemy_pos];
-    path_find.add_walk_influence(all_pos, 100f32, 7f32);
+    path_find.add_walk_influence(all_pos, 100f32, 7f32).unwrap();
     // Act
-    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32);
+    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32).unwrap();
     // Assert
     let pos = round_point2(r.0);
-    let influence = path_find.map[pos.0][pos.1];
+    let influence = path_find.map[pos];
     let distance = octile_distance_f32(enemy_pos, pos);
 
     assert!(distance <= 8f32);
@@ -58,12 +70,12 @@ fn test_find_low_inside2() {
     let enemy_pos = (4usize, 0usize);
     let start_pos = (5f32, 0f32);
     let all_pos: Vec<(usize, usize)> = vec![enemy_pos];
-    path_find.add_walk_influence(all_pos, 100f32, 7f32);
+    path_find.add_walk_influence(all_pos, 100f32, 7f32).unwrap();
     // Act
-    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 6f32);
+    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 6f32).unwrap();
     // Assert
     let pos = round_point2(r.0);
-    let influence = path_find.map[pos.0][pos.1];
+    let influence = path_find.map[pos];
     let distance = octile_distance_f32(enemy_pos, pos);
     assert!(distance <= 6f32);
     assert_eq!(influence, 15);
@@ -77,12 +89,12 @@ fn test_find_low_inside3() {
     let enemy_pos = (4usize, 0usize);
     let start_pos = (8f32, 4f32);
     let all_pos: Vec<(usize, usize)> = vec![enemy_pos];
-    path_find.add_walk_influence(all_pos, 100f32, 7f32);
+    path_find.add_walk_influence(all_pos, 100f32, 7f32).unwrap();
     // Act
-    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32);
+    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32).unwrap();
     // Assert
     let pos = round_point2(r.0);
-    let influence = path_find.map[pos.0][pos.1];
+    let influence = path_find.map[pos];
     let distance = octile_distance_f32(enemy_pos, pos);
 
     assert!(distance <= 8f32);
@@ -97,12 +109,12 @@ fn test_find_low_inside_far1() {
     let enemy_pos = (4usize, 0usize);
     let start_pos = (9f32, 9f32);
     let all_pos: Vec<(usize, usize)> = vec![enemy_pos];
-    path_find.add_walk_influence(all_pos, 100f32, 7f32);
+    path_find.add_walk_influence(all_pos, 100f32, 7f32).unwrap();
     // Act
-    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 6f32);
+    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 6f32).unwrap();
     // Assert
     let pos = round_point2(r.0);
-    let influence = path_find.map[pos.0][pos.1];
+    let influence = path_find.map[pos];
     let distance = octile_distance_f32(enemy_pos, pos);
     assert!(distance <= 6f32);
     assert_eq!(influence, 17);
@@ -116,13 +128,874 @@ fn test_find_low_inside_far2() {
     let enemy_pos = (4usize, 0usize);
     let start_pos = (9f32, 9f32);
     let all_pos: Vec<(usize, usize)> = vec![enemy_pos];
-    path_find.add_walk_influence(all_pos, 100f32, 7f32);
+    path_find.add_walk_influence(all_pos, 100f32, 7f32).unwrap();
     // Act
-    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32);
+    let r = path_find.find_low_inside_walk(start_pos, point2_f32(enemy_pos), 8f32).unwrap();
     // Assert
     let pos = round_point2(r.0);
-    let influence = path_find.map[pos.0][pos.1];
+    let influence = path_find.map[pos];
     let distance = octile_distance_f32(enemy_pos, pos);
     assert!(distance <= 8f32);
     assert_eq!(influence, 1);
 }
+
+#[test]
+fn test_jump_point_search_automaton_le() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let (astar_path, astar_distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new((32, 51), (150, 118)) }).unwrap();
+    let (jps_path, jps_distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(3), ..PathQuery::new((32, 51), (150, 118)) }).unwrap();
+
+    assert_eq!(jps_distance, astar_distance);
+    assert_eq!(jps_path.first(), astar_path.first());
+    assert_eq!(jps_path.last(), astar_path.last());
+
+    for pair in jps_path.windows(2) {
+        assert!(pair[0].0.abs_diff(pair[1].0) <= 1 && pair[0].1.abs_diff(pair[1].1) <= 1);
+        assert!(path_find.map[pair[1]] > 0);
+    }
+}
+
+#[test]
+fn test_jump_point_search_large_10x10() {
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(3),
+                            ..PathQuery::new((0, 0), (8, 9)) };
+    let (path, distance) = path_find.find_path(query).unwrap();
+
+    assert_eq!(distance, 12.3136);
+    assert_eq!(path.len(), 10);
+}
+
+#[test]
+fn test_jump_point_search_large_automaton_le() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(1),
+                            ..PathQuery::new((32, 51), (150, 118)) };
+    let (astar_path, astar_distance) = path_find.find_path(query).unwrap();
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(3),
+                            ..PathQuery::new((32, 51), (150, 118)) };
+    let (jps_path, jps_distance) = path_find.find_path(query).unwrap();
+
+    assert!(!astar_path.is_empty());
+    assert_eq!(jps_distance, astar_distance);
+    assert_eq!(jps_path.last(), astar_path.last());
+
+    // Two 2x2 blocks that only touch side by side in one cell each, too narrow for a large unit
+    let grid = vec![vec![1, 1, 0], vec![1, 1, 0], vec![0, 1, 1], vec![0, 1, 1]];
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(1),
+                            ..PathQuery::new((0, 0), (3, 2)) };
+    let (astar_path, astar_distance) = path_find.find_path(query).unwrap();
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(3),
+                            ..PathQuery::new((0, 0), (3, 2)) };
+    let (jps_path, jps_distance) = path_find.find_path(query).unwrap();
+
+    assert!(astar_path.is_empty());
+    assert_eq!(astar_path, jps_path);
+    assert_eq!(astar_distance, jps_distance);
+}
+
+/// Whether a fully walkable 2x2 block contains both cells.
+fn walkable_block(grid: &[Vec<usize>], first: (usize, usize), second: (usize, usize)) -> bool {
+    let (x_min, x_max) = (first.0.min(second.0), first.0.max(second.0));
+    let (y_min, y_max) = (first.1.min(second.1), first.1.max(second.1));
+
+    (x_max.saturating_sub(1)..=x_min).any(|x| {
+        (y_max.saturating_sub(1)..=y_min).any(|y| {
+            x + 1 < grid.len()
+            && y + 1 < grid[0].len()
+            && grid[x][y] > 0
+            && grid[x + 1][y] > 0
+            && grid[x][y + 1] > 0
+            && grid[x + 1][y + 1] > 0
+        })
+    })
+}
+
+#[test]
+fn test_jump_point_search_large_matches_astar_on_random_grids() {
+    let mut seed: u64 = 12345;
+    let mut random = move |limit: usize| {
+        seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        (seed >> 33) as usize % limit
+    };
+
+    for _ in 0..40 {
+        let mut grid = vec![vec![1; 24]; 24];
+        for _ in 0..150 {
+            grid[random(24)][random(24)] = 0;
+        }
+
+        let mut ends = Vec::new();
+        while ends.len() < 6 {
+            let end = (random(24), random(24));
+            if walkable_block(&grid, end, end) {
+                ends.push(end);
+            }
+        }
+
+        let path_find = sc2pathlib::path_find::PathFind::new_internal(grid.clone());
+        for pair in ends.windows(2) {
+            let astar = PathQuery { large: true,
+                                    possible_heuristic: Some(1),
+                                    ..PathQuery::new(pair[0], pair[1]) };
+            let jps = PathQuery { possible_heuristic: Some(3),
+                                  ..astar.clone() };
+            let (_, astar_distance) = path_find.find_path(astar).unwrap();
+            let (jps_path, jps_distance) = path_find.find_path(jps).unwrap();
+
+            assert!((astar_distance - jps_distance).abs() < 0.01, "{:?}", pair);
+            // Every step stays in a walkable 2x2 block, as in A*
+            for step in jps_path.windows(2) {
+                assert!(walkable_block(&grid, step[0], step[1]), "{:?}", step);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_jump_point_search_matches_astar_after_blocks() {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    let distances = |path_find: &sc2pathlib::path_find::PathFind, large: bool| {
+        [1, 3].map(|heuristic| {
+                  let query = PathQuery { large,
+                                          possible_heuristic: Some(heuristic),
+                                          ..PathQuery::new((32, 51), (150, 118)) };
+                  path_find.find_path(query).unwrap().1
+              })
+    };
+
+    // Block the path in two places, then clear the blocks again
+    let (path, _) = path_find.find_path(PathQuery::new((32, 51), (150, 118))).unwrap();
+    let blocks = vec![point2_f32(path[path.len() / 3]), point2_f32(path[path.len() * 2 / 3])];
+
+    for blocks in [blocks, vec![]] {
+        path_find.reset_void();
+        path_find.create_blocks_rust(&blocks, (6, 6));
+        for large in [false, true] {
+            let [astar_distance, jps_distance] = distances(&path_find, large);
+            assert_eq!(jps_distance, astar_distance, "{:?} {}", blocks, large);
+        }
+    }
+}
+
+#[test]
+fn test_find_path_large_steps_sideways_only_inside_a_block() {
+    // Every neighbour is open, which used to push more than eight successors for a large unit
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(1),
+                            ..PathQuery::new((0, 0), (8, 9)) };
+    let (path, distance) = path_find.find_path(query).unwrap();
+
+    assert_eq!(distance, 12.3136);
+    assert_eq!(path.len(), 10);
+
+    // The cell at (2, 0) is open, but no fully walkable 2x2 block contains it
+    let grid = vec![vec![1, 1], vec![1, 1], vec![1, 0]];
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(1),
+                            ..PathQuery::new((0, 0), (2, 0)) };
+    let (path, _) = path_find.find_path(query).unwrap();
+
+    assert!(!path.contains(&(2, 0)));
+}
+
+#[test]
+fn test_jump_point_search_distance_from_target() {
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let query = PathQuery { possible_heuristic: Some(3),
+                            possible_distance_from_target: Some(3f32),
+                            ..PathQuery::new((0, 0), (9, 9)) };
+    let (path, _) = path_find.find_path(query).unwrap();
+    let last = *path.last().unwrap();
+
+    assert!(octile_distance_f32(last, (9, 9)) < 3f32);
+}
+
+#[test]
+fn test_find_path_smooth_10x10() {
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let query = PathQuery { possible_heuristic: Some(1),
+                            ..PathQuery::new((0, 0), (8, 9)) };
+    let (waypoints, distance) = path_find.find_path_smooth(query).unwrap();
+
+    assert_eq!(waypoints, vec![(0f32, 0f32), (8f32, 9f32)]);
+    assert!((distance - 145f32.sqrt()).abs() < 0.001);
+}
+
+#[test]
+fn test_find_path_smooth_automaton_le() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let (path, path_distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new((32, 51), (150, 118)) }).unwrap();
+    let (waypoints, distance) =
+        path_find.find_path_smooth(PathQuery { possible_heuristic: Some(1), ..PathQuery::new((32, 51), (150, 118)) })
+                 .unwrap();
+
+    assert!(waypoints.len() < path.len());
+    assert!(distance <= path_distance);
+    assert_eq!(waypoints.first(), Some(&(32f32, 51f32)));
+    assert_eq!(waypoints.last(), Some(&(150f32, 118f32)));
+}
+
+#[test]
+fn test_find_path_smooth_keeps_radius() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let query = PathQuery { possible_heuristic: Some(1),
+                            possible_radius: Some(0.9),
+                            ..PathQuery::new((32, 51), (150, 118)) };
+    let (waypoints, _) = path_find.find_path_smooth(query).unwrap();
+
+    assert!(waypoints.len() > 2);
+    let blocked = |x: usize, y: usize| path_find.clearance((x, y)).unwrap() < 1.4;
+
+    // Steps between neighbouring cells are moves of the original path, shortcuts need clearance on every cell
+    for pair in waypoints.windows(2).filter(|pair| euclidean_distance(pair[0], pair[1]) > 1.5) {
+        let (clear, _, _) = line_of_sight::raycast(round_point2(pair[0]), round_point2(pair[1]), false, blocked);
+        assert!(clear);
+    }
+}
+
+#[test]
+fn test_distance_field_automaton_le() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let field = path_find.distance_field(vec![(150, 118)], false, false, None, None).unwrap();
+    let (_, distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new((32, 51), (150, 118)) }).unwrap();
+
+    assert_eq!(field.distance((32f32, 51f32)), Some(distance));
+    assert_eq!(field.direction((150f32, 118f32)), Some((0f32, 0f32)));
+
+    let path = field.path((32f32, 51f32), 1000);
+    assert_eq!(path.last(), Some(&(150, 118)));
+
+    let next = field.next_step((32f32, 51f32)).unwrap();
+    assert!(octile_distance((32, 51), next) <= 14142);
+}
+
+#[test]
+fn test_distance_field_multiple_goals() {
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let field = path_find.distance_field(vec![(0, 0), (9, 9)], false, false, None, None).unwrap();
+
+    assert_eq!(field.distance((1f32, 0f32)), Some(1f32));
+    assert_eq!(field.distance((9f32, 7f32)), Some(2f32));
+    assert_eq!(field.next_step((9f32, 7f32)), Some((9, 8)));
+    assert_eq!(field.distance((20f32, 20f32)), None);
+    assert_eq!(field.direction((f32::NAN, 0f32)), None);
+    assert_eq!(field.direction((0f32, f32::INFINITY)), None);
+}
+
+#[test]
+fn test_find_paths_batch_keeps_order() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let ends = [(150, 118), (40, 60), (100, 100), (32, 51), (140, 40), (60, 140)];
+    let queries: Vec<PathQuery> = ends.iter()
+                                      .map(|end| {
+                                          let mut query = PathQuery::new((32, 51), *end);
+                                          query.possible_heuristic = Some(1);
+                                          query
+                                      })
+                                      .collect();
+
+    let results = path_find.find_paths_batch_rust(&queries).unwrap();
+
+    assert_eq!(results.len(), queries.len());
+    for (query, result) in queries.iter().zip(results) {
+        assert_eq!(result, path_find.find_path(query.clone()).unwrap());
+    }
+}
+
+#[test]
+fn test_grid_matches_list_layout() {
+    let columns = read_vec_from_file("tests/AutomatonLE.txt");
+    let grid = Grid::from(columns.clone());
+
+    assert_eq!(grid.width(), columns.len());
+    assert_eq!(grid.height(), columns[0].len());
+    assert_eq!(grid[(32, 51)], columns[32][51]);
+    assert_eq!(grid[(150, 118)], columns[150][118]);
+    assert_eq!(grid.to_vec(), columns);
+}
+
+#[test]
+fn test_destinations_match_dijkstra_partial() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let api = NormalPosAPI();
+
+    for (start, distance) in [((32, 51), 10f32), ((100, 100), 7.5f32), ((150, 118), 20f32)] {
+        let start_pos = Pos(start.0, start.1);
+        let u_distance = (distance * MULTF32) as usize;
+        let (expected, _) = dijkstra_partial(&start_pos,
+                                             |p| api.successors(p, &path_find.map),
+                                             |p| api.octile_distance(p, &start_pos) > u_distance);
+        let mut expected: Vec<((usize, usize), f32)> =
+            expected.into_iter().map(|(p, (_, c))| ((p.0, p.1), c as f32 / MULTF32)).collect();
+        expected.sort_by_key(|d| d.0);
+
+        // Twice to make sure the reused buffers do not leak state between searches
+        for _ in 0..2 {
+            let mut result = path_find.find_destinations_in_inline(start, distance);
+            result.sort_by_key(|d| d.0);
+            assert_eq!(result, expected);
+        }
+    }
+}
+
+#[test]
+fn test_planner_repairs_path_after_blocks() {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    let start = (32, 51);
+    let end = (150, 118);
+    let mut planner = path_find.create_planner(start, end, false, false).unwrap();
+    let (path, distance) = planner.current_path();
+    let (_, expected) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+
+    assert_eq!(path.first(), Some(&start));
+    assert_eq!(path.last(), Some(&end));
+    assert!((distance - expected).abs() < 0.01);
+
+    let blocked = path[path.len() / 2];
+    let changed: Vec<(usize, usize)> = (blocked.0 - 4..blocked.0 + 5).flat_map(|x| {
+                                                                          (blocked.1 - 4..blocked.1 + 5).map(move |y| (x, y))
+                                                                      })
+                                                                      .collect();
+    path_find.create_block(point2_f32(blocked), (6, 6));
+    path_find.update_planner_rust(&mut planner, &changed);
+
+    let (blocked_path, blocked_distance) = planner.current_path();
+    let (_, expected) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+
+    assert!((blocked_distance - expected).abs() < 0.01);
+    assert!(blocked_distance >= distance);
+    for pos in &blocked_path {
+        assert!(path_find.map[*pos] > 0);
+    }
+
+    path_find.remove_block(point2_f32(blocked), (6, 6));
+    path_find.update_planner_rust(&mut planner, &changed);
+
+    assert!((planner.current_path().1 - distance).abs() < 0.01);
+}
+
+#[test]
+fn test_large_planner_repairs_path_next_to_block() {
+    let grid = vec![vec![1; 10]; 10];
+    let mut path_find = sc2pathlib::path_find::PathFind::new_internal(grid.clone());
+    let (start, end) = ((1, 1), (8, 8));
+    let mut planner = path_find.create_planner(start, end, true, false).unwrap();
+    assert!(planner.current_path().0.contains(&(4, 4)));
+
+    // Next to the diagonal path, large units can't pass between (4, 4) and (5, 5) anymore
+    let mut grid = grid;
+    grid[5][4] = 0;
+    path_find.map[(5, 4)] = 0;
+    path_find.update_planner_rust(&mut planner, &[(5, 4)]);
+
+    let (path, distance) = planner.current_path();
+    let query = PathQuery { large: true,
+                            possible_heuristic: Some(1),
+                            ..PathQuery::new(start, end) };
+    let (_, expected) = path_find.find_path(query).unwrap();
+
+    assert_eq!(path.last(), Some(&end));
+    assert!((distance - expected).abs() < 0.01);
+    for step in path.windows(2) {
+        assert!(walkable_block(&grid, step[0], step[1]), "{:?}", step);
+    }
+
+    // Planner buffers only fit the grid it was created for
+    assert!(path_find.check_planner(&planner).is_ok());
+    let other = get_pathfind("tests/maze4x4.txt");
+    assert!(matches!(other.check_planner(&planner), Err(PathlibError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_clearance_updates_locally_and_limits_radius_paths() {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    let (width, height) = (path_find.width, path_find.height);
+    let start = (32, 51);
+    let end = (150, 118);
+
+    path_find.create_block((90.0, 90.0), (6, 6));
+    let fresh = sc2pathlib::path_find::PathFind::new_internal(path_find.map.clone());
+    for x in 0..width {
+        for y in 0..height {
+            assert_eq!(path_find.clearance((x, y)).unwrap(), fresh.clearance((x, y)).unwrap());
+        }
+    }
+
+    path_find.remove_block((90.0, 90.0), (6, 6));
+    let fresh = sc2pathlib::path_find::PathFind::new_internal(path_find.map.clone());
+    for x in 0..width {
+        for y in 0..height {
+            assert_eq!(path_find.clearance((x, y)).unwrap(), fresh.clearance((x, y)).unwrap());
+        }
+    }
+
+    let (_, distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+    let query = PathQuery { possible_heuristic: Some(1),
+                            possible_radius: Some(0.9),
+                            ..PathQuery::new(start, end) };
+    let (path, radius_distance) = path_find.find_path(query).unwrap();
+
+    assert!(!path.is_empty());
+    assert!(radius_distance > distance);
+    for pos in path.iter().skip(1) {
+        assert!(path_find.clearance(*pos).unwrap() >= 1.4);
+    }
+
+    let query = PathQuery { possible_heuristic: Some(3),
+                            possible_radius: Some(0.9),
+                            ..PathQuery::new(start, end) };
+    let (jps_path, _) = path_find.find_path(query).unwrap();
+    for pos in jps_path.iter().skip(1) {
+        assert!(path_find.clearance(*pos).unwrap() >= 1.4);
+    }
+}
+
+#[test]
+fn test_invalid_positions_return_errors() {
+    let mut path_find = get_pathfind("tests/empty10x10.txt");
+    let out_of_bounds = PathlibError::OutOfBounds { position: (10, 3),
+                                                    width: 10,
+                                                    height: 10 };
+
+    assert_eq!(path_find.find_path_basic((0, 0), (10, 3), Some(0)).unwrap_err(), out_of_bounds);
+    assert_eq!(path_find.current_influence((10, 3)).unwrap_err(), out_of_bounds);
+    assert_eq!(path_find.add_influence_spot((10, 3), 5).unwrap_err(), out_of_bounds);
+    assert_eq!(path_find.find_destinations_in((10, 3), 5.0, None, None).unwrap_err(), out_of_bounds);
+    assert_eq!(path_find.lowest_influence((10.5, 3.5), 3).unwrap_err(), out_of_bounds);
+
+    let window = Some(((0, 0), (11, 5)));
+    let query = PathQuery { possible_heuristic: Some(0),
+                            possible_window: window,
+                            ..PathQuery::new((0, 0), (3, 3)) };
+    let result = path_find.find_path(query);
+    assert!(matches!(result, Err(PathlibError::InvalidWindow { .. })));
+
+    let queries = vec![PathQuery::new((0, 0), (3, 3)), PathQuery::new((0, 0), (10, 3))];
+    assert_eq!(path_find.find_paths_batch_rust(&queries).unwrap_err(), out_of_bounds);
+}
+
+#[test]
+fn test_influence_cap_avoids_danger_zone() {
+    let mut path_find = get_pathfind("tests/AutomatonLE.txt");
+    path_find.normalize_influence(1);
+    let (start, end) = ((32, 51), (150, 118));
+    let (path, distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+    let danger = path[path.len() / 2];
+    path_find.add_influence(vec![danger], 100f32, 6f32);
+    assert!(path.iter().any(|p| path_find.map[*p] > 1));
+
+    let hard_cap = Some(InfluenceCap::new(1f32, None));
+    let query = PathQuery { influence: true,
+                            possible_heuristic: Some(1),
+                            possible_influence_cap: hard_cap,
+                            ..PathQuery::new(start, end) };
+    let (capped_path, capped_distance) = path_find.find_path(query).unwrap();
+    assert!(!capped_path.is_empty());
+    assert!(capped_distance >= distance);
+    assert!(capped_path.iter().all(|p| path_find.map[*p] <= 1));
+
+    let query = PathQuery { possible_heuristic: Some(3),
+                            possible_influence_cap: hard_cap,
+                            ..PathQuery::new(start, end) };
+    let (jps_path, _) = path_find.find_path(query).unwrap();
+    assert!(!jps_path.is_empty());
+    assert!(jps_path.iter().all(|p| path_find.map[*p] <= 1));
+
+    let soft_cap = Some(InfluenceCap::new(1f32, Some(1000f32)));
+    let query = PathQuery { possible_heuristic: Some(1),
+                            possible_influence_cap: soft_cap,
+                            ..PathQuery::new(start, end) };
+    let (soft_path, _) = path_find.find_path(query).unwrap();
+    assert!(soft_path.iter().all(|p| path_find.map[*p] <= 1));
+
+    let destinations = path_find.find_destinations_in(danger, 10f32, None, hard_cap).unwrap();
+    assert!(destinations.iter().skip(1).all(|(p, _)| path_find.map[*p] <= 1));
+
+    let field = path_find.distance_field(vec![end], false, false, None, hard_cap).unwrap();
+    assert_eq!(field.distance(point2_f32(danger)), None);
+    assert!(field.distance(point2_f32(start)).is_some());
+}
+
+#[test]
+fn test_find_path_budget_statuses() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let (start, end) = ((32, 51), (150, 118));
+    let (_, distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+
+    let unlimited = SearchBudget::new(None, None);
+    let (path, budget_distance, status) = path_find.find_path_budget(start, end, false, false, unlimited).unwrap();
+    assert_eq!(status, PathStatus::Complete as u8);
+    assert_eq!(path.last(), Some(&end));
+    assert!((budget_distance - distance).abs() < 0.01);
+
+    let limited = SearchBudget::new(Some(200), None);
+    let (path, _, status) = path_find.find_path_budget(start, end, false, false, limited).unwrap();
+    assert_eq!(status, PathStatus::Partial as u8);
+    assert_eq!(path.first(), Some(&start));
+    assert!(octile_distance(*path.last().unwrap(), end) < octile_distance(start, end));
+
+    let walled = get_split_pathfind();
+    let (path, _, status) = walled.find_path_budget((1, 1), (8, 8), false, false, unlimited).unwrap();
+    assert_eq!(status, PathStatus::Unreachable as u8);
+    assert_eq!(path.last(), Some(&(4, 8)));
+}
+
+#[test]
+fn test_find_alternative_paths_differ() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let (start, end) = ((32, 51), (150, 118));
+    let (_, distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, end) }).unwrap();
+
+    let paths = path_find.find_alternative_paths(start, end, 3, 2.0, false).unwrap();
+    assert_eq!(paths.len(), 3);
+
+    let (best, best_length, best_overlap) = &paths[0];
+    assert!((best_length - distance).abs() < 0.01);
+    assert_eq!(*best_overlap, 1.0);
+
+    for (path, length, overlap) in paths.iter().skip(1) {
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&end));
+        assert_ne!(path, best);
+        assert!(*length >= *best_length);
+        assert!(*overlap < 0.9);
+    }
+
+    for penalty in [-1.0, f32::NAN, f32::INFINITY] {
+        let result = path_find.find_alternative_paths(start, end, 3, penalty, false);
+        assert!(matches!(result, Err(PathlibError::InvalidArgument(_))), "{}", penalty);
+    }
+}
+
+#[test]
+fn test_evaluate_path_danger() {
+    let mut path_find = get_pathfind("tests/empty10x10.txt");
+    path_find.normalize_influence(10);
+    path_find.add_influence_spot((3, 0), 5).unwrap();
+    path_find.add_influence_spot((5, 0), 20).unwrap();
+    path_find.add_influence_spot((6, 0), 2).unwrap();
+
+    let path: Vec<(usize, usize)> = (0..8).map(|x| (x, 0)).collect();
+    // Threshold is compared with the influence above normal, 15 and 30 are above it but 12 is not
+    let stats = path_find.evaluate_path(path, 4.0).unwrap();
+
+    assert_eq!(stats.total_influence, 27);
+    assert_eq!(stats.max_influence, 20);
+    assert_eq!(stats.max_influence_index, Some(5));
+    assert_eq!(stats.cells_above_threshold, 2);
+    assert_eq!(stats.first_danger_index, Some(3));
+    assert_eq!(stats.seen_cells, None);
+
+    let out_of_bounds = path_find.evaluate_path(vec![(0, 0), (10, 0)], 12.0);
+    assert!(matches!(out_of_bounds, Err(PathlibError::OutOfBounds { .. })));
+}
+
+#[test]
+fn test_find_nearest_goal_single_search() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let start = (32, 51);
+    let goals = vec![(150, 118), (40, 60), (100, 100)];
+    let (_, near_distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, goals[1]) }).unwrap();
+    let (_, mid_distance) =
+        path_find.find_path(PathQuery { possible_heuristic: Some(1), ..PathQuery::new(start, goals[2]) }).unwrap();
+
+    let nearest = path_find.find_nearest_goal(start, goals.clone(), None, false, false).unwrap();
+    let (index, path, distance) = nearest.unwrap();
+    assert_eq!(index, 1);
+    assert_eq!(path.first(), Some(&start));
+    assert_eq!(path.last(), Some(&goals[1]));
+    assert!((distance - near_distance).abs() < 0.01);
+
+    let weights = Some(vec![0.0, 500.0, 0.0]);
+    let nearest = path_find.find_nearest_goal(start, goals.clone(), weights, false, false).unwrap();
+    let (index, _, distance) = nearest.unwrap();
+    assert_eq!(index, 2);
+    assert!((distance - mid_distance).abs() < 0.01);
+
+    let result = path_find.find_nearest_goal(start, goals, Some(vec![1.0]), false, false);
+    assert!(matches!(result, Err(PathlibError::InvalidArgument(_))));
+
+    let walled = get_split_pathfind();
+    assert_eq!(walled.find_nearest_goal((1, 1), vec![(8, 8), (9, 1)], None, false, false).unwrap(), None);
+}
+
+#[test]
+fn test_raycast_finds_first_blocking_cell() {
+    let path_find = get_split_pathfind();
+
+    let (clear, blocking, cells) = path_find.raycast((1, 1), (4, 8), true).unwrap();
+    assert!(clear);
+    assert_eq!(blocking, None);
+    assert_eq!(cells.first(), Some(&(1, 1)));
+    assert_eq!(cells.last(), Some(&(4, 8)));
+
+    let (clear, blocking, cells) = path_find.raycast((1, 1), (8, 1), true).unwrap();
+    assert!(!clear);
+    assert_eq!(blocking, Some((5, 1)));
+    assert_eq!(cells, vec![(1, 1), (2, 1), (3, 1), (4, 1), (5, 1)]);
+
+    let (clear, _, cells) = path_find.raycast((1, 1), (8, 8), false).unwrap();
+    assert!(!clear);
+    assert!(cells.is_empty());
+}
+
+#[test]
+fn test_flee_path_avoids_dead_end() {
+    // Corridor from the threat up to the unit continues as a short dead end,
+    // a long side corridor to the right is the only real way out
+    let mut grid = vec![vec![0; 16]; 40];
+    for y in 1..=12 {
+        grid[5][y] = 1;
+    }
+    for column in grid.iter_mut().skip(5).take(34) {
+        column[8] = 1;
+    }
+
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+    let (path, distance) = path_find.flee_path((5, 8), vec![(5, 1)], 10.0).unwrap();
+
+    assert_eq!(path.first(), Some(&(5, 8)));
+    assert!(path.iter().all(|p| p.1 == 8));
+    assert_eq!(path.last(), Some(&(15, 8)));
+    assert!((distance - 10.0).abs() < 0.01);
+
+    let (path, distance) = path_find.flee_path((5, 8), vec![(5, 1)], 100.0).unwrap();
+    assert_eq!(path.last(), Some(&(38, 8)));
+    assert!((distance - 33.0).abs() < 0.01);
+}
+
+#[test]
+fn test_components_follow_blocks() {
+    let mut path_find = sc2pathlib::path_find::PathFind::new_internal(vec![vec![1; 10]; 10]);
+    assert!(path_find.same_component((1, 1), (8, 8)).unwrap());
+
+    // Same wall as in the split map
+    path_find.create_block((5.0, 5.0), (1, 10));
+    assert!(!path_find.same_component((1, 1), (8, 8)).unwrap());
+    assert!(path_find.same_component((1, 1), (4, 9)).unwrap());
+    // Cells of the wall itself are not part of any component
+    assert_eq!(path_find.component_id((5, 5)).unwrap(), None);
+    assert!(!path_find.same_component((4, 5), (5, 5)).unwrap());
+    assert_ne!(path_find.component_id((1, 1)).unwrap(), None);
+
+    path_find.remove_block((5.0, 2.0), (1, 1));
+    assert!(path_find.same_component((1, 1), (8, 8)).unwrap());
+
+    let split = get_split_pathfind();
+    assert!(!split.same_component((1, 1), (8, 8)).unwrap());
+}
+
+#[test]
+fn test_auto_correct_out_of_large_block() {
+    let mut path_find = sc2pathlib::path_find::PathFind::new_internal(vec![vec![1; 20]; 20]);
+    // Closest pathable cells are 6 cells away from the middle of the block
+    path_find.create_block((10.0, 10.0), (11, 11));
+    assert_eq!(path_find.closest_pathable((10, 10), None).unwrap(), None);
+    path_find.set_auto_correct_distance(10.0).unwrap();
+
+    let corrected = path_find.closest_pathable((10, 10), None).unwrap().unwrap();
+    assert_eq!(path_find.map[corrected], 1);
+    assert!((euclidean_distance((10.0, 10.0), (corrected.0 as f32, corrected.1 as f32)) - 6.0).abs() < 0.01);
+    assert_eq!(path_find.closest_pathable((10, 10), Some(3.0)).unwrap(), None);
+    assert_eq!(path_find.closest_pathable((1, 1), Some(3.0)).unwrap(), Some((1, 1)));
+    // Huge distances stop at the edge of the map, broken ones are rejected
+    assert_eq!(path_find.closest_pathable((10, 10), Some(1e9)).unwrap(), Some(corrected));
+    assert!(matches!(path_find.closest_pathable((10, 10), Some(f32::NAN)), Err(PathlibError::InvalidArgument(_))));
+    assert!(matches!(path_find.set_auto_correct_distance(-1.0), Err(PathlibError::InvalidArgument(_))));
+
+    let query = PathQuery { possible_heuristic: Some(1),
+                            ..PathQuery::new((10, 10), (1, 1)) };
+    let (path, _, (start, end)) = path_find.find_path_corrected(query.clone()).unwrap();
+    assert_eq!(start, corrected);
+    assert_eq!(end, (1, 1));
+    assert_eq!(path.first(), Some(&corrected));
+
+    path_find.set_auto_correct_distance(3.0).unwrap();
+    let (_, _, (start, _)) = path_find.find_path_corrected(query).unwrap();
+    assert_eq!(start, (10, 10));
+
+    // Corner cell is pathable but too narrow for a large unit
+    let mut grid = vec![vec![1; 5]; 5];
+    grid[1][0] = 0;
+    grid[0][1] = 0;
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+    let (_, _, (start, _)) = path_find.find_path_corrected(PathQuery::new((0, 0), (4, 4))).unwrap();
+    assert_eq!(start, (0, 0));
+
+    let query = PathQuery { large: true,
+                            ..PathQuery::new((0, 0), (4, 4)) };
+    let (path, _, (start, end)) = path_find.find_path_corrected(query).unwrap();
+    assert_eq!(start, (1, 1));
+    assert_eq!(end, (4, 4));
+    assert_eq!(path.first(), Some(&start));
+}
+
+/// Times when the agent occupies the cell, moving between two cells occupies both.
+fn occupied_intervals(path: &[((usize, usize), f32)], cell: (usize, usize)) -> Vec<(f32, f32)> {
+    (0..path.len()).filter(|i| path[*i].0 == cell)
+                   .map(|i| {
+                       let from = path[i.saturating_sub(1)].1;
+                       let to = path.get(i + 1).map_or(f32::MAX, |next| next.1);
+                       (from, to)
+                   })
+                   .collect()
+}
+
+#[test]
+fn test_cooperative_paths_share_a_gap() {
+    // Wall with a single cell gap between the two sides
+    let mut grid = vec![vec![1; 7]; 11];
+    grid[5] = vec![0, 0, 0, 1, 0, 0, 0];
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+
+    let first = Agent::new((1, 3), (9, 3));
+    let mut second = Agent::new((9, 4), (1, 3));
+    second.speed = 2.0;
+    let paths = path_find.find_cooperative_paths(vec![first, second], None).unwrap();
+
+    assert_eq!(paths[0].first().map(|p| p.0), Some((1, 3)));
+    assert_eq!(paths[0].last().map(|p| p.0), Some((9, 3)));
+    assert_eq!(paths[1].first().map(|p| p.0), Some((9, 4)));
+    assert_eq!(paths[1].last().map(|p| p.0), Some((1, 3)));
+
+    for (cell, _) in &paths[0] {
+        for a in occupied_intervals(&paths[0], *cell) {
+            for b in occupied_intervals(&paths[1], *cell) {
+                assert!(a.1 < b.0 || b.1 < a.0, "{:?} is used at {:?} and {:?}", cell, a, b);
+            }
+        }
+    }
+
+    // Goal of the first agent is taken for good
+    let paths = path_find.find_cooperative_paths(vec![Agent::new((1, 3), (9, 3)), Agent::new((1, 1), (9, 3))], None)
+                         .unwrap();
+    assert!(!paths[0].is_empty());
+    assert!(paths[1].is_empty());
+}
+
+#[test]
+fn test_cooperative_paths_large_agent_in_two_cell_gap() {
+    // Wall with a two cell gap between the two sides
+    let mut grid = vec![vec![1; 7]; 11];
+    grid[5] = vec![0, 0, 0, 1, 1, 0, 0];
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+
+    let mut large = Agent::new((1, 3), (9, 3));
+    large.size = 2;
+    // Next to the cells where the large agent stays
+    let small = Agent::new((1, 1), (8, 2));
+    let paths = path_find.find_cooperative_paths(vec![large, small], None).unwrap();
+
+    assert_eq!(paths[0].last().map(|p| p.0), Some((9, 3)));
+    assert!(paths[0].iter().any(|(cell, _)| cell.0 == 5));
+    assert_eq!(paths[1].last().map(|p| p.0), Some((8, 2)));
+}
+
+#[test]
+fn test_cooperative_paths_agent_footprint_must_fit() {
+    // Wall with a two cell gap between the two sides
+    let mut grid = vec![vec![1; 7]; 11];
+    grid[5] = vec![0, 0, 0, 1, 1, 0, 0];
+    let path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+
+    let mut agent = Agent::new((1, 3), (9, 3));
+    agent.size = 3;
+    let paths = path_find.find_cooperative_paths(vec![agent.clone()], None).unwrap();
+    assert!(paths[0].is_empty());
+
+    // Part of the footprint is in the wall
+    agent.start = (4, 2);
+    let result = path_find.find_cooperative_paths(vec![agent], None);
+    assert!(matches!(result, Err(PathlibError::InvalidArgument(_))));
+}
+
+#[test]
+fn test_cooperative_paths_reject_empty_agents() {
+    let path_find = get_pathfind("tests/empty10x10.txt");
+    let mut agent = Agent::new((1, 1), (8, 8));
+    agent.size = 0;
+    let result = path_find.find_cooperative_paths(vec![Agent::new((1, 3), (8, 3)), agent], None);
+
+    assert!(matches!(result, Err(PathlibError::InvalidArgument(_))));
+
+    for speed in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+        let mut agent = Agent::new((1, 1), (8, 8));
+        agent.speed = speed;
+        let result = path_find.find_cooperative_paths(vec![agent], None);
+        assert!(matches!(result, Err(PathlibError::InvalidArgument(_))), "{}", speed);
+    }
+}
+
+#[test]
+fn test_cooperative_paths_automaton_le() {
+    let path_find = get_pathfind("tests/AutomatonLE.txt");
+    let mut large = Agent::new((42, 64), (136, 108));
+    large.size = 2;
+    let agents = vec![Agent::new((40, 60), (140, 110)),
+                      Agent::new((40, 62), (140, 110)),
+                      Agent::new((42, 60), (138, 110)),
+                      large];
+
+    let started = Instant::now();
+    let paths = path_find.find_cooperative_paths(agents, None).unwrap();
+    assert!(started.elapsed() < Duration::from_secs(5), "took {:?}", started.elapsed());
+
+    assert_eq!(paths[0].last().map(|p| p.0), Some((140, 110)));
+    // Goal is taken by the first agent
+    assert!(paths[1].is_empty());
+    assert_eq!(paths[2].last().map(|p| p.0), Some((138, 110)));
+    assert_eq!(paths[3].last().map(|p| p.0), Some((136, 108)));
+
+    // Runs out of budget before reaching the goal
+    let paths = path_find.find_cooperative_paths(vec![Agent::new((40, 60), (140, 110))],
+                                                 Some(SearchBudget::new(Some(100), None)))
+                         .unwrap();
+    assert!(paths[0].is_empty());
+}
+
+#[test]
+fn test_remove_block_by_id_restores_terrain() {
+    let mut grid = vec![vec![1; 10]; 10];
+    grid[3][3] = 0;
+    let mut path_find = sc2pathlib::path_find::PathFind::new_internal(grid);
+    path_find.add_influence_spot((4, 4), 5).unwrap();
+
+    // Covers cells 3..6, the second one 4..7
+    let first = path_find.create_block((4.0, 4.0), (3, 3));
+    let second = path_find.create_block((5.0, 5.0), (3, 3));
+    assert_ne!(first, second);
+
+    assert!(path_find.remove_block_by_id(first));
+    assert!(!path_find.remove_block_by_id(first));
+    assert_eq!(path_find.map[(3, 4)], 1);
+    assert_eq!(path_find.map[(3, 3)], 0);
+    assert_eq!(path_find.map[(4, 4)], 0);
+
+    assert!(path_find.remove_block_by_id(second));
+    assert_eq!(path_find.map[(4, 4)], 6);
+    assert_eq!(path_find.map[(6, 6)], 1);
+
+    // Removing by area doesn't open walls of the original map either
+    path_find.remove_block((3.0, 3.0), (1, 1));
+    assert_eq!(path_find.map[(3, 3)], 0);
+}